@@ -0,0 +1,63 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform texture2D inputImage;
+layout (set = 0, binding = 1) uniform sampler inputSampler;
+
+// params.x: maximum search span in texels
+// params.y: direction reduce multiplier
+// params.z: minimum direction reduce
+layout (push_constant) uniform PostParams {
+    vec4 params;
+    vec2 texelSize;
+} post;
+
+layout (location = 0) in vec2 fragTexCoord;
+
+layout (location = 0) out vec4 outColor;
+
+vec3 fetch(vec2 uv) {
+    return texture(sampler2D(inputImage, inputSampler), uv).rgb;
+}
+
+float luma(vec3 color) {
+    return dot(color, vec3(0.299, 0.587, 0.114));
+}
+
+void main() {
+
+    vec3 rgbM = fetch(fragTexCoord);
+    float lumaNW = luma(fetch(fragTexCoord + vec2(-1.0, -1.0) * post.texelSize));
+    float lumaNE = luma(fetch(fragTexCoord + vec2(1.0, -1.0) * post.texelSize));
+    float lumaSW = luma(fetch(fragTexCoord + vec2(-1.0, 1.0) * post.texelSize));
+    float lumaSE = luma(fetch(fragTexCoord + vec2(1.0, 1.0) * post.texelSize));
+    float lumaM = luma(rgbM);
+
+    float lumaMin = min(lumaM, min(min(lumaNW, lumaNE), min(lumaSW, lumaSE)));
+    float lumaMax = max(lumaM, max(max(lumaNW, lumaNE), max(lumaSW, lumaSE)));
+
+    vec2 dir = vec2(
+        -((lumaNW + lumaNE) - (lumaSW + lumaSE)),
+        (lumaNW + lumaSW) - (lumaNE + lumaSE));
+
+    float dirReduce = max(
+        (lumaNW + lumaNE + lumaSW + lumaSE) * 0.25 * post.params.y,
+        post.params.z);
+    float rcpDirMin = 1.0 / (min(abs(dir.x), abs(dir.y)) + dirReduce);
+    dir = clamp(dir * rcpDirMin, vec2(-post.params.x), vec2(post.params.x)) * post.texelSize;
+
+    vec3 rgbA = 0.5 * (
+        fetch(fragTexCoord + dir * (1.0 / 3.0 - 0.5)) +
+        fetch(fragTexCoord + dir * (2.0 / 3.0 - 0.5)));
+    vec3 rgbB = rgbA * 0.5 + 0.25 * (
+        fetch(fragTexCoord + dir * -0.5) +
+        fetch(fragTexCoord + dir * 0.5));
+
+    float lumaB = luma(rgbB);
+    if (lumaB < lumaMin || lumaB > lumaMax) {
+        outColor = vec4(rgbA, 1.0);
+    } else {
+        outColor = vec4(rgbB, 1.0);
+    }
+}
@@ -0,0 +1,22 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform texture2D inputImage;
+layout (set = 0, binding = 1) uniform sampler inputSampler;
+
+// params.x: gamma
+layout (push_constant) uniform PostParams {
+    vec4 params;
+    vec2 texelSize;
+} post;
+
+layout (location = 0) in vec2 fragTexCoord;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    vec3 color = texture(sampler2D(inputImage, inputSampler), fragTexCoord).rgb;
+    outColor = vec4(pow(color, vec3(1.0 / post.params.x)), 1.0);
+}
@@ -0,0 +1,32 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform texture2D inputImage;
+layout (set = 0, binding = 1) uniform sampler inputSampler;
+
+// params.x: exposure
+layout (push_constant) uniform PostParams {
+    vec4 params;
+    vec2 texelSize;
+} post;
+
+layout (location = 0) in vec2 fragTexCoord;
+
+layout (location = 0) out vec4 outColor;
+
+// ACES filmic curve fitted by Krzysztof Narkowicz.
+vec3 aces(vec3 x) {
+    const float a = 2.51;
+    const float b = 0.03;
+    const float c = 2.43;
+    const float d = 0.59;
+    const float e = 0.14;
+    return clamp((x * (a * x + b)) / (x * (c * x + d) + e), 0.0, 1.0);
+}
+
+void main() {
+
+    vec3 hdrColor = texture(sampler2D(inputImage, inputSampler), fragTexCoord).rgb;
+    outColor = vec4(aces(hdrColor * post.params.x), 1.0);
+}
@@ -0,0 +1,27 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform texture2D inputImage;
+layout (set = 0, binding = 1) uniform sampler inputSampler;
+
+// params.x: strength
+// params.y: radius
+// params.z: softness
+layout (push_constant) uniform PostParams {
+    vec4 params;
+    vec2 texelSize;
+} post;
+
+layout (location = 0) in vec2 fragTexCoord;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    vec3 color = texture(sampler2D(inputImage, inputSampler), fragTexCoord).rgb;
+
+    float distanceToCenter = distance(fragTexCoord, vec2(0.5));
+    float vignette = smoothstep(post.params.y, post.params.y - post.params.z, distanceToCenter);
+    outColor = vec4(color * mix(1.0, vignette, post.params.x), 1.0);
+}
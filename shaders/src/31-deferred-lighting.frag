@@ -0,0 +1,45 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+// the size of the light array, specialized by the app to match its uniform buffer.
+layout (constant_id = 0) const int MAX_LIGHTS = 32;
+
+struct PointLight {
+    vec4 positionRadius; // view space position, radius in w
+    vec4 color;
+};
+
+layout (input_attachment_index = 0, set = 0, binding = 0) uniform subpassInput inPosition;
+layout (input_attachment_index = 1, set = 0, binding = 1) uniform subpassInput inNormal;
+layout (input_attachment_index = 2, set = 0, binding = 2) uniform subpassInput inAlbedo;
+layout (set = 0, binding = 3) uniform LightsBufferObject {
+    vec4 params; // x: light count, y: ambient
+    PointLight lights[MAX_LIGHTS];
+} lightsUbo;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    vec4 position = subpassLoad(inPosition);
+    // nothing was drawn on this pixel.
+    if (position.w == 0.0) {
+        outColor = vec4(0.0, 0.0, 0.0, 1.0);
+        return;
+    }
+
+    vec3 normal = subpassLoad(inNormal).xyz;
+    vec3 albedo = subpassLoad(inAlbedo).rgb;
+
+    vec3 color = albedo * lightsUbo.params.y;
+    for (int i = 0; i < min(int(lightsUbo.params.x), MAX_LIGHTS); i++) {
+        vec3 toLight = lightsUbo.lights[i].positionRadius.xyz - position.xyz;
+        float distanceToLight = length(toLight);
+        float attenuation = clamp(1.0 - distanceToLight / lightsUbo.lights[i].positionRadius.w, 0.0, 1.0);
+        float diffuse = max(dot(normal, toLight / distanceToLight), 0.0);
+        color += albedo * lightsUbo.lights[i].color.rgb * diffuse * attenuation * attenuation;
+    }
+
+    outColor = vec4(color, 1.0);
+}
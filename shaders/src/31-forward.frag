@@ -0,0 +1,43 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+// the size of the light array, specialized by the app to match its uniform buffer.
+layout (constant_id = 0) const int MAX_LIGHTS = 32;
+
+struct PointLight {
+    vec4 positionRadius; // view space position, radius in w
+    vec4 color;
+};
+
+layout (set = 0, binding = 1) uniform texture2D albedoTexture;
+layout (set = 0, binding = 2) uniform sampler albedoSampler;
+layout (set = 0, binding = 3) uniform LightsBufferObject {
+    vec4 params; // x: light count, y: ambient
+    PointLight lights[MAX_LIGHTS];
+} lightsUbo;
+
+layout (location = 0) in vec3 fragViewPosition;
+layout (location = 1) in vec2 fragTexCoord;
+
+layout (location = 0) out vec4 outColor;
+
+void main() {
+
+    vec3 normal = normalize(cross(dFdx(fragViewPosition), dFdy(fragViewPosition)));
+    if (dot(normal, -fragViewPosition) < 0.0) {
+        normal = -normal;
+    }
+    vec3 albedo = texture(sampler2D(albedoTexture, albedoSampler), fragTexCoord).rgb;
+
+    vec3 color = albedo * lightsUbo.params.y;
+    for (int i = 0; i < min(int(lightsUbo.params.x), MAX_LIGHTS); i++) {
+        vec3 toLight = lightsUbo.lights[i].positionRadius.xyz - fragViewPosition;
+        float distanceToLight = length(toLight);
+        float attenuation = clamp(1.0 - distanceToLight / lightsUbo.lights[i].positionRadius.w, 0.0, 1.0);
+        float diffuse = max(dot(normal, toLight / distanceToLight), 0.0);
+        color += albedo * lightsUbo.lights[i].color.rgb * diffuse * attenuation * attenuation;
+    }
+
+    outColor = vec4(color, 1.0);
+}
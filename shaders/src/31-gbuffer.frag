@@ -0,0 +1,26 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 1) uniform texture2D albedoTexture;
+layout (set = 0, binding = 2) uniform sampler albedoSampler;
+
+layout (location = 0) in vec3 fragViewPosition;
+layout (location = 1) in vec2 fragTexCoord;
+
+layout (location = 0) out vec4 outPosition;
+layout (location = 1) out vec4 outNormal;
+layout (location = 2) out vec4 outAlbedo;
+
+void main() {
+
+    // the model has no normals, rebuild the face normal and turn it towards the camera.
+    vec3 normal = normalize(cross(dFdx(fragViewPosition), dFdy(fragViewPosition)));
+    if (dot(normal, -fragViewPosition) < 0.0) {
+        normal = -normal;
+    }
+
+    outPosition = vec4(fragViewPosition, 1.0);
+    outNormal = vec4(normal, 0.0);
+    outAlbedo = texture(sampler2D(albedoTexture, albedoSampler), fragTexCoord);
+}
@@ -0,0 +1,24 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform UniformBufferObject {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+} ubo;
+
+layout (location = 0) in vec3 inPosition;
+layout (location = 1) in vec3 inColor;
+layout (location = 2) in vec2 inTexCoord;
+
+layout (location = 0) out vec3 fragViewPosition;
+layout (location = 1) out vec2 fragTexCoord;
+
+void main() {
+
+    vec4 viewPosition = ubo.view * ubo.model * vec4(inPosition, 1.0);
+    gl_Position = ubo.proj * viewPosition;
+    fragViewPosition = viewPosition.xyz;
+    fragTexCoord = inTexCoord;
+}
@@ -0,0 +1,74 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform ShadowUniformBufferObject {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+    mat4 lightSpace;
+    vec4 cameraPosition;
+    vec4 lightPosition;  // xyz: world position of the spot light
+    vec4 lightDirection; // xyz: direction the light shines to, w: cos of the outer cone angle
+    vec4 params;         // x: ambient, y: unused, z: cos of the inner cone angle, w: 1.0 for spot lights
+} ubo;
+
+// picked when the pipeline is created, the filtering loop is compiled out without PCF.
+layout (constant_id = 0) const bool IS_PCF_ENABLED = true;
+
+layout (set = 0, binding = 1) uniform texture2D albedoTexture;
+layout (set = 0, binding = 2) uniform sampler albedoSampler;
+layout (set = 0, binding = 3) uniform texture2D shadowMap;
+layout (set = 0, binding = 4) uniform samplerShadow shadowSampler;
+
+layout (location = 0) in vec3 fragWorldPosition;
+layout (location = 1) in vec2 fragTexCoord;
+layout (location = 2) in vec4 fragLightPosition;
+
+layout (location = 0) out vec4 outColor;
+
+float shadowFactor(vec3 shadowCoord) {
+
+    if (shadowCoord.z > 1.0) {
+        // beyond the far plane of the light.
+        return 1.0;
+    }
+
+    if (!IS_PCF_ENABLED) {
+        return texture(sampler2DShadow(shadowMap, shadowSampler), shadowCoord);
+    }
+
+    // percentage closer filtering over a 3x3 texel neighbourhood.
+    vec2 texelSize = 1.0 / vec2(textureSize(sampler2DShadow(shadowMap, shadowSampler), 0));
+    float lit = 0.0;
+    for (int x = -1; x <= 1; x++) {
+        for (int y = -1; y <= 1; y++) {
+            vec2 offset = vec2(x, y) * texelSize;
+            lit += texture(sampler2DShadow(shadowMap, shadowSampler), vec3(shadowCoord.xy + offset, shadowCoord.z));
+        }
+    }
+    return lit / 9.0;
+}
+
+void main() {
+
+    vec3 normal = normalize(cross(dFdx(fragWorldPosition), dFdy(fragWorldPosition)));
+    if (dot(normal, ubo.cameraPosition.xyz - fragWorldPosition) < 0.0) {
+        normal = -normal;
+    }
+    vec3 albedo = texture(sampler2D(albedoTexture, albedoSampler), fragTexCoord).rgb;
+
+    vec3 toLight = -ubo.lightDirection.xyz;
+    float cone = 1.0;
+    if (ubo.params.w != 0.0) {
+        toLight = normalize(ubo.lightPosition.xyz - fragWorldPosition);
+        cone = smoothstep(ubo.lightDirection.w, ubo.params.z, dot(-toLight, ubo.lightDirection.xyz));
+    }
+    float diffuse = max(dot(normal, toLight), 0.0);
+
+    vec3 lightCoord = fragLightPosition.xyz / fragLightPosition.w;
+    float shadow = shadowFactor(vec3(lightCoord.xy * 0.5 + 0.5, lightCoord.z));
+
+    float ambient = ubo.params.x;
+    outColor = vec4(albedo * (ambient + (1.0 - ambient) * diffuse * cone * shadow), 1.0);
+}
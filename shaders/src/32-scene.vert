@@ -0,0 +1,31 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform ShadowUniformBufferObject {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+    mat4 lightSpace;
+    vec4 cameraPosition;
+    vec4 lightPosition;
+    vec4 lightDirection;
+    vec4 params;
+} ubo;
+
+layout (location = 0) in vec3 inPosition;
+layout (location = 1) in vec3 inColor;
+layout (location = 2) in vec2 inTexCoord;
+
+layout (location = 0) out vec3 fragWorldPosition;
+layout (location = 1) out vec2 fragTexCoord;
+layout (location = 2) out vec4 fragLightPosition;
+
+void main() {
+
+    vec4 worldPosition = ubo.model * vec4(inPosition, 1.0);
+    gl_Position = ubo.proj * ubo.view * worldPosition;
+    fragWorldPosition = worldPosition.xyz;
+    fragTexCoord = inTexCoord;
+    fragLightPosition = ubo.lightSpace * worldPosition;
+}
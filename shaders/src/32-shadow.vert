@@ -0,0 +1,22 @@
+#version 450
+
+#extension GL_ARB_separate_shader_objects : enable
+
+layout (set = 0, binding = 0) uniform ShadowUniformBufferObject {
+    mat4 model;
+    mat4 view;
+    mat4 proj;
+    mat4 lightSpace;
+    vec4 cameraPosition;
+    vec4 lightPosition;
+    vec4 lightDirection;
+    vec4 params;
+} ubo;
+
+layout (location = 0) in vec3 inPosition;
+
+// depth only, the shadow pass has no fragment shader.
+void main() {
+
+    gl_Position = ubo.lightSpace * ubo.model * vec4(inPosition, 1.0);
+}
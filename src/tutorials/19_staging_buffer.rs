@@ -124,8 +124,7 @@ impl VulkanApp {
             VALIDATION.is_enable,
             &VALIDATION.required_validation_layers.to_vec(),
         );
-        let surface_stuff =
-            share::create_surface(&entry, &instance, &window, WINDOW_WIDTH, WINDOW_HEIGHT);
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
         let (debug_utils_loader, debug_merssager) =
             setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
         let physical_device =
@@ -827,12 +826,19 @@ impl VulkanApp {
     }
 
     fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
         // parameters -------------
+        let window_size = self.window.inner_size();
         let surface_suff = SurfaceStuff {
             surface_loader: self.surface_loader.clone(),
             surface: self.surface,
-            screen_width: WINDOW_WIDTH,
-            screen_height: WINDOW_HEIGHT,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
         };
         // ------------------------
 
@@ -967,8 +973,14 @@ impl VulkanApp {
                         | _ => {},
                     }
                 },
-                | Event::MainEventsCleared => {
-                    self.window.request_redraw();
+                | Event::MainEventsCleared if *control_flow != ControlFlow::Exit => {
+                    if share::is_window_minimized(&self.window) {
+                        // nothing to draw to, sleep until the next event instead.
+                        *control_flow = ControlFlow::Wait;
+                    } else {
+                        *control_flow = ControlFlow::Poll;
+                        self.window.request_redraw();
+                    }
                 },
                 | Event::RedrawRequested(_window_id) => {
                     self.draw_frame();
@@ -2,6 +2,7 @@ use vulkan_tutorial_rust::{
     utility, // the mod define some fixed functions that have been learned before.
     utility::constants::*,
     utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
     utility::share,
     utility::structures::*,
     utility::window::{ProgramProc, VulkanApp},
@@ -64,6 +65,7 @@ struct VulkanApp21 {
 
     uniform_buffers: Vec<vk::Buffer>,
     uniform_buffers_memory: Vec<vk::DeviceMemory>,
+    model_angle: InterpolatedValue,
 
     command_pool: vk::CommandPool,
     command_buffers: Vec<vk::CommandBuffer>,
@@ -89,8 +91,7 @@ impl VulkanApp21 {
             VALIDATION.is_enable,
             &VALIDATION.required_validation_layers.to_vec(),
         );
-        let surface_stuff =
-            share::create_surface(&entry, &instance, &window, WINDOW_WIDTH, WINDOW_HEIGHT);
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
         let (debug_utils_loader, debug_merssager) =
             setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
         let physical_device =
@@ -207,6 +208,7 @@ impl VulkanApp21 {
 
             uniform_buffers,
             uniform_buffers_memory,
+            model_angle: InterpolatedValue::new(0.0),
 
             command_pool,
             command_buffers,
@@ -269,9 +271,9 @@ impl VulkanApp21 {
         (uniform_buffers, uniform_buffers_memory)
     }
 
-    fn update_uniform_buffer(&self, current_image: usize, delta_time: f32) {
+    fn update_uniform_buffer(&self, current_image: usize, alpha: f32) {
         let ubos = [UniformBufferObject {
-            model: Matrix4::from_angle_z(Deg(90.0 * delta_time)),
+            model: Matrix4::from_angle_z(Deg(self.model_angle.value(alpha))),
             view: Matrix4::look_at(
                 Point3::new(2.0, 2.0, 2.0),
                 Point3::new(0.0, 0.0, 0.0),
@@ -662,7 +664,11 @@ impl Drop for VulkanApp21 {
 }
 
 impl VulkanApp for VulkanApp21 {
-    fn draw_frame(&mut self, delta_time: f32) {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
         let wait_fences = [self.in_flight_fences[self.current_frame]];
 
         unsafe {
@@ -690,7 +696,7 @@ impl VulkanApp for VulkanApp21 {
             }
         };
 
-        self.update_uniform_buffer(image_index as usize, delta_time);
+        self.update_uniform_buffer(image_index as usize, alpha);
 
         let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
         let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
@@ -756,12 +762,19 @@ impl VulkanApp for VulkanApp21 {
     }
 
     fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
         // parameters -------------
+        let window_size = self.window.inner_size();
         let surface_suff = SurfaceStuff {
             surface_loader: self.surface_loader.clone(),
             surface: self.surface,
-            screen_width: WINDOW_WIDTH,
-            screen_height: WINDOW_HEIGHT,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
         };
         // ------------------------
 
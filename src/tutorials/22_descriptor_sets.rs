@@ -2,6 +2,7 @@ use vulkan_tutorial_rust::{
     utility, // the mod define some fixed functions that have been learned before.
     utility::constants::*,
     utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
     utility::share,
     utility::structures::*,
     utility::window::{ProgramProc, VulkanApp},
@@ -63,6 +64,7 @@ struct VulkanApp22 {
     index_buffer_memory: vk::DeviceMemory,
 
     uniform_transform: UniformBufferObject,
+    model_angle: InterpolatedValue,
     uniform_buffers: Vec<vk::Buffer>,
     uniform_buffers_memory: Vec<vk::DeviceMemory>,
 
@@ -93,8 +95,7 @@ impl VulkanApp22 {
             VALIDATION.is_enable,
             &VALIDATION.required_validation_layers.to_vec(),
         );
-        let surface_stuff =
-            share::create_surface(&entry, &instance, &window, WINDOW_WIDTH, WINDOW_HEIGHT);
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
         let (debug_utils_loader, debug_merssager) =
             setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
         let physical_device =
@@ -235,6 +236,7 @@ impl VulkanApp22 {
                     10.0,
                 ),
             },
+            model_angle: InterpolatedValue::new(0.0),
             uniform_buffers,
             uniform_buffers_memory,
 
@@ -488,10 +490,8 @@ impl VulkanApp22 {
         (uniform_buffers, uniform_buffers_memory)
     }
 
-    fn update_uniform_buffer(&mut self, current_image: usize, delta_time: f32) {
-        self.uniform_transform.model =
-            Matrix4::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), Deg(90.0) * delta_time)
-                * self.uniform_transform.model;
+    fn update_uniform_buffer(&mut self, current_image: usize, alpha: f32) {
+        self.uniform_transform.model = Matrix4::from_angle_z(Deg(self.model_angle.value(alpha)));
 
         let ubos = [self.uniform_transform.clone()];
 
@@ -779,7 +779,11 @@ impl Drop for VulkanApp22 {
 }
 
 impl VulkanApp for VulkanApp22 {
-    fn draw_frame(&mut self, delta_time: f32) {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
         let wait_fences = [self.in_flight_fences[self.current_frame]];
 
         unsafe {
@@ -807,7 +811,7 @@ impl VulkanApp for VulkanApp22 {
             }
         };
 
-        self.update_uniform_buffer(image_index as usize, delta_time);
+        self.update_uniform_buffer(image_index as usize, alpha);
 
         let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
         let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
@@ -873,12 +877,19 @@ impl VulkanApp for VulkanApp22 {
     }
 
     fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
         // parameters -------------
+        let window_size = self.window.inner_size();
         let surface_suff = SurfaceStuff {
             surface_loader: self.surface_loader.clone(),
             surface: self.surface,
-            screen_width: WINDOW_WIDTH,
-            screen_height: WINDOW_HEIGHT,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
         };
         // ------------------------
 
@@ -1,7 +1,12 @@
 use vulkan_tutorial_rust::{
     utility, // the mod define some fixed functions that have been learned before.
+    utility::camera::{self, Camera, FlyCamera, OrbitCamera, Projection},
     utility::constants::*,
     utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
+    utility::input::{ActionMap, Binding},
+    utility::render_state::{self, PipelineVariants, RasterizationMode},
+    utility::screenshot,
     utility::share,
     utility::structures::*,
     utility::window::{ProgramProc, VulkanApp},
@@ -10,17 +15,19 @@ use vulkan_tutorial_rust::{
 use ash::version::DeviceV1_0;
 use ash::version::InstanceV1_0;
 use ash::vk;
-use cgmath::{Deg, Matrix4, Point3, Vector3};
+use cgmath::{Deg, Matrix4, Point3};
 use memoffset::offset_of;
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode};
 
 use std::ffi::CString;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::ptr;
 
 // Constants
 const WINDOW_TITLE: &'static str = "27.Model Loading";
 const MODEL_PATH: &'static str = "assets/chalet.obj";
 const TEXTURE_PATH: &'static str = "assets/chalet.jpg";
+const TOGGLE_CAMERA: &str = "toggle_camera";
 
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
@@ -93,6 +100,9 @@ struct VulkanApp27 {
     ubo_layout: vk::DescriptorSetLayout,
     pipeline_layout: vk::PipelineLayout,
     graphics_pipeline: vk::Pipeline,
+    pipeline_variants: PipelineVariants,
+    rasterization_mode: RasterizationMode,
+    is_fill_mode_non_solid_supported: bool,
 
     depth_image: vk::Image,
     depth_image_view: vk::ImageView,
@@ -112,6 +122,10 @@ struct VulkanApp27 {
     index_buffer_memory: vk::DeviceMemory,
 
     uniform_transform: UniformBufferObject,
+    model_angle: InterpolatedValue,
+    orbit_camera: OrbitCamera,
+    fly_camera: FlyCamera,
+    is_flying: bool,
     uniform_buffers: Vec<vk::Buffer>,
     uniform_buffers_memory: Vec<vk::DeviceMemory>,
 
@@ -127,6 +141,8 @@ struct VulkanApp27 {
     current_frame: usize,
 
     is_framebuffer_resized: bool,
+    // where to save the next frame, if a screenshot was asked for.
+    screenshot_path: Option<PathBuf>,
 }
 
 impl VulkanApp27 {
@@ -142,8 +158,7 @@ impl VulkanApp27 {
             VALIDATION.is_enable,
             &VALIDATION.required_validation_layers.to_vec(),
         );
-        let surface_stuff =
-            share::create_surface(&entry, &instance, &window, WINDOW_WIDTH, WINDOW_HEIGHT);
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
         let (debug_utils_loader, debug_merssager) =
             setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
         let physical_device =
@@ -169,6 +184,12 @@ impl VulkanApp27 {
             &surface_stuff,
             &queue_family,
         );
+        let orbit_camera = OrbitCamera::new(
+            Point3::new(2.0, 2.0, 2.0),
+            Point3::new(0.0, 0.0, 0.0),
+            Projection::new(Deg(45.0), 0.1, 10.0, swapchain_stuff.swapchain_extent),
+        );
+        let fly_camera = FlyCamera::from(&orbit_camera);
         let swapchain_imageviews = share::v1::create_image_views(
             &device,
             swapchain_stuff.swapchain_format,
@@ -181,12 +202,20 @@ impl VulkanApp27 {
             swapchain_stuff.swapchain_format,
         );
         let ubo_layout = share::v2::create_descriptor_set_layout(&device);
-        let (graphics_pipeline, pipeline_layout) = VulkanApp27::create_graphics_pipeline(
-            &device,
-            render_pass,
-            swapchain_stuff.swapchain_extent,
-            ubo_layout,
-        );
+        let pipeline_layout = VulkanApp27::create_pipeline_layout(&device, ubo_layout);
+        let is_fill_mode_non_solid_supported =
+            render_state::is_fill_mode_non_solid_supported(&instance, physical_device);
+        let rasterization_mode = RasterizationMode::new(vk::FrontFace::COUNTER_CLOCKWISE);
+        let mut pipeline_variants = PipelineVariants::new();
+        let graphics_pipeline = pipeline_variants.get_or_create(rasterization_mode, |mode| {
+            VulkanApp27::create_graphics_pipeline(
+                &device,
+                render_pass,
+                swapchain_stuff.swapchain_extent,
+                pipeline_layout,
+                mode,
+            )
+        });
         let command_pool = share::v1::create_command_pool(&device, &queue_family);
         let (depth_image, depth_image_view, depth_image_memory) = share::v1::create_depth_resources(
             &instance,
@@ -293,6 +322,9 @@ impl VulkanApp27 {
             ubo_layout,
             render_pass,
             graphics_pipeline,
+            pipeline_variants,
+            rasterization_mode,
+            is_fill_mode_non_solid_supported,
 
             depth_image,
             depth_image_view,
@@ -313,23 +345,13 @@ impl VulkanApp27 {
 
             uniform_transform: UniformBufferObject {
                 model: Matrix4::from_angle_z(Deg(90.0)),
-                view: Matrix4::look_at(
-                    Point3::new(2.0, 2.0, 2.0),
-                    Point3::new(0.0, 0.0, 0.0),
-                    Vector3::new(0.0, 0.0, 1.0),
-                ),
-                proj: {
-                    let mut proj = cgmath::perspective(
-                        Deg(45.0),
-                        swapchain_stuff.swapchain_extent.width as f32
-                            / swapchain_stuff.swapchain_extent.height as f32,
-                        0.1,
-                        10.0,
-                    );
-                    proj[1][1] = proj[1][1] * -1.0;
-                    proj
-                },
+                view: orbit_camera.view(0.0),
+                proj: orbit_camera.projection().matrix(),
             },
+            model_angle: InterpolatedValue::new(90.0),
+            orbit_camera,
+            fly_camera,
+            is_flying: false,
             uniform_buffers,
             uniform_buffers_memory,
 
@@ -345,6 +367,7 @@ impl VulkanApp27 {
             current_frame: 0,
 
             is_framebuffer_resized: false,
+            screenshot_path: None,
         }
     }
 
@@ -507,12 +530,36 @@ impl VulkanApp27 {
         framebuffers
     }
 
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        ubo_set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [ubo_set_layout];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: 0,
+            p_push_constant_ranges: ptr::null(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
     fn create_graphics_pipeline(
         device: &ash::Device,
         render_pass: vk::RenderPass,
         swapchain_extent: vk::Extent2D,
-        ubo_set_layout: vk::DescriptorSetLayout,
-    ) -> (vk::Pipeline, vk::PipelineLayout) {
+        pipeline_layout: vk::PipelineLayout,
+        rasterization_mode: &RasterizationMode,
+    ) -> vk::Pipeline {
         let vert_shader_module = share::create_shader_module(
             device,
             include_bytes!("../../shaders/spv/26-shader-depth.vert.spv").to_vec(),
@@ -591,21 +638,7 @@ impl VulkanApp27 {
             p_viewports: viewports.as_ptr(),
         };
 
-        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
-            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
-            p_next: ptr::null(),
-            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
-            depth_clamp_enable: vk::FALSE,
-            cull_mode: vk::CullModeFlags::BACK,
-            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
-            line_width: 1.0,
-            polygon_mode: vk::PolygonMode::FILL,
-            rasterizer_discard_enable: vk::FALSE,
-            depth_bias_clamp: 0.0,
-            depth_bias_constant_factor: 0.0,
-            depth_bias_enable: vk::FALSE,
-            depth_bias_slope_factor: 0.0,
-        };
+        let rasterization_statue_create_info = rasterization_mode.rasterization_state_create_info();
 
         let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
             s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
@@ -666,24 +699,6 @@ impl VulkanApp27 {
             blend_constants: [0.0, 0.0, 0.0, 0.0],
         };
 
-        let set_layouts = [ubo_set_layout];
-
-        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
-            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
-            p_next: ptr::null(),
-            flags: vk::PipelineLayoutCreateFlags::empty(),
-            set_layout_count: set_layouts.len() as u32,
-            p_set_layouts: set_layouts.as_ptr(),
-            push_constant_range_count: 0,
-            p_push_constant_ranges: ptr::null(),
-        };
-
-        let pipeline_layout = unsafe {
-            device
-                .create_pipeline_layout(&pipeline_layout_create_info, None)
-                .expect("Failed to create pipeline layout!")
-        };
-
         let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
             s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
             p_next: ptr::null(),
@@ -721,7 +736,7 @@ impl VulkanApp27 {
             device.destroy_shader_module(frag_shader_module, None);
         }
 
-        (graphics_pipelines[0], pipeline_layout)
+        graphics_pipelines[0]
     }
 
     fn create_command_buffers(
@@ -839,10 +854,13 @@ impl VulkanApp27 {
         command_buffers
     }
 
-    fn update_uniform_buffer(&mut self, current_image: usize, delta_time: f32) {
-        self.uniform_transform.model =
-            Matrix4::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), Deg(90.0) * delta_time)
-                * self.uniform_transform.model;
+    fn update_uniform_buffer(&mut self, current_image: usize, alpha: f32) {
+        self.uniform_transform.model = Matrix4::from_angle_z(Deg(self.model_angle.value(alpha)));
+        if self.is_flying {
+            self.fly_camera.apply(&mut self.uniform_transform, alpha);
+        } else {
+            self.orbit_camera.apply(&mut self.uniform_transform, alpha);
+        }
 
         let ubos = [self.uniform_transform.clone()];
 
@@ -865,6 +883,91 @@ impl VulkanApp27 {
                 .unmap_memory(self.uniform_buffers_memory[current_image]);
         }
     }
+
+    fn camera_mut(&mut self) -> &mut dyn Camera {
+        if self.is_flying {
+            &mut self.fly_camera
+        } else {
+            &mut self.orbit_camera
+        }
+    }
+
+    /// Switch between the orbit and the fly camera, the new one starts where the other looks.
+    fn toggle_camera(&mut self) {
+        self.is_flying = !self.is_flying;
+        if self.is_flying {
+            self.orbit_camera.release_mouse();
+            self.fly_camera = FlyCamera::from(&self.orbit_camera);
+            println!("Camera: fly, move with WASD, E and Q, hold the right mouse button to look.");
+        } else {
+            self.fly_camera.release_mouse();
+            self.orbit_camera = OrbitCamera::from(&self.fly_camera);
+            println!("Camera: orbit, drag to rotate, right drag to pan, scroll to zoom.");
+        }
+    }
+
+    /// Save the next frame to `path`, if the swapchain images can be copied.
+    fn request_screenshot(&mut self, path: PathBuf) {
+        let surface_stuff = SurfaceStuff {
+            surface_loader: self.surface_loader.clone(),
+            surface: self.surface,
+            screen_width: self.swapchain_extent.width,
+            screen_height: self.swapchain_extent.height,
+        };
+        if screenshot::is_screenshot_supported(self.physical_device, &surface_stuff) {
+            self.screenshot_path = Some(path);
+        } else {
+            println!("The swapchain images can not be copied, no screenshot taken.");
+        }
+    }
+
+    fn select_pipeline_variant(&mut self) {
+        let device = &self.device;
+        let render_pass = self.render_pass;
+        let swapchain_extent = self.swapchain_extent;
+        let pipeline_layout = self.pipeline_layout;
+        self.graphics_pipeline =
+            self.pipeline_variants
+                .get_or_create(self.rasterization_mode, |mode| {
+                    VulkanApp27::create_graphics_pipeline(
+                        device,
+                        render_pass,
+                        swapchain_extent,
+                        pipeline_layout,
+                        mode,
+                    )
+                });
+    }
+
+    fn apply_rasterization_mode(&mut self) {
+        // the pipeline is baked into the pre-recorded command buffers, record them again.
+        self.wait_device_idle();
+        self.select_pipeline_variant();
+        unsafe {
+            self.device
+                .free_command_buffers(self.command_pool, &self.command_buffers);
+        }
+        self.command_buffers = VulkanApp27::create_command_buffers(
+            &self.device,
+            self.command_pool,
+            self.graphics_pipeline,
+            &self.swapchain_framebuffers,
+            self.render_pass,
+            self.swapchain_extent,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.pipeline_layout,
+            &self.descriptor_sets,
+            self.indices.len() as u32,
+        );
+
+        println!(
+            "Polygon mode: {:?}, Cull mode: {:?}, Front face: {:?}",
+            self.rasterization_mode.polygon_mode,
+            self.rasterization_mode.cull_mode,
+            self.rasterization_mode.front_face,
+        );
+    }
 }
 
 impl Drop for VulkanApp27 {
@@ -920,7 +1023,12 @@ impl Drop for VulkanApp27 {
 }
 
 impl VulkanApp for VulkanApp27 {
-    fn draw_frame(&mut self, delta_time: f32) {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+        self.camera_mut().update(fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
         let wait_fences = [self.in_flight_fences[self.current_frame]];
 
         unsafe {
@@ -948,7 +1056,7 @@ impl VulkanApp for VulkanApp27 {
             }
         };
 
-        self.update_uniform_buffer(image_index as usize, delta_time);
+        self.update_uniform_buffer(image_index as usize, alpha);
 
         let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
         let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
@@ -980,6 +1088,24 @@ impl VulkanApp for VulkanApp27 {
                 .expect("Failed to execute queue submit.");
         }
 
+        if let Some(path) = self.screenshot_path.take() {
+            // the copy runs after the frame is drawn, and is done before the frame is presented.
+            let result = screenshot::save_swapchain_image(
+                &self.device,
+                self.command_pool,
+                self.graphics_queue,
+                self.swapchain_images[image_index as usize],
+                self.swapchain_format,
+                self.swapchain_extent,
+                &self.memory_properties,
+                &path,
+            );
+            match result {
+                Ok(()) => println!("Screenshot saved to {}", path.display()),
+                Err(err) => println!("Failed to save screenshot {}: {}", path.display(), err),
+            }
+        }
+
         let swapchains = [self.swapchain];
 
         let present_info = vk::PresentInfoKHR {
@@ -1014,12 +1140,19 @@ impl VulkanApp for VulkanApp27 {
     }
 
     fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
         // parameters -------------
+        let window_size = self.window.inner_size();
         let surface_suff = SurfaceStuff {
             surface_loader: self.surface_loader.clone(),
             surface: self.surface,
-            screen_width: WINDOW_WIDTH,
-            screen_height: WINDOW_HEIGHT,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
         };
         // ------------------------
 
@@ -1043,6 +1176,8 @@ impl VulkanApp for VulkanApp27 {
         self.swapchain_images = swapchain_stuff.swapchain_images;
         self.swapchain_format = swapchain_stuff.swapchain_format;
         self.swapchain_extent = swapchain_stuff.swapchain_extent;
+        self.orbit_camera.set_extent(self.swapchain_extent);
+        self.fly_camera.set_extent(self.swapchain_extent);
 
         self.swapchain_imageviews = share::v1::create_image_views(
             &self.device,
@@ -1055,14 +1190,10 @@ impl VulkanApp for VulkanApp27 {
             self.physical_device,
             self.swapchain_format,
         );
-        let (graphics_pipeline, pipeline_layout) = VulkanApp27::create_graphics_pipeline(
-            &self.device,
-            self.render_pass,
-            swapchain_stuff.swapchain_extent,
-            self.ubo_layout,
-        );
-        self.graphics_pipeline = graphics_pipeline;
-        self.pipeline_layout = pipeline_layout;
+        self.pipeline_layout = VulkanApp27::create_pipeline_layout(&self.device, self.ubo_layout);
+        // every cached variant was built against the old render pass.
+        self.pipeline_variants.clear();
+        self.select_pipeline_variant();
 
         let depth_resources = share::v1::create_depth_resources(
             &self.instance,
@@ -1111,7 +1242,7 @@ impl VulkanApp for VulkanApp27 {
             for &framebuffer in self.swapchain_framebuffers.iter() {
                 self.device.destroy_framebuffer(framebuffer, None);
             }
-            self.device.destroy_pipeline(self.graphics_pipeline, None);
+            self.pipeline_variants.destroy_pipelines(&self.device);
             self.device
                 .destroy_pipeline_layout(self.pipeline_layout, None);
             self.device.destroy_render_pass(self.render_pass, None);
@@ -1138,6 +1269,47 @@ impl VulkanApp for VulkanApp27 {
     fn window_ref(&self) -> &winit::window::Window {
         &self.window
     }
+
+    fn register_actions(&mut self, actions: &mut ActionMap) {
+        camera::register_actions(actions);
+        render_state::register_actions(actions);
+        screenshot::register_actions(actions);
+        actions.register(TOGGLE_CAMERA, &[Binding::key(VirtualKeyCode::C)]);
+    }
+
+    fn on_input(&mut self, actions: &ActionMap) {
+        if self
+            .rasterization_mode
+            .on_input(actions, self.is_fill_mode_non_solid_supported)
+        {
+            self.apply_rasterization_mode();
+        }
+        if actions.is_pressed(screenshot::SCREENSHOT) {
+            self.request_screenshot(screenshot::screenshot_path());
+        }
+        if actions.is_pressed(TOGGLE_CAMERA) {
+            self.toggle_camera();
+        }
+        self.camera_mut().on_input(actions);
+    }
+
+    fn on_mouse_motion(&mut self, delta: (f64, f64)) {
+        self.camera_mut().on_mouse_motion(delta);
+    }
+
+    fn on_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        self.camera_mut().on_mouse_button(button, state);
+    }
+
+    fn on_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        self.camera_mut().on_mouse_wheel(delta);
+    }
+
+    fn on_focus_changed(&mut self, is_focused: bool) {
+        if !is_focused {
+            self.camera_mut().release_mouse();
+        }
+    }
 }
 
 fn main() {
@@ -2,6 +2,9 @@ use vulkan_tutorial_rust::{
     utility, // the mod define some fixed functions that have been learned before.
     utility::constants::*,
     utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
+    utility::input::ActionMap,
+    utility::render_state::{self, PipelineVariants, RasterizationMode},
     utility::share,
     utility::structures::*,
     utility::window::{ProgramProc, VulkanApp},
@@ -54,6 +57,9 @@ struct VulkanApp28 {
     ubo_layout: vk::DescriptorSetLayout,
     pipeline_layout: vk::PipelineLayout,
     graphics_pipeline: vk::Pipeline,
+    pipeline_variants: PipelineVariants,
+    rasterization_mode: RasterizationMode,
+    is_fill_mode_non_solid_supported: bool,
 
     depth_image: vk::Image,
     depth_image_view: vk::ImageView,
@@ -74,6 +80,7 @@ struct VulkanApp28 {
     index_buffer_memory: vk::DeviceMemory,
 
     uniform_transform: UniformBufferObject,
+    model_angle: InterpolatedValue,
     uniform_buffers: Vec<vk::Buffer>,
     uniform_buffers_memory: Vec<vk::DeviceMemory>,
 
@@ -104,8 +111,7 @@ impl VulkanApp28 {
             VALIDATION.is_enable,
             &VALIDATION.required_validation_layers.to_vec(),
         );
-        let surface_stuff =
-            share::create_surface(&entry, &instance, &window, WINDOW_WIDTH, WINDOW_HEIGHT);
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
         let (debug_utils_loader, debug_merssager) =
             setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
         let physical_device =
@@ -143,12 +149,20 @@ impl VulkanApp28 {
             swapchain_stuff.swapchain_format,
         );
         let ubo_layout = share::v2::create_descriptor_set_layout(&device);
-        let (graphics_pipeline, pipeline_layout) = VulkanApp28::create_graphics_pipeline(
-            &device,
-            render_pass,
-            swapchain_stuff.swapchain_extent,
-            ubo_layout,
-        );
+        let pipeline_layout = VulkanApp28::create_pipeline_layout(&device, ubo_layout);
+        let is_fill_mode_non_solid_supported =
+            render_state::is_fill_mode_non_solid_supported(&instance, physical_device);
+        let rasterization_mode = RasterizationMode::new(vk::FrontFace::COUNTER_CLOCKWISE);
+        let mut pipeline_variants = PipelineVariants::new();
+        let graphics_pipeline = pipeline_variants.get_or_create(rasterization_mode, |mode| {
+            VulkanApp28::create_graphics_pipeline(
+                &device,
+                render_pass,
+                swapchain_stuff.swapchain_extent,
+                pipeline_layout,
+                mode,
+            )
+        });
         let command_pool = share::v1::create_command_pool(&device, &queue_family);
         let (depth_image, depth_image_view, depth_image_memory) = share::v1::create_depth_resources(
             &instance,
@@ -257,6 +271,9 @@ impl VulkanApp28 {
             ubo_layout,
             render_pass,
             graphics_pipeline,
+            pipeline_variants,
+            rasterization_mode,
+            is_fill_mode_non_solid_supported,
 
             depth_image,
             depth_image_view,
@@ -295,6 +312,7 @@ impl VulkanApp28 {
                     proj
                 },
             },
+            model_angle: InterpolatedValue::new(90.0),
             uniform_buffers,
             uniform_buffers_memory,
 
@@ -727,12 +745,36 @@ impl VulkanApp28 {
         framebuffers
     }
 
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        ubo_set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [ubo_set_layout];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: 0,
+            p_push_constant_ranges: ptr::null(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
     fn create_graphics_pipeline(
         device: &ash::Device,
         render_pass: vk::RenderPass,
         swapchain_extent: vk::Extent2D,
-        ubo_set_layout: vk::DescriptorSetLayout,
-    ) -> (vk::Pipeline, vk::PipelineLayout) {
+        pipeline_layout: vk::PipelineLayout,
+        rasterization_mode: &RasterizationMode,
+    ) -> vk::Pipeline {
         let vert_shader_module = share::create_shader_module(
             device,
             include_bytes!("../../shaders/spv/26-shader-depth.vert.spv").to_vec(),
@@ -811,21 +853,7 @@ impl VulkanApp28 {
             p_viewports: viewports.as_ptr(),
         };
 
-        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
-            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
-            p_next: ptr::null(),
-            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
-            depth_clamp_enable: vk::FALSE,
-            cull_mode: vk::CullModeFlags::BACK,
-            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
-            line_width: 1.0,
-            polygon_mode: vk::PolygonMode::FILL,
-            rasterizer_discard_enable: vk::FALSE,
-            depth_bias_clamp: 0.0,
-            depth_bias_constant_factor: 0.0,
-            depth_bias_enable: vk::FALSE,
-            depth_bias_slope_factor: 0.0,
-        };
+        let rasterization_statue_create_info = rasterization_mode.rasterization_state_create_info();
 
         let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
             s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
@@ -886,24 +914,6 @@ impl VulkanApp28 {
             blend_constants: [0.0, 0.0, 0.0, 0.0],
         };
 
-        let set_layouts = [ubo_set_layout];
-
-        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
-            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
-            p_next: ptr::null(),
-            flags: vk::PipelineLayoutCreateFlags::empty(),
-            set_layout_count: set_layouts.len() as u32,
-            p_set_layouts: set_layouts.as_ptr(),
-            push_constant_range_count: 0,
-            p_push_constant_ranges: ptr::null(),
-        };
-
-        let pipeline_layout = unsafe {
-            device
-                .create_pipeline_layout(&pipeline_layout_create_info, None)
-                .expect("Failed to create pipeline layout!")
-        };
-
         let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
             s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
             p_next: ptr::null(),
@@ -941,7 +951,7 @@ impl VulkanApp28 {
             device.destroy_shader_module(frag_shader_module, None);
         }
 
-        (graphics_pipelines[0], pipeline_layout)
+        graphics_pipelines[0]
     }
 
     fn create_command_buffers(
@@ -1059,10 +1069,8 @@ impl VulkanApp28 {
         command_buffers
     }
 
-    fn update_uniform_buffer(&mut self, current_image: usize, delta_time: f32) {
-        self.uniform_transform.model =
-            Matrix4::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), Deg(90.0) * delta_time)
-                * self.uniform_transform.model;
+    fn update_uniform_buffer(&mut self, current_image: usize, alpha: f32) {
+        self.uniform_transform.model = Matrix4::from_angle_z(Deg(self.model_angle.value(alpha)));
 
         let ubos = [self.uniform_transform.clone()];
 
@@ -1085,6 +1093,54 @@ impl VulkanApp28 {
                 .unmap_memory(self.uniform_buffers_memory[current_image]);
         }
     }
+
+    fn select_pipeline_variant(&mut self) {
+        let device = &self.device;
+        let render_pass = self.render_pass;
+        let swapchain_extent = self.swapchain_extent;
+        let pipeline_layout = self.pipeline_layout;
+        self.graphics_pipeline =
+            self.pipeline_variants
+                .get_or_create(self.rasterization_mode, |mode| {
+                    VulkanApp28::create_graphics_pipeline(
+                        device,
+                        render_pass,
+                        swapchain_extent,
+                        pipeline_layout,
+                        mode,
+                    )
+                });
+    }
+
+    fn apply_rasterization_mode(&mut self) {
+        // the pipeline is baked into the pre-recorded command buffers, record them again.
+        self.wait_device_idle();
+        self.select_pipeline_variant();
+        unsafe {
+            self.device
+                .free_command_buffers(self.command_pool, &self.command_buffers);
+        }
+        self.command_buffers = VulkanApp28::create_command_buffers(
+            &self.device,
+            self.command_pool,
+            self.graphics_pipeline,
+            &self.swapchain_framebuffers,
+            self.render_pass,
+            self.swapchain_extent,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.pipeline_layout,
+            &self.descriptor_sets,
+            self.indices.len() as u32,
+        );
+
+        println!(
+            "Polygon mode: {:?}, Cull mode: {:?}, Front face: {:?}",
+            self.rasterization_mode.polygon_mode,
+            self.rasterization_mode.cull_mode,
+            self.rasterization_mode.front_face,
+        );
+    }
 }
 
 impl Drop for VulkanApp28 {
@@ -1140,7 +1196,11 @@ impl Drop for VulkanApp28 {
 }
 
 impl VulkanApp for VulkanApp28 {
-    fn draw_frame(&mut self, delta_time: f32) {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
         let wait_fences = [self.in_flight_fences[self.current_frame]];
 
         unsafe {
@@ -1168,7 +1228,7 @@ impl VulkanApp for VulkanApp28 {
             }
         };
 
-        self.update_uniform_buffer(image_index as usize, delta_time);
+        self.update_uniform_buffer(image_index as usize, alpha);
 
         let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
         let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
@@ -1234,12 +1294,19 @@ impl VulkanApp for VulkanApp28 {
     }
 
     fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
         // parameters -------------
+        let window_size = self.window.inner_size();
         let surface_suff = SurfaceStuff {
             surface_loader: self.surface_loader.clone(),
             surface: self.surface,
-            screen_width: WINDOW_WIDTH,
-            screen_height: WINDOW_HEIGHT,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
         };
         // ------------------------
 
@@ -1275,14 +1342,10 @@ impl VulkanApp for VulkanApp28 {
             self.physical_device,
             self.swapchain_format,
         );
-        let (graphics_pipeline, pipeline_layout) = VulkanApp28::create_graphics_pipeline(
-            &self.device,
-            self.render_pass,
-            swapchain_stuff.swapchain_extent,
-            self.ubo_layout,
-        );
-        self.graphics_pipeline = graphics_pipeline;
-        self.pipeline_layout = pipeline_layout;
+        self.pipeline_layout = VulkanApp28::create_pipeline_layout(&self.device, self.ubo_layout);
+        // every cached variant was built against the old render pass.
+        self.pipeline_variants.clear();
+        self.select_pipeline_variant();
 
         let depth_resources = share::v1::create_depth_resources(
             &self.instance,
@@ -1331,7 +1394,7 @@ impl VulkanApp for VulkanApp28 {
             for &framebuffer in self.swapchain_framebuffers.iter() {
                 self.device.destroy_framebuffer(framebuffer, None);
             }
-            self.device.destroy_pipeline(self.graphics_pipeline, None);
+            self.pipeline_variants.destroy_pipelines(&self.device);
             self.device
                 .destroy_pipeline_layout(self.pipeline_layout, None);
             self.device.destroy_render_pass(self.render_pass, None);
@@ -1358,6 +1421,19 @@ impl VulkanApp for VulkanApp28 {
     fn window_ref(&self) -> &winit::window::Window {
         &self.window
     }
+
+    fn register_actions(&mut self, actions: &mut ActionMap) {
+        render_state::register_actions(actions);
+    }
+
+    fn on_input(&mut self, actions: &ActionMap) {
+        if self
+            .rasterization_mode
+            .on_input(actions, self.is_fill_mode_non_solid_supported)
+        {
+            self.apply_rasterization_mode();
+        }
+    }
 }
 
 fn main() {
@@ -2,6 +2,10 @@ use vulkan_tutorial_rust::{
     utility, // the mod define some fixed functions that have been learned before.
     utility::constants::*,
     utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
+    utility::frame_recording::{DrawItem, FrameCommandBuffers, RecordingMode},
+    utility::input::ActionMap,
+    utility::render_state::{self, MultisampleMode, PipelineVariants, RasterizationMode},
     utility::share,
     utility::structures::*,
     utility::window::{ProgramProc, VulkanApp},
@@ -12,6 +16,7 @@ use ash::version::InstanceV1_0;
 use ash::vk;
 use cgmath::{Deg, Matrix4, Point3, Vector3};
 use image::GenericImageView;
+use winit::event::VirtualKeyCode;
 
 use std::ffi::CString;
 use std::path::Path;
@@ -21,6 +26,7 @@ use std::ptr;
 const WINDOW_TITLE: &'static str = "29.Multi-Sampling";
 const MODEL_PATH: &'static str = "assets/chalet.obj";
 const TEXTURE_PATH: &'static str = "assets/chalet.jpg";
+const DEFAULT_MSAA_SAMPLES: vk::SampleCountFlags = vk::SampleCountFlags::TYPE_8;
 
 struct VulkanApp29 {
     window: winit::window::Window,
@@ -53,16 +59,17 @@ struct VulkanApp29 {
     ubo_layout: vk::DescriptorSetLayout,
     pipeline_layout: vk::PipelineLayout,
     graphics_pipeline: vk::Pipeline,
+    pipeline_variants: PipelineVariants,
+    rasterization_mode: RasterizationMode,
+    is_fill_mode_non_solid_supported: bool,
 
-    color_image: vk::Image,
-    color_image_view: vk::ImageView,
-    color_image_memory: vk::DeviceMemory,
+    // None at 1x, the scene is then drawn straight into the swapchain image.
+    color_attachment: Option<TransientAttachment>,
+    depth_attachment: TransientAttachment,
 
-    depth_image: vk::Image,
-    depth_image_view: vk::ImageView,
-    depth_image_memory: vk::DeviceMemory,
-
-    msaa_samples: vk::SampleCountFlags,
+    multisample_mode: MultisampleMode,
+    supported_sample_counts: vk::SampleCountFlags,
+    is_sample_rate_shading_supported: bool,
 
     _mip_levels: u32,
     texture_image: vk::Image,
@@ -71,7 +78,7 @@ struct VulkanApp29 {
     texture_image_memory: vk::DeviceMemory,
 
     _vertices: Vec<VertexV3>,
-    indices: Vec<u32>,
+    _indices: Vec<u32>,
 
     vertex_buffer: vk::Buffer,
     vertex_buffer_memory: vk::DeviceMemory,
@@ -79,6 +86,7 @@ struct VulkanApp29 {
     index_buffer_memory: vk::DeviceMemory,
 
     uniform_transform: UniformBufferObject,
+    model_angle: InterpolatedValue,
     uniform_buffers: Vec<vk::Buffer>,
     uniform_buffers_memory: Vec<vk::DeviceMemory>,
 
@@ -86,7 +94,8 @@ struct VulkanApp29 {
     descriptor_sets: Vec<vk::DescriptorSet>,
 
     command_pool: vk::CommandPool,
-    command_buffers: Vec<vk::CommandBuffer>,
+    frame_command_buffers: FrameCommandBuffers,
+    draw_list: Vec<DrawItem>,
 
     image_available_semaphores: Vec<vk::Semaphore>,
     render_finished_semaphores: Vec<vk::Semaphore>,
@@ -109,13 +118,20 @@ impl VulkanApp29 {
             VALIDATION.is_enable,
             &VALIDATION.required_validation_layers.to_vec(),
         );
-        let surface_stuff =
-            share::create_surface(&entry, &instance, &window, WINDOW_WIDTH, WINDOW_HEIGHT);
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
         let (debug_utils_loader, debug_merssager) =
             setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
         let physical_device =
             share::pick_physical_device(&instance, &surface_stuff, &DEVICE_EXTENSIONS);
-        let msaa_samples = VulkanApp29::get_max_usable_sample_count(&instance, physical_device);
+        let supported_sample_counts =
+            render_state::supported_sample_counts(&instance, physical_device);
+        let multisample_mode = MultisampleMode::new(render_state::clamp_sample_count(
+            DEFAULT_MSAA_SAMPLES,
+            supported_sample_counts,
+        ));
+        let msaa_samples = multisample_mode.samples;
+        let is_sample_rate_shading_supported =
+            render_state::is_sample_rate_shading_supported(&instance, physical_device);
         let physical_device_memory_properties =
             unsafe { instance.get_physical_device_memory_properties(physical_device) };
         let (device, queue_family) = share::create_logical_device(
@@ -150,28 +166,33 @@ impl VulkanApp29 {
             msaa_samples,
         );
         let ubo_layout = share::v2::create_descriptor_set_layout(&device);
-        let (graphics_pipeline, pipeline_layout) = VulkanApp29::create_graphics_pipeline(
+        let pipeline_layout = VulkanApp29::create_pipeline_layout(&device, ubo_layout);
+        let is_fill_mode_non_solid_supported =
+            utility::render_state::is_fill_mode_non_solid_supported(&instance, physical_device);
+        let rasterization_mode = RasterizationMode::new(vk::FrontFace::COUNTER_CLOCKWISE);
+        let mut pipeline_variants = PipelineVariants::new();
+        let graphics_pipeline = pipeline_variants.get_or_create(rasterization_mode, |mode| {
+            VulkanApp29::create_graphics_pipeline(
+                &device,
+                render_pass,
+                swapchain_stuff.swapchain_extent,
+                pipeline_layout,
+                &multisample_mode,
+                mode,
+            )
+        });
+        let command_pool = share::v1::create_command_pool(&device, &queue_family);
+        let color_attachment = VulkanApp29::create_color_resources(
             &device,
-            render_pass,
+            swapchain_stuff.swapchain_format,
             swapchain_stuff.swapchain_extent,
-            ubo_layout,
+            &physical_device_memory_properties,
             msaa_samples,
         );
-        let command_pool = share::v1::create_command_pool(&device, &queue_family);
-        let (color_image, color_image_view, color_image_memory) =
-            VulkanApp29::create_color_resources(
-                &device,
-                swapchain_stuff.swapchain_format,
-                swapchain_stuff.swapchain_extent,
-                &physical_device_memory_properties,
-                msaa_samples,
-            );
-        let (depth_image, depth_image_view, depth_image_memory) = share::v1::create_depth_resources(
+        let depth_attachment = VulkanApp29::create_depth_resources(
             &instance,
             &device,
             physical_device,
-            command_pool,
-            graphics_queue,
             swapchain_stuff.swapchain_extent,
             &physical_device_memory_properties,
             msaa_samples,
@@ -180,8 +201,10 @@ impl VulkanApp29 {
             &device,
             render_pass,
             &swapchain_imageviews,
-            depth_image_view,
-            color_image_view,
+            depth_attachment.image_view,
+            color_attachment
+                .as_ref()
+                .map(|attachment| attachment.image_view),
             swapchain_stuff.swapchain_extent,
         );
         let (vertices, indices) = share::load_model(&Path::new(MODEL_PATH));
@@ -226,19 +249,19 @@ impl VulkanApp29 {
             texture_sampler,
             swapchain_stuff.swapchain_images.len(),
         );
-        let command_buffers = VulkanApp29::create_command_buffers(
+        let frame_command_buffers = FrameCommandBuffers::new(
             &device,
-            command_pool,
-            graphics_pipeline,
-            &swapchain_framebuffers,
-            render_pass,
-            swapchain_stuff.swapchain_extent,
-            vertex_buffer,
-            index_buffer,
-            pipeline_layout,
-            &descriptor_sets,
-            indices.len() as u32,
+            queue_family.graphics_family.unwrap(),
+            RecordingMode::PreRecorded,
+            swapchain_stuff.swapchain_images.len(),
+            MAX_FRAMES_IN_FLIGHT,
         );
+        let draw_list = vec![DrawItem {
+            index_count: indices.len() as u32,
+            instance_count: 1,
+            first_index: 0,
+            vertex_offset: 0,
+        }];
         let sync_ojbects = share::v1::create_sync_objects(&device, MAX_FRAMES_IN_FLIGHT);
 
         // cleanup(); the 'drop' function will take care of it.
@@ -274,16 +297,16 @@ impl VulkanApp29 {
             ubo_layout,
             render_pass,
             graphics_pipeline,
+            pipeline_variants,
+            rasterization_mode,
+            is_fill_mode_non_solid_supported,
 
-            color_image,
-            color_image_view,
-            color_image_memory,
+            color_attachment,
+            depth_attachment,
 
-            depth_image,
-            depth_image_view,
-            depth_image_memory,
-
-            msaa_samples,
+            multisample_mode,
+            supported_sample_counts,
+            is_sample_rate_shading_supported,
 
             _mip_levels: mip_levels,
             texture_image,
@@ -292,7 +315,7 @@ impl VulkanApp29 {
             texture_image_memory,
 
             _vertices: vertices,
-            indices,
+            _indices: indices,
 
             vertex_buffer,
             vertex_buffer_memory,
@@ -318,6 +341,7 @@ impl VulkanApp29 {
                     proj
                 },
             },
+            model_angle: InterpolatedValue::new(90.0),
             uniform_buffers,
             uniform_buffers_memory,
 
@@ -325,7 +349,8 @@ impl VulkanApp29 {
             descriptor_sets,
 
             command_pool,
-            command_buffers,
+            frame_command_buffers,
+            draw_list,
 
             image_available_semaphores: sync_ojbects.image_available_semaphores,
             render_finished_semaphores: sync_ojbects.render_finished_semaphores,
@@ -336,75 +361,83 @@ impl VulkanApp29 {
         }
     }
 
-    fn get_max_usable_sample_count(
-        instance: &ash::Instance,
-        physical_device: vk::PhysicalDevice,
-    ) -> vk::SampleCountFlags {
-        let physical_device_properties =
-            unsafe { instance.get_physical_device_properties(physical_device) };
-
-        let count = std::cmp::min(
-            physical_device_properties
-                .limits
-                .framebuffer_color_sample_counts,
-            physical_device_properties
-                .limits
-                .framebuffer_depth_sample_counts,
-        );
-
-        if count.contains(vk::SampleCountFlags::TYPE_64) {
-            return vk::SampleCountFlags::TYPE_64;
-        }
-        if count.contains(vk::SampleCountFlags::TYPE_32) {
-            return vk::SampleCountFlags::TYPE_32;
-        }
-        if count.contains(vk::SampleCountFlags::TYPE_16) {
-            return vk::SampleCountFlags::TYPE_16;
-        }
-        if count.contains(vk::SampleCountFlags::TYPE_8) {
-            return vk::SampleCountFlags::TYPE_8;
-        }
-        if count.contains(vk::SampleCountFlags::TYPE_4) {
-            return vk::SampleCountFlags::TYPE_4;
-        }
-        if count.contains(vk::SampleCountFlags::TYPE_2) {
-            return vk::SampleCountFlags::TYPE_2;
-        }
-
-        vk::SampleCountFlags::TYPE_1
-    }
-
     fn create_color_resources(
         device: &ash::Device,
         swapchain_format: vk::Format,
         swapchain_extent: vk::Extent2D,
         device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
         msaa_samples: vk::SampleCountFlags,
-    ) -> (vk::Image, vk::ImageView, vk::DeviceMemory) {
-        let color_format = swapchain_format;
+    ) -> Option<TransientAttachment> {
+        if msaa_samples == vk::SampleCountFlags::TYPE_1 {
+            return None;
+        }
 
-        let (color_image, color_image_memory) = share::v1::create_image(
+        // the multisampled color is resolved inside the render pass and never stored.
+        Some(share::v1::create_transient_attachment(
             device,
-            swapchain_extent.width,
-            swapchain_extent.height,
-            1,
+            swapchain_extent,
             msaa_samples,
-            color_format,
-            vk::ImageTiling::OPTIMAL,
-            vk::ImageUsageFlags::TRANSIENT_ATTACHMENT | vk::ImageUsageFlags::COLOR_ATTACHMENT,
-            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            swapchain_format,
+            vk::ImageUsageFlags::COLOR_ATTACHMENT,
+            vk::ImageAspectFlags::COLOR,
             device_memory_properties,
-        );
+        ))
+    }
 
-        let color_image_view = share::v1::create_image_view(
+    fn create_depth_resources(
+        instance: &ash::Instance,
+        device: &ash::Device,
+        physical_device: vk::PhysicalDevice,
+        swapchain_extent: vk::Extent2D,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        msaa_samples: vk::SampleCountFlags,
+    ) -> TransientAttachment {
+        share::v1::create_transient_attachment(
             device,
-            color_image,
-            color_format,
-            vk::ImageAspectFlags::COLOR,
-            1,
-        );
+            swapchain_extent,
+            msaa_samples,
+            share::find_depth_format(instance, physical_device),
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT,
+            vk::ImageAspectFlags::DEPTH,
+            device_memory_properties,
+        )
+    }
+
+    /// Print how much of the attachment memory the driver has not had to commit.
+    fn report_transient_memory(&self) {
+        let mut total_size = 0;
+        let mut saved_size = 0;
 
-        (color_image, color_image_view, color_image_memory)
+        let mut attachments = vec![("Depth", &self.depth_attachment)];
+        if let Some(color_attachment) = &self.color_attachment {
+            attachments.insert(0, ("MSAA color", color_attachment));
+        }
+
+        for (name, attachment) in attachments.iter() {
+            let committed_size =
+                share::v1::transient_attachment_committed_size(&self.device, attachment);
+            println!(
+                "{} attachment: {} KiB, {} KiB committed ({}).",
+                name,
+                attachment.allocation_size / 1024,
+                committed_size / 1024,
+                if attachment.is_lazily_allocated {
+                    "lazily allocated"
+                } else {
+                    "device local"
+                },
+            );
+
+            total_size += attachment.allocation_size;
+            saved_size +=
+                attachment.allocation_size - committed_size.min(attachment.allocation_size);
+        }
+
+        println!(
+            "Transient attachments: {} KiB saved of {} KiB.",
+            saved_size / 1024,
+            total_size / 1024
+        );
     }
 
     fn create_texture_image(
@@ -552,16 +585,27 @@ impl VulkanApp29 {
         surface_format: vk::Format,
         msaa_samples: vk::SampleCountFlags,
     ) -> vk::RenderPass {
+        // at 1x there is nothing to resolve, the color attachment is the swapchain image itself.
+        let is_multisampled = msaa_samples != vk::SampleCountFlags::TYPE_1;
+
         let color_attachment = vk::AttachmentDescription {
             flags: vk::AttachmentDescriptionFlags::empty(),
             format: surface_format,
             samples: msaa_samples,
             load_op: vk::AttachmentLoadOp::CLEAR,
-            store_op: vk::AttachmentStoreOp::STORE,
+            store_op: if is_multisampled {
+                vk::AttachmentStoreOp::DONT_CARE
+            } else {
+                vk::AttachmentStoreOp::STORE
+            },
             stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
             stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
             initial_layout: vk::ImageLayout::UNDEFINED,
-            final_layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            final_layout: if is_multisampled {
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
+            } else {
+                vk::ImageLayout::PRESENT_SRC_KHR
+            },
         };
 
         let depth_attachment = vk::AttachmentDescription {
@@ -611,13 +655,19 @@ impl VulkanApp29 {
             pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
             input_attachment_count: 0,
             p_input_attachments: ptr::null(),
-            p_resolve_attachments: &color_attachment_resolve_ref,
+            p_resolve_attachments: if is_multisampled {
+                &color_attachment_resolve_ref
+            } else {
+                ptr::null()
+            },
             preserve_attachment_count: 0,
             p_preserve_attachments: ptr::null(),
         }];
 
-        let render_pass_attachments =
-            [color_attachment, depth_attachment, color_attachment_resolve];
+        let mut render_pass_attachments = vec![color_attachment, depth_attachment];
+        if is_multisampled {
+            render_pass_attachments.push(color_attachment_resolve);
+        }
 
         let subpass_dependencies = [vk::SubpassDependency {
             src_subpass: vk::SUBPASS_EXTERNAL,
@@ -654,13 +704,16 @@ impl VulkanApp29 {
         render_pass: vk::RenderPass,
         swapchain_image_views: &Vec<vk::ImageView>,
         depth_image_view: vk::ImageView,
-        color_image_view: vk::ImageView,
+        color_image_view: Option<vk::ImageView>,
         swapchain_extent: vk::Extent2D,
     ) -> Vec<vk::Framebuffer> {
         let mut framebuffers = vec![];
 
         for &image_view in swapchain_image_views.iter() {
-            let attachments = [color_image_view, depth_image_view, image_view];
+            let attachments = match color_image_view {
+                Some(color_image_view) => vec![color_image_view, depth_image_view, image_view],
+                None => vec![image_view, depth_image_view],
+            };
 
             let framebuffer_create_info = vk::FramebufferCreateInfo {
                 s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
@@ -686,13 +739,37 @@ impl VulkanApp29 {
         framebuffers
     }
 
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        ubo_set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [ubo_set_layout];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: 0,
+            p_push_constant_ranges: ptr::null(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
     fn create_graphics_pipeline(
         device: &ash::Device,
         render_pass: vk::RenderPass,
         swapchain_extent: vk::Extent2D,
-        ubo_set_layout: vk::DescriptorSetLayout,
-        msaa_samples: vk::SampleCountFlags,
-    ) -> (vk::Pipeline, vk::PipelineLayout) {
+        pipeline_layout: vk::PipelineLayout,
+        multisample_mode: &MultisampleMode,
+        rasterization_mode: &RasterizationMode,
+    ) -> vk::Pipeline {
         let vert_shader_module = share::create_shader_module(
             device,
             include_bytes!("../../shaders/spv/26-shader-depth.vert.spv").to_vec(),
@@ -771,33 +848,9 @@ impl VulkanApp29 {
             p_viewports: viewports.as_ptr(),
         };
 
-        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
-            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
-            p_next: ptr::null(),
-            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
-            depth_clamp_enable: vk::FALSE,
-            cull_mode: vk::CullModeFlags::BACK,
-            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
-            line_width: 1.0,
-            polygon_mode: vk::PolygonMode::FILL,
-            rasterizer_discard_enable: vk::FALSE,
-            depth_bias_clamp: 0.0,
-            depth_bias_constant_factor: 0.0,
-            depth_bias_enable: vk::FALSE,
-            depth_bias_slope_factor: 0.0,
-        };
+        let rasterization_statue_create_info = rasterization_mode.rasterization_state_create_info();
 
-        let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
-            s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
-            flags: vk::PipelineMultisampleStateCreateFlags::empty(),
-            p_next: ptr::null(),
-            rasterization_samples: msaa_samples,
-            sample_shading_enable: vk::FALSE,
-            min_sample_shading: 0.0,
-            p_sample_mask: ptr::null(),
-            alpha_to_one_enable: vk::FALSE,
-            alpha_to_coverage_enable: vk::FALSE,
-        };
+        let multisample_state_create_info = multisample_mode.multisample_state_create_info();
 
         let stencil_state = vk::StencilOpState {
             fail_op: vk::StencilOp::KEEP,
@@ -846,24 +899,6 @@ impl VulkanApp29 {
             blend_constants: [0.0, 0.0, 0.0, 0.0],
         };
 
-        let set_layouts = [ubo_set_layout];
-
-        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
-            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
-            p_next: ptr::null(),
-            flags: vk::PipelineLayoutCreateFlags::empty(),
-            set_layout_count: set_layouts.len() as u32,
-            p_set_layouts: set_layouts.as_ptr(),
-            push_constant_range_count: 0,
-            p_push_constant_ranges: ptr::null(),
-        };
-
-        let pipeline_layout = unsafe {
-            device
-                .create_pipeline_layout(&pipeline_layout_create_info, None)
-                .expect("Failed to create pipeline layout!")
-        };
-
         let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
             s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
             p_next: ptr::null(),
@@ -901,128 +936,133 @@ impl VulkanApp29 {
             device.destroy_shader_module(frag_shader_module, None);
         }
 
-        (graphics_pipelines[0], pipeline_layout)
+        graphics_pipelines[0]
     }
 
-    fn create_command_buffers(
+    #[allow(clippy::too_many_arguments)]
+    fn record_command_buffer(
         device: &ash::Device,
-        command_pool: vk::CommandPool,
+        command_buffer: vk::CommandBuffer,
         graphics_pipeline: vk::Pipeline,
-        framebuffers: &Vec<vk::Framebuffer>,
+        framebuffer: vk::Framebuffer,
         render_pass: vk::RenderPass,
         surface_extent: vk::Extent2D,
         vertex_buffer: vk::Buffer,
         index_buffer: vk::Buffer,
         pipeline_layout: vk::PipelineLayout,
-        descriptor_sets: &Vec<vk::DescriptorSet>,
-        index_count: u32,
-    ) -> Vec<vk::CommandBuffer> {
-        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
-            s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
-            p_next: ptr::null(),
-            command_buffer_count: framebuffers.len() as u32,
-            command_pool,
-            level: vk::CommandBufferLevel::PRIMARY,
-        };
+        descriptor_set: vk::DescriptorSet,
+        draw_list: &[DrawItem],
+    ) {
+        let clear_values = [
+            vk::ClearValue {
+                // clear value for color buffer
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 1.0],
+                },
+            },
+            vk::ClearValue {
+                // clear value for depth buffer
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
 
-        let command_buffers = unsafe {
-            device
-                .allocate_command_buffers(&command_buffer_allocate_info)
-                .expect("Failed to allocate Command Buffers!")
+        let render_pass_begin_info = vk::RenderPassBeginInfo {
+            s_type: vk::StructureType::RENDER_PASS_BEGIN_INFO,
+            p_next: ptr::null(),
+            render_pass,
+            framebuffer,
+            render_area: vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent: surface_extent,
+            },
+            clear_value_count: clear_values.len() as u32,
+            p_clear_values: clear_values.as_ptr(),
         };
 
-        for (i, &command_buffer) in command_buffers.iter().enumerate() {
-            let command_buffer_begin_info = vk::CommandBufferBeginInfo {
-                s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
-                p_next: ptr::null(),
-                p_inheritance_info: ptr::null(),
-                flags: vk::CommandBufferUsageFlags::SIMULTANEOUS_USE,
-            };
+        unsafe {
+            device.cmd_begin_render_pass(
+                command_buffer,
+                &render_pass_begin_info,
+                vk::SubpassContents::INLINE,
+            );
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                graphics_pipeline,
+            );
 
-            unsafe {
-                device
-                    .begin_command_buffer(command_buffer, &command_buffer_begin_info)
-                    .expect("Failed to begin recording Command Buffer at beginning!");
-            }
+            let vertex_buffers = [vertex_buffer];
+            let offsets = [0_u64];
+            let descriptor_sets_to_bind = [descriptor_set];
+
+            device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets);
+            device.cmd_bind_index_buffer(command_buffer, index_buffer, 0, vk::IndexType::UINT32);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                0,
+                &descriptor_sets_to_bind,
+                &[],
+            );
+        }
 
-            let clear_values = [
-                vk::ClearValue {
-                    // clear value for color buffer
-                    color: vk::ClearColorValue {
-                        float32: [0.0, 0.0, 0.0, 1.0],
-                    },
-                },
-                vk::ClearValue {
-                    // clear value for depth buffer
-                    depth_stencil: vk::ClearDepthStencilValue {
-                        depth: 1.0,
-                        stencil: 0,
-                    },
-                },
-            ];
+        for draw_item in draw_list.iter() {
+            draw_item.cmd_draw(device, command_buffer);
+        }
 
-            let render_pass_begin_info = vk::RenderPassBeginInfo {
-                s_type: vk::StructureType::RENDER_PASS_BEGIN_INFO,
-                p_next: ptr::null(),
-                render_pass,
-                framebuffer: framebuffers[i],
-                render_area: vk::Rect2D {
-                    offset: vk::Offset2D { x: 0, y: 0 },
-                    extent: surface_extent,
-                },
-                clear_value_count: clear_values.len() as u32,
-                p_clear_values: clear_values.as_ptr(),
-            };
+        unsafe {
+            device.cmd_end_render_pass(command_buffer);
+        }
+    }
 
-            unsafe {
-                device.cmd_begin_render_pass(
-                    command_buffer,
-                    &render_pass_begin_info,
-                    vk::SubpassContents::INLINE,
-                );
-                device.cmd_bind_pipeline(
+    /// The command buffer of this frame, recorded again first if the recording mode asks for it.
+    fn prepare_command_buffer(&mut self, image_index: usize) -> vk::CommandBuffer {
+        let device = &self.device;
+        let graphics_pipeline = self.graphics_pipeline;
+        let framebuffers = &self.swapchain_framebuffers;
+        let render_pass = self.render_pass;
+        let swapchain_extent = self.swapchain_extent;
+        let vertex_buffer = self.vertex_buffer;
+        let index_buffer = self.index_buffer;
+        let pipeline_layout = self.pipeline_layout;
+        let descriptor_sets = &self.descriptor_sets;
+        let draw_list = &self.draw_list;
+
+        self.frame_command_buffers.command_buffer(
+            device,
+            self.current_frame,
+            image_index,
+            |command_buffer, image_index| {
+                VulkanApp29::record_command_buffer(
+                    device,
                     command_buffer,
-                    vk::PipelineBindPoint::GRAPHICS,
                     graphics_pipeline,
-                );
-
-                let vertex_buffers = [vertex_buffer];
-                let offsets = [0_u64];
-                let descriptor_sets_to_bind = [descriptor_sets[i]];
-
-                device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets);
-                device.cmd_bind_index_buffer(
-                    command_buffer,
+                    framebuffers[image_index],
+                    render_pass,
+                    swapchain_extent,
+                    vertex_buffer,
                     index_buffer,
-                    0,
-                    vk::IndexType::UINT32,
-                );
-                device.cmd_bind_descriptor_sets(
-                    command_buffer,
-                    vk::PipelineBindPoint::GRAPHICS,
                     pipeline_layout,
-                    0,
-                    &descriptor_sets_to_bind,
-                    &[],
-                );
-
-                device.cmd_draw_indexed(command_buffer, index_count, 1, 0, 0, 0);
+                    descriptor_sets[image_index],
+                    draw_list,
+                )
+            },
+        )
+    }
 
-                device.cmd_end_render_pass(command_buffer);
+    fn toggle_recording_mode(&mut self) {
+        let recording_mode = self.frame_command_buffers.mode().toggle();
+        self.frame_command_buffers.set_mode(recording_mode);
 
-                device
-                    .end_command_buffer(command_buffer)
-                    .expect("Failed to record Command Buffer at Ending!");
-            }
-        }
-
-        command_buffers
+        println!("Command buffer recording: {:?}", recording_mode);
     }
 
-    fn update_uniform_buffer(&mut self, current_image: usize, delta_time: f32) {
-        self.uniform_transform.model =
-            Matrix4::from_axis_angle(Vector3::new(0.0, 0.0, 1.0), Deg(90.0) * delta_time)
-                * self.uniform_transform.model;
+    fn update_uniform_buffer(&mut self, current_image: usize, alpha: f32) {
+        self.uniform_transform.model = Matrix4::from_angle_z(Deg(self.model_angle.value(alpha)));
 
         let ubos = [self.uniform_transform.clone()];
 
@@ -1045,6 +1085,69 @@ impl VulkanApp29 {
                 .unmap_memory(self.uniform_buffers_memory[current_image]);
         }
     }
+
+    fn select_pipeline_variant(&mut self) {
+        let device = &self.device;
+        let render_pass = self.render_pass;
+        let swapchain_extent = self.swapchain_extent;
+        let pipeline_layout = self.pipeline_layout;
+        let multisample_mode = self.multisample_mode;
+        self.graphics_pipeline =
+            self.pipeline_variants
+                .get_or_create(self.rasterization_mode, |mode| {
+                    VulkanApp29::create_graphics_pipeline(
+                        device,
+                        render_pass,
+                        swapchain_extent,
+                        pipeline_layout,
+                        &multisample_mode,
+                        mode,
+                    )
+                });
+    }
+
+    fn set_multisample_mode(&mut self, multisample_mode: MultisampleMode) {
+        if multisample_mode == self.multisample_mode {
+            return;
+        }
+
+        let is_sample_count_changed = multisample_mode.samples != self.multisample_mode.samples;
+        self.multisample_mode = multisample_mode;
+
+        if is_sample_count_changed {
+            // the color/depth attachments and the render pass depend on the sample count.
+            self.recreate_swapchain();
+        } else {
+            self.wait_device_idle();
+            self.pipeline_variants.destroy_pipelines(&self.device);
+            self.pipeline_variants.clear();
+            self.apply_rasterization_mode();
+        }
+
+        println!(
+            "MSAA: {}x, Sample shading: {}",
+            self.multisample_mode.samples.as_raw(),
+            if self.multisample_mode.is_sample_shading_enabled {
+                "on"
+            } else {
+                "off"
+            },
+        );
+    }
+
+    fn apply_rasterization_mode(&mut self) {
+        self.select_pipeline_variant();
+
+        // the pipeline is baked into the pre-recorded command buffers.
+        self.frame_command_buffers.invalidate();
+
+        println!(
+            "Polygon mode: {:?}, Cull mode: {:?}, Front face: {:?}",
+            self.rasterization_mode.polygon_mode,
+            self.rasterization_mode.cull_mode,
+            self.rasterization_mode.front_face,
+        );
+    }
 }
 
 impl Drop for VulkanApp29 {
@@ -1085,6 +1188,7 @@ impl Drop for VulkanApp29 {
             self.device
                 .destroy_descriptor_set_layout(self.ubo_layout, None);
 
+            self.frame_command_buffers.destroy(&self.device);
             self.device.destroy_command_pool(self.command_pool, None);
 
             self.device.destroy_device(None);
@@ -1100,7 +1204,11 @@ impl Drop for VulkanApp29 {
 }
 
 impl VulkanApp for VulkanApp29 {
-    fn draw_frame(&mut self, delta_time: f32) {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
         let wait_fences = [self.in_flight_fences[self.current_frame]];
 
         unsafe {
@@ -1128,7 +1236,8 @@ impl VulkanApp for VulkanApp29 {
             }
         };
 
-        self.update_uniform_buffer(image_index as usize, delta_time);
+        self.update_uniform_buffer(image_index as usize, alpha);
+        let command_buffer = self.prepare_command_buffer(image_index as usize);
 
         let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
         let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
@@ -1141,7 +1250,7 @@ impl VulkanApp for VulkanApp29 {
             p_wait_semaphores: wait_semaphores.as_ptr(),
             p_wait_dst_stage_mask: wait_stages.as_ptr(),
             command_buffer_count: 1,
-            p_command_buffers: &self.command_buffers[image_index as usize],
+            p_command_buffers: &command_buffer,
             signal_semaphore_count: signal_semaphores.len() as u32,
             p_signal_semaphores: signal_semaphores.as_ptr(),
         }];
@@ -1194,12 +1303,19 @@ impl VulkanApp for VulkanApp29 {
     }
 
     fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
         // parameters -------------
+        let window_size = self.window.inner_size();
         let surface_suff = SurfaceStuff {
             surface_loader: self.surface_loader.clone(),
             surface: self.surface,
-            screen_width: WINDOW_WIDTH,
-            screen_height: WINDOW_HEIGHT,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
         };
         // ------------------------
 
@@ -1234,82 +1350,54 @@ impl VulkanApp for VulkanApp29 {
             &self.device,
             self.physical_device,
             self.swapchain_format,
-            self.msaa_samples,
+            self.multisample_mode.samples,
         );
-        let (graphics_pipeline, pipeline_layout) = VulkanApp29::create_graphics_pipeline(
-            &self.device,
-            self.render_pass,
-            swapchain_stuff.swapchain_extent,
-            self.ubo_layout,
-            self.msaa_samples,
-        );
-        self.graphics_pipeline = graphics_pipeline;
-        self.pipeline_layout = pipeline_layout;
+        self.pipeline_layout = VulkanApp29::create_pipeline_layout(&self.device, self.ubo_layout);
+        // every cached variant was built against the old render pass.
+        self.pipeline_variants.clear();
+        self.select_pipeline_variant();
 
-        let color_resources = VulkanApp29::create_color_resources(
+        self.color_attachment = VulkanApp29::create_color_resources(
             &self.device,
             self.swapchain_format,
             self.swapchain_extent,
             &self.memory_properties,
-            self.msaa_samples,
+            self.multisample_mode.samples,
         );
-        self.color_image = color_resources.0;
-        self.color_image_view = color_resources.1;
-        self.color_image_memory = color_resources.2;
-
-        let depth_resources = share::v1::create_depth_resources(
+        self.depth_attachment = VulkanApp29::create_depth_resources(
             &self.instance,
             &self.device,
             self.physical_device,
-            self.command_pool,
-            self.graphics_queue,
             self.swapchain_extent,
             &self.memory_properties,
-            self.msaa_samples,
+            self.multisample_mode.samples,
         );
-        self.depth_image = depth_resources.0;
-        self.depth_image_view = depth_resources.1;
-        self.depth_image_memory = depth_resources.2;
 
         self.swapchain_framebuffers = VulkanApp29::create_framebuffers(
             &self.device,
             self.render_pass,
             &self.swapchain_imageviews,
-            self.depth_image_view,
-            self.color_image_view,
-            self.swapchain_extent,
-        );
-        self.command_buffers = VulkanApp29::create_command_buffers(
-            &self.device,
-            self.command_pool,
-            self.graphics_pipeline,
-            &self.swapchain_framebuffers,
-            self.render_pass,
+            self.depth_attachment.image_view,
+            self.color_attachment
+                .as_ref()
+                .map(|attachment| attachment.image_view),
             self.swapchain_extent,
-            self.vertex_buffer,
-            self.index_buffer,
-            self.pipeline_layout,
-            &self.descriptor_sets,
-            self.indices.len() as u32,
         );
+        self.frame_command_buffers
+            .resize(&self.device, self.swapchain_images.len());
     }
 
     fn cleanup_swapchain(&self) {
         unsafe {
-            self.device.destroy_image(self.depth_image, None);
-            self.device.destroy_image_view(self.depth_image_view, None);
-            self.device.free_memory(self.depth_image_memory, None);
-
-            self.device.destroy_image(self.color_image, None);
-            self.device.destroy_image_view(self.color_image_view, None);
-            self.device.free_memory(self.color_image_memory, None);
+            share::v1::destroy_transient_attachment(&self.device, &self.depth_attachment);
+            if let Some(color_attachment) = &self.color_attachment {
+                share::v1::destroy_transient_attachment(&self.device, color_attachment);
+            }
 
-            self.device
-                .free_command_buffers(self.command_pool, &self.command_buffers);
             for &framebuffer in self.swapchain_framebuffers.iter() {
                 self.device.destroy_framebuffer(framebuffer, None);
             }
-            self.device.destroy_pipeline(self.graphics_pipeline, None);
+            self.pipeline_variants.destroy_pipelines(&self.device);
             self.device
                 .destroy_pipeline_layout(self.pipeline_layout, None);
             self.device.destroy_render_pass(self.render_pass, None);
@@ -1336,11 +1424,46 @@ impl VulkanApp for VulkanApp29 {
     fn window_ref(&self) -> &winit::window::Window {
         &self.window
     }
+
+    fn register_actions(&mut self, actions: &mut ActionMap) {
+        render_state::register_actions(actions);
+    }
+
+    fn on_input(&mut self, actions: &ActionMap) {
+        if self
+            .rasterization_mode
+            .on_input(actions, self.is_fill_mode_non_solid_supported)
+        {
+            self.apply_rasterization_mode();
+        }
+    }
+
+    fn on_key_pressed(&mut self, key_code: VirtualKeyCode) {
+        match key_code {
+            | VirtualKeyCode::F4 => self.report_transient_memory(),
+            | VirtualKeyCode::F5 => {
+                let mut multisample_mode = self.multisample_mode;
+                multisample_mode.cycle_samples(self.supported_sample_counts);
+                self.set_multisample_mode(multisample_mode);
+            }
+            | VirtualKeyCode::F6 => {
+                if !self.is_sample_rate_shading_supported {
+                    println!("sampleRateShading is not supported, keep shading per pixel.");
+                }
+                let mut multisample_mode = self.multisample_mode;
+                multisample_mode.toggle_sample_shading(self.is_sample_rate_shading_supported);
+                self.set_multisample_mode(multisample_mode);
+            }
+            | VirtualKeyCode::F7 => self.toggle_recording_mode(),
+            | _ => (),
+        }
+    }
 }
 
 fn main() {
     let program_proc = ProgramProc::new();
     let vulkan_app = VulkanApp29::new(&program_proc.event_loop);
+    vulkan_app.report_transient_memory();
 
     program_proc.main_loop(vulkan_app);
 }
@@ -0,0 +1,1141 @@
+use vulkan_tutorial_rust::{
+    utility, // the mod define some fixed functions that have been learned before.
+    utility::constants::*,
+    utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
+    utility::gpu_profiler::GpuProfiler,
+    utility::post_process::{Fxaa, Gamma, PostProcessChain, Tonemap, Vignette},
+    utility::render_graph::{
+        AttachmentLoad, ImageDesc, ImageState, PassContext, PassHandle, PassKind, RenderGraph,
+    },
+    utility::share,
+    utility::structures::*,
+    utility::window::{ProgramProc, VulkanApp},
+};
+
+use ash::version::DeviceV1_0;
+use ash::version::InstanceV1_0;
+use ash::vk;
+use cgmath::{Deg, Matrix4, Point3, Vector3};
+use winit::event::VirtualKeyCode;
+
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+
+// Constants
+const WINDOW_TITLE: &str = "30.Post-Processing";
+const MODEL_PATH: &str = "assets/chalet.obj";
+const TEXTURE_PATH: &str = "assets/chalet.jpg";
+const HDR_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+const EXPOSURE_STEP: f32 = 1.25;
+const MAX_PROFILER_REGIONS: u32 = 16;
+
+struct VulkanApp30 {
+    window: winit::window::Window,
+
+    // vulkan stuff
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    surface_loader: ash::extensions::khr::Surface,
+    surface: vk::SurfaceKHR,
+    debug_utils_loader: ash::extensions::ext::DebugUtils,
+    debug_merssager: vk::DebugUtilsMessengerEXT,
+
+    physical_device: vk::PhysicalDevice,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    device: ash::Device,
+
+    queue_family: QueueFamilyIndices,
+    graphics_queue: vk::Queue,
+    present_queue: vk::Queue,
+
+    swapchain_loader: ash::extensions::khr::Swapchain,
+    swapchain: vk::SwapchainKHR,
+    swapchain_images: Vec<vk::Image>,
+    swapchain_format: vk::Format,
+    swapchain_extent: vk::Extent2D,
+    swapchain_imageviews: Vec<vk::ImageView>,
+
+    render_graph: RenderGraph,
+    scene_pass: PassHandle,
+    post_chain: PostProcessChain,
+    tonemap_index: usize,
+    exposure: f32,
+
+    ubo_layout: vk::DescriptorSetLayout,
+    pipeline_layout: vk::PipelineLayout,
+    graphics_pipeline: vk::Pipeline,
+
+    texture_image: vk::Image,
+    texture_image_view: vk::ImageView,
+    texture_sampler: vk::Sampler,
+    texture_image_memory: vk::DeviceMemory,
+
+    _vertices: Vec<VertexV3>,
+    indices: Vec<u32>,
+
+    vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+
+    uniform_transform: UniformBufferObject,
+    model_angle: InterpolatedValue,
+    uniform_buffers: Vec<vk::Buffer>,
+    uniform_buffers_memory: Vec<vk::DeviceMemory>,
+
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+
+    command_pool: vk::CommandPool,
+    command_buffers: Vec<vk::CommandBuffer>,
+    // one profiler slot per pre-recorded command buffer, none if timestamps are not supported.
+    gpu_profiler: Option<GpuProfiler>,
+
+    image_available_semaphores: Vec<vk::Semaphore>,
+    render_finished_semaphores: Vec<vk::Semaphore>,
+    in_flight_fences: Vec<vk::Fence>,
+    current_frame: usize,
+
+    is_framebuffer_resized: bool,
+}
+
+impl VulkanApp30 {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<()>) -> VulkanApp30 {
+        let window =
+            utility::window::init_window(event_loop, WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT);
+
+        // init vulkan stuff
+        let entry = ash::Entry::new().unwrap();
+        let instance = share::create_instance(
+            &entry,
+            WINDOW_TITLE,
+            VALIDATION.is_enable,
+            &VALIDATION.required_validation_layers.to_vec(),
+        );
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
+        let (debug_utils_loader, debug_merssager) =
+            setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
+        let physical_device =
+            share::pick_physical_device(&instance, &surface_stuff, &DEVICE_EXTENSIONS);
+        let physical_device_memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let (device, queue_family) = share::create_logical_device(
+            &instance,
+            physical_device,
+            &VALIDATION,
+            &DEVICE_EXTENSIONS,
+            &surface_stuff,
+        );
+        let graphics_queue =
+            unsafe { device.get_device_queue(queue_family.graphics_family.unwrap(), 0) };
+        let present_queue =
+            unsafe { device.get_device_queue(queue_family.present_family.unwrap(), 0) };
+        let swapchain_stuff = share::create_swapchain(
+            &instance,
+            &device,
+            physical_device,
+            &window,
+            &surface_stuff,
+            &queue_family,
+        );
+        let swapchain_imageviews = share::v1::create_image_views(
+            &device,
+            swapchain_stuff.swapchain_format,
+            &swapchain_stuff.swapchain_images,
+        );
+
+        let exposure = 1.0;
+        let mut post_chain = PostProcessChain::new(&device);
+        let tonemap_index = post_chain.push_effect(Box::new(Tonemap { exposure }));
+        post_chain.push_effect(Box::new(Fxaa::default()));
+        post_chain.push_effect(Box::new(Vignette::default()));
+        // an sRGB swapchain already encodes the output, only apply gamma to UNORM swapchains.
+        if !VulkanApp30::is_srgb_format(swapchain_stuff.swapchain_format) {
+            post_chain.push_effect(Box::new(Gamma::default()));
+        }
+
+        let (render_graph, scene_pass) = VulkanApp30::create_render_graph(
+            &instance,
+            &device,
+            physical_device,
+            &physical_device_memory_properties,
+            &swapchain_stuff.swapchain_images,
+            &swapchain_imageviews,
+            swapchain_stuff.swapchain_format,
+            swapchain_stuff.swapchain_extent,
+            &mut post_chain,
+        );
+        println!("Render graph: {:?}", render_graph);
+
+        let ubo_layout = share::v2::create_descriptor_set_layout(&device);
+        let pipeline_layout = VulkanApp30::create_pipeline_layout(&device, ubo_layout);
+        let graphics_pipeline = VulkanApp30::create_graphics_pipeline(
+            &device,
+            render_graph.render_pass(scene_pass).unwrap(),
+            swapchain_stuff.swapchain_extent,
+            pipeline_layout,
+        );
+        let command_pool = share::v1::create_command_pool(&device, &queue_family);
+        let (vertices, indices) = share::load_model(Path::new(MODEL_PATH));
+        let (texture_image, texture_image_memory) = share::v1::create_texture_image(
+            &device,
+            command_pool,
+            graphics_queue,
+            &physical_device_memory_properties,
+            Path::new(TEXTURE_PATH),
+        );
+        let texture_image_view = share::v1::create_texture_image_view(&device, texture_image, 1);
+        let texture_sampler = share::v1::create_texture_sampler(&device);
+        let (vertex_buffer, vertex_buffer_memory) = share::v1::create_vertex_buffer(
+            &device,
+            &physical_device_memory_properties,
+            command_pool,
+            graphics_queue,
+            &vertices,
+        );
+        let (index_buffer, index_buffer_memory) = share::v1::create_index_buffer(
+            &device,
+            &physical_device_memory_properties,
+            command_pool,
+            graphics_queue,
+            &indices,
+        );
+        let (uniform_buffers, uniform_buffers_memory) = share::v1::create_uniform_buffers(
+            &device,
+            &physical_device_memory_properties,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let descriptor_pool =
+            share::v2::create_descriptor_pool(&device, swapchain_stuff.swapchain_images.len());
+        let descriptor_sets = share::v2::create_descriptor_sets(
+            &device,
+            descriptor_pool,
+            ubo_layout,
+            &uniform_buffers,
+            texture_image_view,
+            texture_sampler,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let mut gpu_profiler = VulkanApp30::create_gpu_profiler(
+            &instance,
+            physical_device,
+            &device,
+            &queue_family,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let command_buffers = VulkanApp30::create_command_buffers(
+            &device,
+            command_pool,
+            gpu_profiler.as_mut(),
+            &render_graph,
+            scene_pass,
+            &post_chain,
+            graphics_pipeline,
+            vertex_buffer,
+            index_buffer,
+            pipeline_layout,
+            &descriptor_sets,
+            indices.len() as u32,
+        );
+        let sync_ojbects = share::v1::create_sync_objects(&device, MAX_FRAMES_IN_FLIGHT);
+
+        // cleanup(); the 'drop' function will take care of it.
+        VulkanApp30 {
+            // winit stuff
+            window,
+
+            // vulkan stuff
+            _entry: entry,
+            instance,
+            surface: surface_stuff.surface,
+            surface_loader: surface_stuff.surface_loader,
+            debug_utils_loader,
+            debug_merssager,
+
+            physical_device,
+            memory_properties: physical_device_memory_properties,
+            device,
+
+            queue_family,
+            graphics_queue,
+            present_queue,
+
+            swapchain_loader: swapchain_stuff.swapchain_loader,
+            swapchain: swapchain_stuff.swapchain,
+            swapchain_format: swapchain_stuff.swapchain_format,
+            swapchain_images: swapchain_stuff.swapchain_images,
+            swapchain_extent: swapchain_stuff.swapchain_extent,
+            swapchain_imageviews,
+
+            render_graph,
+            scene_pass,
+            post_chain,
+            tonemap_index,
+            exposure,
+
+            ubo_layout,
+            pipeline_layout,
+            graphics_pipeline,
+
+            texture_image,
+            texture_image_view,
+            texture_sampler,
+            texture_image_memory,
+
+            _vertices: vertices,
+            indices,
+
+            vertex_buffer,
+            vertex_buffer_memory,
+            index_buffer,
+            index_buffer_memory,
+
+            uniform_transform: UniformBufferObject {
+                model: Matrix4::from_angle_z(Deg(90.0)),
+                view: Matrix4::look_at(
+                    Point3::new(2.0, 2.0, 2.0),
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 1.0),
+                ),
+                proj: {
+                    let mut proj = cgmath::perspective(
+                        Deg(45.0),
+                        swapchain_stuff.swapchain_extent.width as f32
+                            / swapchain_stuff.swapchain_extent.height as f32,
+                        0.1,
+                        10.0,
+                    );
+                    proj[1][1] = -proj[1][1];
+                    proj
+                },
+            },
+            model_angle: InterpolatedValue::new(90.0),
+            uniform_buffers,
+            uniform_buffers_memory,
+
+            descriptor_pool,
+            descriptor_sets,
+
+            command_pool,
+            command_buffers,
+            gpu_profiler,
+
+            image_available_semaphores: sync_ojbects.image_available_semaphores,
+            render_finished_semaphores: sync_ojbects.render_finished_semaphores,
+            in_flight_fences: sync_ojbects.inflight_fences,
+            current_frame: 0,
+
+            is_framebuffer_resized: false,
+        }
+    }
+
+    fn is_srgb_format(format: vk::Format) -> bool {
+        matches!(
+            format,
+            vk::Format::B8G8R8A8_SRGB | vk::Format::R8G8B8A8_SRGB
+        )
+    }
+
+    /// The scene is drawn into an HDR image, then the post chain brings it to the swapchain.
+    #[allow(clippy::too_many_arguments)]
+    fn create_render_graph(
+        instance: &ash::Instance,
+        device: &ash::Device,
+        physical_device: vk::PhysicalDevice,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        swapchain_images: &[vk::Image],
+        swapchain_imageviews: &[vk::ImageView],
+        swapchain_format: vk::Format,
+        swapchain_extent: vk::Extent2D,
+        post_chain: &mut PostProcessChain,
+    ) -> (RenderGraph, PassHandle) {
+        let hdr_desc = ImageDesc {
+            format: HDR_FORMAT,
+            extent: swapchain_extent,
+            samples: vk::SampleCountFlags::TYPE_1,
+        };
+
+        let mut render_graph = RenderGraph::new();
+        let hdr_color = render_graph.create_image("hdr color", hdr_desc);
+        let depth = render_graph.create_image(
+            "depth",
+            ImageDesc {
+                format: share::find_depth_format(instance, physical_device),
+                ..hdr_desc
+            },
+        );
+        let swapchain_image = render_graph.import_image(
+            "swapchain",
+            swapchain_images,
+            swapchain_imageviews,
+            ImageDesc {
+                format: swapchain_format,
+                ..hdr_desc
+            },
+            ImageState::acquired_swapchain_image(),
+            vk::ImageLayout::PRESENT_SRC_KHR,
+        );
+
+        let scene_pass = render_graph
+            .add_pass("scene", PassKind::Graphics)
+            .color_attachment(
+                hdr_color,
+                AttachmentLoad::Clear(vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: [0.0, 0.0, 0.0, 1.0],
+                    },
+                }),
+            )
+            .depth_stencil_attachment(
+                depth,
+                AttachmentLoad::Clear(vk::ClearValue {
+                    depth_stencil: vk::ClearDepthStencilValue {
+                        depth: 1.0,
+                        stencil: 0,
+                    },
+                }),
+            )
+            .finish();
+        post_chain.add_passes(&mut render_graph, hdr_color, swapchain_image, hdr_desc);
+
+        render_graph.compile(device, device_memory_properties);
+        post_chain.create_pipelines(device, &render_graph);
+
+        (render_graph, scene_pass)
+    }
+
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        ubo_set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [ubo_set_layout];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: 0,
+            p_push_constant_ranges: ptr::null(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
+    fn create_graphics_pipeline(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        swapchain_extent: vk::Extent2D,
+        pipeline_layout: vk::PipelineLayout,
+    ) -> vk::Pipeline {
+        let vert_shader_module = share::create_shader_module(
+            device,
+            include_bytes!("../../shaders/spv/26-shader-depth.vert.spv").to_vec(),
+        );
+        let frag_shader_module = share::create_shader_module(
+            device,
+            include_bytes!("../../shaders/spv/26-shader-depth.frag.spv").to_vec(),
+        );
+
+        let main_function_name = CString::new("main").unwrap(); // the beginning function name in shader code.
+
+        let shader_stages = [
+            vk::PipelineShaderStageCreateInfo {
+                // Vertex Shader
+                s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineShaderStageCreateFlags::empty(),
+                module: vert_shader_module,
+                p_name: main_function_name.as_ptr(),
+                p_specialization_info: ptr::null(),
+                stage: vk::ShaderStageFlags::VERTEX,
+            },
+            vk::PipelineShaderStageCreateInfo {
+                // Fragment Shader
+                s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineShaderStageCreateFlags::empty(),
+                module: frag_shader_module,
+                p_name: main_function_name.as_ptr(),
+                p_specialization_info: ptr::null(),
+                stage: vk::ShaderStageFlags::FRAGMENT,
+            },
+        ];
+
+        let binding_description = VertexV3::get_binding_descriptions();
+        let attribute_description = VertexV3::get_attribute_descriptions();
+
+        let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+            vertex_attribute_description_count: attribute_description.len() as u32,
+            p_vertex_attribute_descriptions: attribute_description.as_ptr(),
+            vertex_binding_description_count: binding_description.len() as u32,
+            p_vertex_binding_descriptions: binding_description.as_ptr(),
+        };
+        let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
+            flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            primitive_restart_enable: vk::FALSE,
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        };
+
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: swapchain_extent.width as f32,
+            height: swapchain_extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        let scissors = [vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: swapchain_extent,
+        }];
+
+        let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VIEWPORT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineViewportStateCreateFlags::empty(),
+            scissor_count: scissors.len() as u32,
+            p_scissors: scissors.as_ptr(),
+            viewport_count: viewports.len() as u32,
+            p_viewports: viewports.as_ptr(),
+        };
+
+        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
+            depth_clamp_enable: vk::FALSE,
+            cull_mode: vk::CullModeFlags::BACK,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            polygon_mode: vk::PolygonMode::FILL,
+            rasterizer_discard_enable: vk::FALSE,
+            depth_bias_clamp: 0.0,
+            depth_bias_constant_factor: 0.0,
+            depth_bias_enable: vk::FALSE,
+            depth_bias_slope_factor: 0.0,
+        };
+
+        let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
+            flags: vk::PipelineMultisampleStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            sample_shading_enable: vk::FALSE,
+            min_sample_shading: 0.0,
+            p_sample_mask: ptr::null(),
+            alpha_to_one_enable: vk::FALSE,
+            alpha_to_coverage_enable: vk::FALSE,
+        };
+
+        let stencil_state = vk::StencilOpState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::KEEP,
+            depth_fail_op: vk::StencilOp::KEEP,
+            compare_op: vk::CompareOp::ALWAYS,
+            compare_mask: 0,
+            write_mask: 0,
+            reference: 0,
+        };
+
+        let depth_state_create_info = vk::PipelineDepthStencilStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
+            depth_test_enable: vk::TRUE,
+            depth_write_enable: vk::TRUE,
+            depth_compare_op: vk::CompareOp::LESS,
+            depth_bounds_test_enable: vk::FALSE,
+            stencil_test_enable: vk::FALSE,
+            front: stencil_state,
+            back: stencil_state,
+            max_depth_bounds: 1.0,
+            min_depth_bounds: 0.0,
+        };
+
+        let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::FALSE,
+            color_write_mask: vk::ColorComponentFlags::all(),
+            src_color_blend_factor: vk::BlendFactor::ONE,
+            dst_color_blend_factor: vk::BlendFactor::ZERO,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+            alpha_blend_op: vk::BlendOp::ADD,
+        }];
+
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineColorBlendStateCreateFlags::empty(),
+            logic_op_enable: vk::FALSE,
+            logic_op: vk::LogicOp::COPY,
+            attachment_count: color_blend_attachment_states.len() as u32,
+            p_attachments: color_blend_attachment_states.as_ptr(),
+            blend_constants: [0.0, 0.0, 0.0, 0.0],
+        };
+
+        let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
+            s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineCreateFlags::empty(),
+            stage_count: shader_stages.len() as u32,
+            p_stages: shader_stages.as_ptr(),
+            p_vertex_input_state: &vertex_input_state_create_info,
+            p_input_assembly_state: &vertex_input_assembly_state_info,
+            p_tessellation_state: ptr::null(),
+            p_viewport_state: &viewport_state_create_info,
+            p_rasterization_state: &rasterization_statue_create_info,
+            p_multisample_state: &multisample_state_create_info,
+            p_depth_stencil_state: &depth_state_create_info,
+            p_color_blend_state: &color_blend_state,
+            p_dynamic_state: ptr::null(),
+            layout: pipeline_layout,
+            render_pass,
+            subpass: 0,
+            base_pipeline_handle: vk::Pipeline::null(),
+            base_pipeline_index: -1,
+        }];
+
+        let graphics_pipelines = unsafe {
+            device
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    &graphic_pipeline_create_infos,
+                    None,
+                )
+                .expect("Failed to create Graphics Pipeline!.")
+        };
+
+        unsafe {
+            device.destroy_shader_module(vert_shader_module, None);
+            device.destroy_shader_module(frag_shader_module, None);
+        }
+
+        graphics_pipelines[0]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_command_buffers(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        mut gpu_profiler: Option<&mut GpuProfiler>,
+        render_graph: &RenderGraph,
+        scene_pass: PassHandle,
+        post_chain: &PostProcessChain,
+        graphics_pipeline: vk::Pipeline,
+        vertex_buffer: vk::Buffer,
+        index_buffer: vk::Buffer,
+        pipeline_layout: vk::PipelineLayout,
+        descriptor_sets: &[vk::DescriptorSet],
+        index_count: u32,
+    ) -> Vec<vk::CommandBuffer> {
+        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            command_buffer_count: descriptor_sets.len() as u32,
+            command_pool,
+            level: vk::CommandBufferLevel::PRIMARY,
+        };
+
+        let command_buffers = unsafe {
+            device
+                .allocate_command_buffers(&command_buffer_allocate_info)
+                .expect("Failed to allocate Command Buffers!")
+        };
+
+        for (i, &command_buffer) in command_buffers.iter().enumerate() {
+            let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+                s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+                p_next: ptr::null(),
+                p_inheritance_info: ptr::null(),
+                flags: vk::CommandBufferUsageFlags::SIMULTANEOUS_USE,
+            };
+
+            unsafe {
+                device
+                    .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+                    .expect("Failed to begin recording Command Buffer at beginning!");
+            }
+
+            let record_pass = |pass, context: &PassContext| {
+                if pass == scene_pass {
+                    let vertex_buffers = [vertex_buffer];
+                    let offsets = [0_u64];
+                    let descriptor_sets_to_bind = [descriptor_sets[i]];
+
+                    unsafe {
+                        device.cmd_bind_pipeline(
+                            context.command_buffer,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            graphics_pipeline,
+                        );
+                        device.cmd_bind_vertex_buffers(
+                            context.command_buffer,
+                            0,
+                            &vertex_buffers,
+                            &offsets,
+                        );
+                        device.cmd_bind_index_buffer(
+                            context.command_buffer,
+                            index_buffer,
+                            0,
+                            vk::IndexType::UINT32,
+                        );
+                        device.cmd_bind_descriptor_sets(
+                            context.command_buffer,
+                            vk::PipelineBindPoint::GRAPHICS,
+                            pipeline_layout,
+                            0,
+                            &descriptor_sets_to_bind,
+                            &[],
+                        );
+
+                        device.cmd_draw_indexed(context.command_buffer, index_count, 1, 0, 0, 0);
+                    }
+                } else {
+                    post_chain.record(pass, context);
+                }
+            };
+
+            // the graph begins and ends the render passes and puts the barriers in between.
+            match gpu_profiler.as_mut() {
+                Some(profiler) => {
+                    profiler.begin_frame(device, command_buffer, i);
+                    let frame_region = profiler.begin_region(device, command_buffer, i, "frame");
+                    render_graph.execute_profiled(
+                        device,
+                        command_buffer,
+                        i,
+                        profiler,
+                        i,
+                        record_pass,
+                    );
+                    profiler.end_region(device, command_buffer, i, frame_region);
+                }
+                None => render_graph.execute(device, command_buffer, i, record_pass),
+            }
+
+            unsafe {
+                device
+                    .end_command_buffer(command_buffer)
+                    .expect("Failed to record Command Buffer at Ending!");
+            }
+        }
+
+        command_buffers
+    }
+
+    fn update_uniform_buffer(&mut self, current_image: usize, alpha: f32) {
+        self.uniform_transform.model = Matrix4::from_angle_z(Deg(self.model_angle.value(alpha)));
+
+        let ubos = [self.uniform_transform];
+
+        let buffer_size = (std::mem::size_of::<UniformBufferObject>() * ubos.len()) as u64;
+
+        unsafe {
+            let data_ptr =
+                self.device
+                    .map_memory(
+                        self.uniform_buffers_memory[current_image],
+                        0,
+                        buffer_size,
+                        vk::MemoryMapFlags::empty(),
+                    )
+                    .expect("Failed to Map Memory") as *mut UniformBufferObject;
+
+            data_ptr.copy_from_nonoverlapping(ubos.as_ptr(), ubos.len());
+
+            self.device
+                .unmap_memory(self.uniform_buffers_memory[current_image]);
+        }
+    }
+
+    fn create_gpu_profiler(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        device: &ash::Device,
+        queue_family: &QueueFamilyIndices,
+        slot_count: usize,
+    ) -> Option<GpuProfiler> {
+        let gpu_profiler = GpuProfiler::new(
+            instance,
+            physical_device,
+            device,
+            queue_family.graphics_family.unwrap(),
+            slot_count,
+            MAX_PROFILER_REGIONS,
+        );
+        if gpu_profiler.is_none() {
+            println!("Timestamp queries are not supported, GPU timings are disabled.");
+        }
+
+        gpu_profiler
+    }
+
+    fn print_gpu_timings(&self) {
+        match self.gpu_profiler.as_ref().map(|profiler| profiler.latest()) {
+            Some(Some(timings)) => print!("{}", timings),
+            Some(None) => println!("GPU timings are not available yet."),
+            None => println!("Timestamp queries are not supported."),
+        }
+    }
+
+    fn rerecord_command_buffers(&mut self) {
+        unsafe {
+            self.device
+                .free_command_buffers(self.command_pool, &self.command_buffers);
+        }
+        self.command_buffers = VulkanApp30::create_command_buffers(
+            &self.device,
+            self.command_pool,
+            self.gpu_profiler.as_mut(),
+            &self.render_graph,
+            self.scene_pass,
+            &self.post_chain,
+            self.graphics_pipeline,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.pipeline_layout,
+            &self.descriptor_sets,
+            self.indices.len() as u32,
+        );
+    }
+
+    fn set_exposure(&mut self, exposure: f32) {
+        self.wait_device_idle();
+
+        self.exposure = exposure;
+        self.post_chain.replace_effect(
+            self.tonemap_index,
+            Box::new(Tonemap {
+                exposure: self.exposure,
+            }),
+        );
+        // the exposure is pushed as a constant in the pre-recorded command buffers.
+        self.rerecord_command_buffers();
+
+        println!("Exposure: {:.3}", self.exposure);
+    }
+
+    fn destroy_render_graph(&mut self) {
+        self.post_chain.destroy_pipelines(&self.device);
+        self.render_graph.destroy(&self.device);
+    }
+}
+
+impl Drop for VulkanApp30 {
+    fn drop(&mut self) {
+        unsafe {
+            for i in 0..MAX_FRAMES_IN_FLIGHT {
+                self.device
+                    .destroy_semaphore(self.image_available_semaphores[i], None);
+                self.device
+                    .destroy_semaphore(self.render_finished_semaphores[i], None);
+                self.device.destroy_fence(self.in_flight_fences[i], None);
+            }
+        }
+
+        self.destroy_render_graph();
+        self.cleanup_swapchain();
+        self.post_chain.destroy(&self.device);
+
+        unsafe {
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+
+            for i in 0..self.uniform_buffers.len() {
+                self.device.destroy_buffer(self.uniform_buffers[i], None);
+                self.device
+                    .free_memory(self.uniform_buffers_memory[i], None);
+            }
+
+            self.device.destroy_buffer(self.index_buffer, None);
+            self.device.free_memory(self.index_buffer_memory, None);
+
+            self.device.destroy_buffer(self.vertex_buffer, None);
+            self.device.free_memory(self.vertex_buffer_memory, None);
+
+            self.device.destroy_sampler(self.texture_sampler, None);
+            self.device
+                .destroy_image_view(self.texture_image_view, None);
+
+            self.device.destroy_image(self.texture_image, None);
+            self.device.free_memory(self.texture_image_memory, None);
+
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_descriptor_set_layout(self.ubo_layout, None);
+
+            if let Some(profiler) = self.gpu_profiler.as_ref() {
+                profiler.destroy(&self.device);
+            }
+            self.device.destroy_command_pool(self.command_pool, None);
+
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+
+            if VALIDATION.is_enable {
+                self.debug_utils_loader
+                    .destroy_debug_utils_messenger(self.debug_merssager, None);
+            }
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+impl VulkanApp for VulkanApp30 {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
+        let wait_fences = [self.in_flight_fences[self.current_frame]];
+
+        unsafe {
+            self.device
+                .wait_for_fences(&wait_fences, true, u64::MAX)
+                .expect("Failed to wait for Fence!");
+        }
+
+        let (image_index, _is_sub_optimal) = unsafe {
+            let result = self.swapchain_loader.acquire_next_image(
+                self.swapchain,
+                u64::MAX,
+                self.image_available_semaphores[self.current_frame],
+                vk::Fence::null(),
+            );
+            match result {
+                Ok(image_index) => image_index,
+                Err(vk_result) => match vk_result {
+                    vk::Result::ERROR_OUT_OF_DATE_KHR => {
+                        self.recreate_swapchain();
+                        return;
+                    }
+                    _ => panic!("Failed to acquire Swap Chain Image!"),
+                },
+            }
+        };
+
+        self.update_uniform_buffer(image_index as usize, alpha);
+        if let Some(profiler) = self.gpu_profiler.as_mut() {
+            // the results of the previous submission of this command buffer, if it is done.
+            profiler.collect(&self.device, image_index as usize);
+        }
+
+        let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
+        let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let signal_semaphores = [self.render_finished_semaphores[self.current_frame]];
+
+        let submit_infos = [vk::SubmitInfo {
+            s_type: vk::StructureType::SUBMIT_INFO,
+            p_next: ptr::null(),
+            wait_semaphore_count: wait_semaphores.len() as u32,
+            p_wait_semaphores: wait_semaphores.as_ptr(),
+            p_wait_dst_stage_mask: wait_stages.as_ptr(),
+            command_buffer_count: 1,
+            p_command_buffers: &self.command_buffers[image_index as usize],
+            signal_semaphore_count: signal_semaphores.len() as u32,
+            p_signal_semaphores: signal_semaphores.as_ptr(),
+        }];
+
+        unsafe {
+            self.device
+                .reset_fences(&wait_fences)
+                .expect("Failed to reset Fence!");
+
+            self.device
+                .queue_submit(
+                    self.graphics_queue,
+                    &submit_infos,
+                    self.in_flight_fences[self.current_frame],
+                )
+                .expect("Failed to execute queue submit.");
+        }
+
+        let swapchains = [self.swapchain];
+
+        let present_info = vk::PresentInfoKHR {
+            s_type: vk::StructureType::PRESENT_INFO_KHR,
+            p_next: ptr::null(),
+            wait_semaphore_count: 1,
+            p_wait_semaphores: signal_semaphores.as_ptr(),
+            swapchain_count: 1,
+            p_swapchains: swapchains.as_ptr(),
+            p_image_indices: &image_index,
+            p_results: ptr::null_mut(),
+        };
+
+        let result = unsafe {
+            self.swapchain_loader
+                .queue_present(self.present_queue, &present_info)
+        };
+
+        let is_resized = match result {
+            Ok(_) => self.is_framebuffer_resized,
+            Err(vk_result) => match vk_result {
+                vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR => true,
+                _ => panic!("Failed to execute queue present."),
+            },
+        };
+        if is_resized {
+            self.is_framebuffer_resized = false;
+            self.recreate_swapchain();
+        }
+
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+    }
+
+    fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
+        // parameters -------------
+        let window_size = self.window.inner_size();
+        let surface_suff = SurfaceStuff {
+            surface_loader: self.surface_loader.clone(),
+            surface: self.surface,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
+        };
+        // ------------------------
+
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+        // the graph owns the render passes and the images sized after the swapchain.
+        self.destroy_render_graph();
+        self.cleanup_swapchain();
+
+        let swapchain_stuff = share::create_swapchain(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            &self.window,
+            &surface_suff,
+            &self.queue_family,
+        );
+        self.swapchain_loader = swapchain_stuff.swapchain_loader;
+        self.swapchain = swapchain_stuff.swapchain;
+        self.swapchain_images = swapchain_stuff.swapchain_images;
+        self.swapchain_format = swapchain_stuff.swapchain_format;
+        self.swapchain_extent = swapchain_stuff.swapchain_extent;
+
+        self.swapchain_imageviews = share::v1::create_image_views(
+            &self.device,
+            self.swapchain_format,
+            &self.swapchain_images,
+        );
+
+        let (render_graph, scene_pass) = VulkanApp30::create_render_graph(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            &self.memory_properties,
+            &self.swapchain_images,
+            &self.swapchain_imageviews,
+            self.swapchain_format,
+            self.swapchain_extent,
+            &mut self.post_chain,
+        );
+        self.render_graph = render_graph;
+        self.scene_pass = scene_pass;
+
+        self.graphics_pipeline = VulkanApp30::create_graphics_pipeline(
+            &self.device,
+            self.render_graph.render_pass(self.scene_pass).unwrap(),
+            self.swapchain_extent,
+            self.pipeline_layout,
+        );
+        // the swapchain image count may have changed, and with it the number of slots.
+        if let Some(profiler) = self.gpu_profiler.take() {
+            profiler.destroy(&self.device);
+            self.gpu_profiler = VulkanApp30::create_gpu_profiler(
+                &self.instance,
+                self.physical_device,
+                &self.device,
+                &self.queue_family,
+                self.swapchain_images.len(),
+            );
+        }
+        self.command_buffers = VulkanApp30::create_command_buffers(
+            &self.device,
+            self.command_pool,
+            self.gpu_profiler.as_mut(),
+            &self.render_graph,
+            self.scene_pass,
+            &self.post_chain,
+            self.graphics_pipeline,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.pipeline_layout,
+            &self.descriptor_sets,
+            self.indices.len() as u32,
+        );
+    }
+
+    fn cleanup_swapchain(&self) {
+        unsafe {
+            self.device
+                .free_command_buffers(self.command_pool, &self.command_buffers);
+            self.device.destroy_pipeline(self.graphics_pipeline, None);
+            for &image_view in self.swapchain_imageviews.iter() {
+                self.device.destroy_image_view(image_view, None);
+            }
+            self.swapchain_loader
+                .destroy_swapchain(self.swapchain, None);
+        }
+    }
+
+    fn wait_device_idle(&self) {
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+    }
+
+    fn resize_framebuffer(&mut self) {
+        self.is_framebuffer_resized = true;
+    }
+
+    fn window_ref(&self) -> &winit::window::Window {
+        &self.window
+    }
+
+    fn on_key_pressed(&mut self, key_code: VirtualKeyCode) {
+        match key_code {
+            | VirtualKeyCode::Up => self.set_exposure(self.exposure * EXPOSURE_STEP),
+            | VirtualKeyCode::Down => self.set_exposure(self.exposure / EXPOSURE_STEP),
+            | VirtualKeyCode::F1 => self.print_gpu_timings(),
+            | _ => {}
+        }
+    }
+}
+
+fn main() {
+    let program_proc = ProgramProc::new();
+    let vulkan_app = VulkanApp30::new(&program_proc.event_loop);
+
+    program_proc.main_loop(vulkan_app);
+}
+// -------------------------------------------------------------------------------------------
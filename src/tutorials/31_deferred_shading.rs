@@ -0,0 +1,2114 @@
+use vulkan_tutorial_rust::{
+    utility, // the mod define some fixed functions that have been learned before.
+    utility::constants::*,
+    utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
+    utility::share,
+    utility::specialization::{self, SpecializationConstants},
+    utility::structures::*,
+    utility::window::{ProgramProc, VulkanApp},
+};
+
+use ash::version::DeviceV1_0;
+use ash::version::InstanceV1_0;
+use ash::vk;
+use cgmath::{Deg, Matrix4, Point3, Vector3, Vector4};
+use winit::event::VirtualKeyCode;
+
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+
+// Constants
+const WINDOW_TITLE: &str = "31.Deferred-Shading";
+const MODEL_PATH: &str = "assets/chalet.obj";
+const TEXTURE_PATH: &str = "assets/chalet.jpg";
+const MAX_LIGHTS: usize = 32;
+// sizes the light array of the lighting shaders, so it always matches `LightsBufferObject`.
+const MAX_LIGHTS_CONSTANT_ID: u32 = 0;
+const AMBIENT: f32 = 0.05;
+const POSITION_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+const NORMAL_FORMAT: vk::Format = vk::Format::R16G16B16A16_SFLOAT;
+const ALBEDO_FORMAT: vk::Format = vk::Format::R8G8B8A8_UNORM;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RenderPath {
+    Forward,
+    Deferred,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct PointLight {
+    // view space position, radius in w.
+    position_radius: [f32; 4],
+    color: [f32; 4],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct LightsBufferObject {
+    // x: light count, y: ambient
+    params: [f32; 4],
+    lights: [PointLight; MAX_LIGHTS],
+}
+
+/// The attachments the geometry subpass writes and the lighting subpass reads.
+struct GBuffer {
+    images: Vec<vk::Image>,
+    image_views: Vec<vk::ImageView>,
+    image_memories: Vec<vk::DeviceMemory>,
+}
+
+struct VulkanApp31 {
+    window: winit::window::Window,
+
+    // vulkan stuff
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    surface_loader: ash::extensions::khr::Surface,
+    surface: vk::SurfaceKHR,
+    debug_utils_loader: ash::extensions::ext::DebugUtils,
+    debug_merssager: vk::DebugUtilsMessengerEXT,
+
+    physical_device: vk::PhysicalDevice,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    device: ash::Device,
+
+    queue_family: QueueFamilyIndices,
+    graphics_queue: vk::Queue,
+    present_queue: vk::Queue,
+
+    swapchain_loader: ash::extensions::khr::Swapchain,
+    swapchain: vk::SwapchainKHR,
+    swapchain_images: Vec<vk::Image>,
+    swapchain_format: vk::Format,
+    swapchain_extent: vk::Extent2D,
+    swapchain_imageviews: Vec<vk::ImageView>,
+
+    render_path: RenderPath,
+
+    forward_render_pass: vk::RenderPass,
+    forward_framebuffers: Vec<vk::Framebuffer>,
+    forward_pipeline: vk::Pipeline,
+
+    deferred_render_pass: vk::RenderPass,
+    deferred_framebuffers: Vec<vk::Framebuffer>,
+    gbuffer_pipeline: vk::Pipeline,
+    lighting_pipeline: vk::Pipeline,
+    gbuffer: GBuffer,
+
+    scene_set_layout: vk::DescriptorSetLayout,
+    lighting_set_layout: vk::DescriptorSetLayout,
+    scene_pipeline_layout: vk::PipelineLayout,
+    lighting_pipeline_layout: vk::PipelineLayout,
+
+    depth_image: vk::Image,
+    depth_image_view: vk::ImageView,
+    depth_image_memory: vk::DeviceMemory,
+
+    texture_image: vk::Image,
+    texture_image_view: vk::ImageView,
+    texture_sampler: vk::Sampler,
+    texture_image_memory: vk::DeviceMemory,
+
+    _vertices: Vec<VertexV3>,
+    indices: Vec<u32>,
+
+    vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+
+    uniform_transform: UniformBufferObject,
+    model_angle: InterpolatedValue,
+    uniform_buffers: Vec<vk::Buffer>,
+    uniform_buffers_memory: Vec<vk::DeviceMemory>,
+    light_buffers: Vec<vk::Buffer>,
+    light_buffers_memory: Vec<vk::DeviceMemory>,
+    light_time: InterpolatedValue,
+
+    descriptor_pool: vk::DescriptorPool,
+    scene_descriptor_sets: Vec<vk::DescriptorSet>,
+    lighting_descriptor_sets: Vec<vk::DescriptorSet>,
+
+    command_pool: vk::CommandPool,
+    forward_command_buffers: Vec<vk::CommandBuffer>,
+    deferred_command_buffers: Vec<vk::CommandBuffer>,
+
+    image_available_semaphores: Vec<vk::Semaphore>,
+    render_finished_semaphores: Vec<vk::Semaphore>,
+    in_flight_fences: Vec<vk::Fence>,
+    current_frame: usize,
+
+    is_framebuffer_resized: bool,
+}
+
+impl VulkanApp31 {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<()>) -> VulkanApp31 {
+        let window =
+            utility::window::init_window(event_loop, WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT);
+
+        // init vulkan stuff
+        let entry = ash::Entry::new().unwrap();
+        let instance = share::create_instance(
+            &entry,
+            WINDOW_TITLE,
+            VALIDATION.is_enable,
+            &VALIDATION.required_validation_layers.to_vec(),
+        );
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
+        let (debug_utils_loader, debug_merssager) =
+            setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
+        let physical_device =
+            share::pick_physical_device(&instance, &surface_stuff, &DEVICE_EXTENSIONS);
+        let physical_device_memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let (device, queue_family) = share::create_logical_device(
+            &instance,
+            physical_device,
+            &VALIDATION,
+            &DEVICE_EXTENSIONS,
+            &surface_stuff,
+        );
+        let graphics_queue =
+            unsafe { device.get_device_queue(queue_family.graphics_family.unwrap(), 0) };
+        let present_queue =
+            unsafe { device.get_device_queue(queue_family.present_family.unwrap(), 0) };
+        let swapchain_stuff = share::create_swapchain(
+            &instance,
+            &device,
+            physical_device,
+            &window,
+            &surface_stuff,
+            &queue_family,
+        );
+        let swapchain_imageviews = share::v1::create_image_views(
+            &device,
+            swapchain_stuff.swapchain_format,
+            &swapchain_stuff.swapchain_images,
+        );
+        let depth_format = share::find_depth_format(&instance, physical_device);
+        let forward_render_pass = VulkanApp31::create_forward_render_pass(
+            &device,
+            swapchain_stuff.swapchain_format,
+            depth_format,
+        );
+        let deferred_render_pass = VulkanApp31::create_deferred_render_pass(
+            &device,
+            swapchain_stuff.swapchain_format,
+            depth_format,
+        );
+        let scene_set_layout = VulkanApp31::create_scene_set_layout(&device);
+        let lighting_set_layout = VulkanApp31::create_lighting_set_layout(&device);
+        let scene_pipeline_layout = VulkanApp31::create_pipeline_layout(&device, scene_set_layout);
+        let lighting_pipeline_layout =
+            VulkanApp31::create_pipeline_layout(&device, lighting_set_layout);
+        let (forward_pipeline, gbuffer_pipeline, lighting_pipeline) = VulkanApp31::create_pipelines(
+            &device,
+            forward_render_pass,
+            deferred_render_pass,
+            swapchain_stuff.swapchain_extent,
+            scene_pipeline_layout,
+            lighting_pipeline_layout,
+        );
+        let command_pool = share::v1::create_command_pool(&device, &queue_family);
+        let (depth_image, depth_image_view, depth_image_memory) = share::v1::create_depth_resources(
+            &instance,
+            &device,
+            physical_device,
+            command_pool,
+            graphics_queue,
+            swapchain_stuff.swapchain_extent,
+            &physical_device_memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        );
+        let gbuffer = VulkanApp31::create_gbuffer(
+            &device,
+            swapchain_stuff.swapchain_extent,
+            &physical_device_memory_properties,
+        );
+        let forward_framebuffers = VulkanApp31::create_framebuffers(
+            &device,
+            forward_render_pass,
+            &swapchain_imageviews,
+            &[depth_image_view],
+            swapchain_stuff.swapchain_extent,
+        );
+        let deferred_framebuffers = VulkanApp31::create_framebuffers(
+            &device,
+            deferred_render_pass,
+            &swapchain_imageviews,
+            &[
+                depth_image_view,
+                gbuffer.image_views[0],
+                gbuffer.image_views[1],
+                gbuffer.image_views[2],
+            ],
+            swapchain_stuff.swapchain_extent,
+        );
+        let (vertices, indices) = share::load_model(Path::new(MODEL_PATH));
+        let (texture_image, texture_image_memory) = share::v1::create_texture_image(
+            &device,
+            command_pool,
+            graphics_queue,
+            &physical_device_memory_properties,
+            Path::new(TEXTURE_PATH),
+        );
+        let texture_image_view = share::v1::create_texture_image_view(&device, texture_image, 1);
+        let texture_sampler = share::v1::create_texture_sampler(&device);
+        let (vertex_buffer, vertex_buffer_memory) = share::v1::create_vertex_buffer(
+            &device,
+            &physical_device_memory_properties,
+            command_pool,
+            graphics_queue,
+            &vertices,
+        );
+        let (index_buffer, index_buffer_memory) = share::v1::create_index_buffer(
+            &device,
+            &physical_device_memory_properties,
+            command_pool,
+            graphics_queue,
+            &indices,
+        );
+        let (uniform_buffers, uniform_buffers_memory) = share::v1::create_uniform_buffers(
+            &device,
+            &physical_device_memory_properties,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let (light_buffers, light_buffers_memory) = VulkanApp31::create_light_buffers(
+            &device,
+            &physical_device_memory_properties,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let descriptor_pool =
+            VulkanApp31::create_descriptor_pool(&device, swapchain_stuff.swapchain_images.len());
+        let scene_descriptor_sets = VulkanApp31::create_scene_descriptor_sets(
+            &device,
+            descriptor_pool,
+            scene_set_layout,
+            &uniform_buffers,
+            &light_buffers,
+            texture_image_view,
+            texture_sampler,
+        );
+        let lighting_descriptor_sets = VulkanApp31::create_lighting_descriptor_sets(
+            &device,
+            descriptor_pool,
+            lighting_set_layout,
+            &gbuffer,
+            &light_buffers,
+        );
+        let forward_command_buffers = VulkanApp31::create_forward_command_buffers(
+            &device,
+            command_pool,
+            forward_pipeline,
+            &forward_framebuffers,
+            forward_render_pass,
+            swapchain_stuff.swapchain_extent,
+            vertex_buffer,
+            index_buffer,
+            scene_pipeline_layout,
+            &scene_descriptor_sets,
+            indices.len() as u32,
+        );
+        let deferred_command_buffers = VulkanApp31::create_deferred_command_buffers(
+            &device,
+            command_pool,
+            gbuffer_pipeline,
+            lighting_pipeline,
+            &deferred_framebuffers,
+            deferred_render_pass,
+            swapchain_stuff.swapchain_extent,
+            vertex_buffer,
+            index_buffer,
+            scene_pipeline_layout,
+            lighting_pipeline_layout,
+            &scene_descriptor_sets,
+            &lighting_descriptor_sets,
+            indices.len() as u32,
+        );
+        let sync_ojbects = share::v1::create_sync_objects(&device, MAX_FRAMES_IN_FLIGHT);
+
+        // cleanup(); the 'drop' function will take care of it.
+        VulkanApp31 {
+            // winit stuff
+            window,
+
+            // vulkan stuff
+            _entry: entry,
+            instance,
+            surface: surface_stuff.surface,
+            surface_loader: surface_stuff.surface_loader,
+            debug_utils_loader,
+            debug_merssager,
+
+            physical_device,
+            memory_properties: physical_device_memory_properties,
+            device,
+
+            queue_family,
+            graphics_queue,
+            present_queue,
+
+            swapchain_loader: swapchain_stuff.swapchain_loader,
+            swapchain: swapchain_stuff.swapchain,
+            swapchain_format: swapchain_stuff.swapchain_format,
+            swapchain_images: swapchain_stuff.swapchain_images,
+            swapchain_extent: swapchain_stuff.swapchain_extent,
+            swapchain_imageviews,
+
+            render_path: RenderPath::Deferred,
+
+            forward_render_pass,
+            forward_framebuffers,
+            forward_pipeline,
+
+            deferred_render_pass,
+            deferred_framebuffers,
+            gbuffer_pipeline,
+            lighting_pipeline,
+            gbuffer,
+
+            scene_set_layout,
+            lighting_set_layout,
+            scene_pipeline_layout,
+            lighting_pipeline_layout,
+
+            depth_image,
+            depth_image_view,
+            depth_image_memory,
+
+            texture_image,
+            texture_image_view,
+            texture_sampler,
+            texture_image_memory,
+
+            _vertices: vertices,
+            indices,
+
+            vertex_buffer,
+            vertex_buffer_memory,
+            index_buffer,
+            index_buffer_memory,
+
+            uniform_transform: UniformBufferObject {
+                model: Matrix4::from_angle_z(Deg(90.0)),
+                view: Matrix4::look_at(
+                    Point3::new(2.0, 2.0, 2.0),
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 1.0),
+                ),
+                proj: VulkanApp31::create_projection(swapchain_stuff.swapchain_extent),
+            },
+            model_angle: InterpolatedValue::new(90.0),
+            uniform_buffers,
+            uniform_buffers_memory,
+            light_buffers,
+            light_buffers_memory,
+            light_time: InterpolatedValue::new(0.0),
+
+            descriptor_pool,
+            scene_descriptor_sets,
+            lighting_descriptor_sets,
+
+            command_pool,
+            forward_command_buffers,
+            deferred_command_buffers,
+
+            image_available_semaphores: sync_ojbects.image_available_semaphores,
+            render_finished_semaphores: sync_ojbects.render_finished_semaphores,
+            in_flight_fences: sync_ojbects.inflight_fences,
+            current_frame: 0,
+
+            is_framebuffer_resized: false,
+        }
+    }
+
+    fn create_projection(extent: vk::Extent2D) -> Matrix4<f32> {
+        let mut proj = cgmath::perspective(
+            Deg(45.0),
+            extent.width as f32 / extent.height as f32,
+            0.1,
+            10.0,
+        );
+        proj[1][1] = -proj[1][1];
+        proj
+    }
+
+    fn create_gbuffer(
+        device: &ash::Device,
+        swapchain_extent: vk::Extent2D,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    ) -> GBuffer {
+        let mut gbuffer = GBuffer {
+            images: vec![],
+            image_views: vec![],
+            image_memories: vec![],
+        };
+
+        for &format in [POSITION_FORMAT, NORMAL_FORMAT, ALBEDO_FORMAT].iter() {
+            // only read inside the render pass, the content never has to reach memory.
+            let (image, image_memory) = share::v1::create_image(
+                device,
+                swapchain_extent.width,
+                swapchain_extent.height,
+                1,
+                vk::SampleCountFlags::TYPE_1,
+                format,
+                vk::ImageTiling::OPTIMAL,
+                vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vk::ImageUsageFlags::INPUT_ATTACHMENT
+                    | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                device_memory_properties,
+            );
+            let image_view =
+                share::v1::create_image_view(device, image, format, vk::ImageAspectFlags::COLOR, 1);
+
+            gbuffer.images.push(image);
+            gbuffer.image_views.push(image_view);
+            gbuffer.image_memories.push(image_memory);
+        }
+
+        gbuffer
+    }
+
+    fn create_light_buffers(
+        device: &ash::Device,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        swapchain_image_count: usize,
+    ) -> (Vec<vk::Buffer>, Vec<vk::DeviceMemory>) {
+        let buffer_size = std::mem::size_of::<LightsBufferObject>();
+
+        let mut light_buffers = vec![];
+        let mut light_buffers_memory = vec![];
+
+        for _ in 0..swapchain_image_count {
+            let (light_buffer, light_buffer_memory) = share::create_buffer(
+                device,
+                buffer_size as u64,
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+                device_memory_properties,
+            );
+            light_buffers.push(light_buffer);
+            light_buffers_memory.push(light_buffer_memory);
+        }
+
+        (light_buffers, light_buffers_memory)
+    }
+
+    fn create_scene_set_layout(device: &ash::Device) -> vk::DescriptorSetLayout {
+        let bindings = [
+            vk::DescriptorSetLayoutBinding {
+                // transform uniform
+                binding: 0,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX,
+                p_immutable_samplers: ptr::null(),
+            },
+            vk::DescriptorSetLayoutBinding {
+                // albedo texture
+                binding: 1,
+                descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            },
+            vk::DescriptorSetLayoutBinding {
+                // albedo sampler
+                binding: 2,
+                descriptor_type: vk::DescriptorType::SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            },
+            vk::DescriptorSetLayoutBinding {
+                // lights uniform, only used by the forward path
+                binding: 3,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            },
+        ];
+
+        VulkanApp31::create_descriptor_set_layout(device, &bindings)
+    }
+
+    fn create_lighting_set_layout(device: &ash::Device) -> vk::DescriptorSetLayout {
+        let mut bindings = vec![];
+        for binding in 0..3 {
+            // position, normal and albedo of the G-buffer
+            bindings.push(vk::DescriptorSetLayoutBinding {
+                binding,
+                descriptor_type: vk::DescriptorType::INPUT_ATTACHMENT,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            });
+        }
+        bindings.push(vk::DescriptorSetLayoutBinding {
+            // lights uniform
+            binding: 3,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            p_immutable_samplers: ptr::null(),
+        });
+
+        VulkanApp31::create_descriptor_set_layout(device, &bindings)
+    }
+
+    fn create_descriptor_set_layout(
+        device: &ash::Device,
+        bindings: &[vk::DescriptorSetLayoutBinding],
+    ) -> vk::DescriptorSetLayout {
+        let layout_create_info = vk::DescriptorSetLayoutCreateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
+            binding_count: bindings.len() as u32,
+            p_bindings: bindings.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_descriptor_set_layout(&layout_create_info, None)
+                .expect("Failed to create Descriptor Set Layout!")
+        }
+    }
+
+    fn create_descriptor_pool(
+        device: &ash::Device,
+        swapchain_images_size: usize,
+    ) -> vk::DescriptorPool {
+        let set_count = swapchain_images_size as u32;
+        let pool_sizes = [
+            vk::DescriptorPoolSize {
+                // transform uniform and two lights uniforms
+                ty: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: set_count * 3,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLED_IMAGE,
+                descriptor_count: set_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLER,
+                descriptor_count: set_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::INPUT_ATTACHMENT,
+                descriptor_count: set_count * 3,
+            },
+        ];
+
+        let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_POOL_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::DescriptorPoolCreateFlags::empty(),
+            max_sets: set_count * 2,
+            pool_size_count: pool_sizes.len() as u32,
+            p_pool_sizes: pool_sizes.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_descriptor_pool(&descriptor_pool_create_info, None)
+                .expect("Failed to create Descriptor Pool!")
+        }
+    }
+
+    fn allocate_descriptor_sets(
+        device: &ash::Device,
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        count: usize,
+    ) -> Vec<vk::DescriptorSet> {
+        let layouts = vec![descriptor_set_layout; count];
+
+        let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            descriptor_pool,
+            descriptor_set_count: layouts.len() as u32,
+            p_set_layouts: layouts.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .allocate_descriptor_sets(&descriptor_set_allocate_info)
+                .expect("Failed to allocate descriptor sets!")
+        }
+    }
+
+    fn create_scene_descriptor_sets(
+        device: &ash::Device,
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        uniforms_buffers: &[vk::Buffer],
+        light_buffers: &[vk::Buffer],
+        texture_image_view: vk::ImageView,
+        texture_sampler: vk::Sampler,
+    ) -> Vec<vk::DescriptorSet> {
+        let descriptor_sets = VulkanApp31::allocate_descriptor_sets(
+            device,
+            descriptor_pool,
+            descriptor_set_layout,
+            uniforms_buffers.len(),
+        );
+
+        for (i, &descriptor_set) in descriptor_sets.iter().enumerate() {
+            let transform_buffer_infos = [vk::DescriptorBufferInfo {
+                buffer: uniforms_buffers[i],
+                offset: 0,
+                range: std::mem::size_of::<UniformBufferObject>() as u64,
+            }];
+            let light_buffer_infos = [vk::DescriptorBufferInfo {
+                buffer: light_buffers[i],
+                offset: 0,
+                range: std::mem::size_of::<LightsBufferObject>() as u64,
+            }];
+            let texture_infos = [vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view: texture_image_view,
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            }];
+            let sampler_infos = [vk::DescriptorImageInfo {
+                sampler: texture_sampler,
+                image_view: vk::ImageView::null(),
+                image_layout: vk::ImageLayout::UNDEFINED,
+            }];
+
+            let descriptor_write_sets = [
+                VulkanApp31::write_buffer(descriptor_set, 0, &transform_buffer_infos),
+                VulkanApp31::write_image(
+                    descriptor_set,
+                    1,
+                    vk::DescriptorType::SAMPLED_IMAGE,
+                    &texture_infos,
+                ),
+                VulkanApp31::write_image(
+                    descriptor_set,
+                    2,
+                    vk::DescriptorType::SAMPLER,
+                    &sampler_infos,
+                ),
+                VulkanApp31::write_buffer(descriptor_set, 3, &light_buffer_infos),
+            ];
+
+            unsafe {
+                device.update_descriptor_sets(&descriptor_write_sets, &[]);
+            }
+        }
+
+        descriptor_sets
+    }
+
+    fn create_lighting_descriptor_sets(
+        device: &ash::Device,
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        gbuffer: &GBuffer,
+        light_buffers: &[vk::Buffer],
+    ) -> Vec<vk::DescriptorSet> {
+        let descriptor_sets = VulkanApp31::allocate_descriptor_sets(
+            device,
+            descriptor_pool,
+            descriptor_set_layout,
+            light_buffers.len(),
+        );
+
+        let input_infos: Vec<[vk::DescriptorImageInfo; 1]> = gbuffer
+            .image_views
+            .iter()
+            .map(|&image_view| {
+                [vk::DescriptorImageInfo {
+                    sampler: vk::Sampler::null(),
+                    image_view,
+                    image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                }]
+            })
+            .collect();
+
+        for (i, &descriptor_set) in descriptor_sets.iter().enumerate() {
+            let light_buffer_infos = [vk::DescriptorBufferInfo {
+                buffer: light_buffers[i],
+                offset: 0,
+                range: std::mem::size_of::<LightsBufferObject>() as u64,
+            }];
+
+            let mut descriptor_write_sets: Vec<vk::WriteDescriptorSet> = input_infos
+                .iter()
+                .enumerate()
+                .map(|(binding, image_infos)| {
+                    VulkanApp31::write_image(
+                        descriptor_set,
+                        binding as u32,
+                        vk::DescriptorType::INPUT_ATTACHMENT,
+                        image_infos,
+                    )
+                })
+                .collect();
+            descriptor_write_sets.push(VulkanApp31::write_buffer(
+                descriptor_set,
+                3,
+                &light_buffer_infos,
+            ));
+
+            unsafe {
+                device.update_descriptor_sets(&descriptor_write_sets, &[]);
+            }
+        }
+
+        descriptor_sets
+    }
+
+    fn write_buffer(
+        dst_set: vk::DescriptorSet,
+        dst_binding: u32,
+        buffer_infos: &[vk::DescriptorBufferInfo],
+    ) -> vk::WriteDescriptorSet {
+        vk::WriteDescriptorSet {
+            s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+            p_next: ptr::null(),
+            dst_set,
+            dst_binding,
+            dst_array_element: 0,
+            descriptor_count: buffer_infos.len() as u32,
+            descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+            p_image_info: ptr::null(),
+            p_buffer_info: buffer_infos.as_ptr(),
+            p_texel_buffer_view: ptr::null(),
+        }
+    }
+
+    fn write_image(
+        dst_set: vk::DescriptorSet,
+        dst_binding: u32,
+        descriptor_type: vk::DescriptorType,
+        image_infos: &[vk::DescriptorImageInfo],
+    ) -> vk::WriteDescriptorSet {
+        vk::WriteDescriptorSet {
+            s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+            p_next: ptr::null(),
+            dst_set,
+            dst_binding,
+            dst_array_element: 0,
+            descriptor_count: image_infos.len() as u32,
+            descriptor_type,
+            p_image_info: image_infos.as_ptr(),
+            p_buffer_info: ptr::null(),
+            p_texel_buffer_view: ptr::null(),
+        }
+    }
+}
+
+// Fix content -------------------------------------------------------------------------------
+impl VulkanApp31 {
+    fn create_forward_render_pass(
+        device: &ash::Device,
+        surface_format: vk::Format,
+        depth_format: vk::Format,
+    ) -> vk::RenderPass {
+        let attachments = [
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format: surface_format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::STORE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+            },
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format: depth_format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::DONT_CARE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            },
+        ];
+
+        let color_attachment_ref = vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        };
+        let depth_attachment_ref = vk::AttachmentReference {
+            attachment: 1,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+
+        let subpasses = [vk::SubpassDescription {
+            flags: vk::SubpassDescriptionFlags::empty(),
+            pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+            input_attachment_count: 0,
+            p_input_attachments: ptr::null(),
+            color_attachment_count: 1,
+            p_color_attachments: &color_attachment_ref,
+            p_resolve_attachments: ptr::null(),
+            p_depth_stencil_attachment: &depth_attachment_ref,
+            preserve_attachment_count: 0,
+            p_preserve_attachments: ptr::null(),
+        }];
+
+        let subpass_dependencies = [VulkanApp31::external_dependency()];
+
+        VulkanApp31::create_render_pass(device, &attachments, &subpasses, &subpass_dependencies)
+    }
+
+    /// Subpass 0 fills the G-buffer, subpass 1 reads it as input attachments and does the lighting.
+    fn create_deferred_render_pass(
+        device: &ash::Device,
+        surface_format: vk::Format,
+        depth_format: vk::Format,
+    ) -> vk::RenderPass {
+        let gbuffer_attachment = |format| vk::AttachmentDescription {
+            flags: vk::AttachmentDescriptionFlags::empty(),
+            format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            // the G-buffer lives only inside this render pass.
+            store_op: vk::AttachmentStoreOp::DONT_CARE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        };
+
+        let attachments = [
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format: surface_format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::STORE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+            },
+            vk::AttachmentDescription {
+                flags: vk::AttachmentDescriptionFlags::empty(),
+                format: depth_format,
+                samples: vk::SampleCountFlags::TYPE_1,
+                load_op: vk::AttachmentLoadOp::CLEAR,
+                store_op: vk::AttachmentStoreOp::DONT_CARE,
+                stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+                stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+                initial_layout: vk::ImageLayout::UNDEFINED,
+                final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+            },
+            gbuffer_attachment(POSITION_FORMAT),
+            gbuffer_attachment(NORMAL_FORMAT),
+            gbuffer_attachment(ALBEDO_FORMAT),
+        ];
+
+        let gbuffer_write_refs = [
+            vk::AttachmentReference {
+                attachment: 2,
+                layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            },
+            vk::AttachmentReference {
+                attachment: 3,
+                layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            },
+            vk::AttachmentReference {
+                attachment: 4,
+                layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            },
+        ];
+        let depth_attachment_ref = vk::AttachmentReference {
+            attachment: 1,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+        let gbuffer_read_refs = [
+            vk::AttachmentReference {
+                attachment: 2,
+                layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            },
+            vk::AttachmentReference {
+                attachment: 3,
+                layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            },
+            vk::AttachmentReference {
+                attachment: 4,
+                layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            },
+        ];
+        let color_attachment_ref = vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        };
+
+        let subpasses = [
+            vk::SubpassDescription {
+                // geometry subpass
+                flags: vk::SubpassDescriptionFlags::empty(),
+                pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+                input_attachment_count: 0,
+                p_input_attachments: ptr::null(),
+                color_attachment_count: gbuffer_write_refs.len() as u32,
+                p_color_attachments: gbuffer_write_refs.as_ptr(),
+                p_resolve_attachments: ptr::null(),
+                p_depth_stencil_attachment: &depth_attachment_ref,
+                preserve_attachment_count: 0,
+                p_preserve_attachments: ptr::null(),
+            },
+            vk::SubpassDescription {
+                // lighting subpass
+                flags: vk::SubpassDescriptionFlags::empty(),
+                pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+                input_attachment_count: gbuffer_read_refs.len() as u32,
+                p_input_attachments: gbuffer_read_refs.as_ptr(),
+                color_attachment_count: 1,
+                p_color_attachments: &color_attachment_ref,
+                p_resolve_attachments: ptr::null(),
+                p_depth_stencil_attachment: ptr::null(),
+                preserve_attachment_count: 0,
+                p_preserve_attachments: ptr::null(),
+            },
+        ];
+
+        let subpass_dependencies = [
+            VulkanApp31::external_dependency(),
+            vk::SubpassDependency {
+                // the lighting subpass reads what the geometry subpass wrote at the same pixel.
+                src_subpass: 0,
+                dst_subpass: 1,
+                src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                dst_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+                dst_access_mask: vk::AccessFlags::INPUT_ATTACHMENT_READ,
+                dependency_flags: vk::DependencyFlags::BY_REGION,
+            },
+        ];
+
+        VulkanApp31::create_render_pass(device, &attachments, &subpasses, &subpass_dependencies)
+    }
+
+    fn external_dependency() -> vk::SubpassDependency {
+        vk::SubpassDependency {
+            src_subpass: vk::SUBPASS_EXTERNAL,
+            dst_subpass: 0,
+            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            src_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            dependency_flags: vk::DependencyFlags::empty(),
+        }
+    }
+
+    fn create_render_pass(
+        device: &ash::Device,
+        attachments: &[vk::AttachmentDescription],
+        subpasses: &[vk::SubpassDescription],
+        subpass_dependencies: &[vk::SubpassDependency],
+    ) -> vk::RenderPass {
+        let renderpass_create_info = vk::RenderPassCreateInfo {
+            s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
+            flags: vk::RenderPassCreateFlags::empty(),
+            p_next: ptr::null(),
+            attachment_count: attachments.len() as u32,
+            p_attachments: attachments.as_ptr(),
+            subpass_count: subpasses.len() as u32,
+            p_subpasses: subpasses.as_ptr(),
+            dependency_count: subpass_dependencies.len() as u32,
+            p_dependencies: subpass_dependencies.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_render_pass(&renderpass_create_info, None)
+                .expect("Failed to create render pass!")
+        }
+    }
+
+    /// One framebuffer per swapchain image, followed by the `shared_views` attachments.
+    fn create_framebuffers(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        swapchain_image_views: &[vk::ImageView],
+        shared_views: &[vk::ImageView],
+        swapchain_extent: vk::Extent2D,
+    ) -> Vec<vk::Framebuffer> {
+        let mut framebuffers = vec![];
+
+        for &image_view in swapchain_image_views.iter() {
+            let mut attachments = vec![image_view];
+            attachments.extend_from_slice(shared_views);
+
+            let framebuffer_create_info = vk::FramebufferCreateInfo {
+                s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::FramebufferCreateFlags::empty(),
+                render_pass,
+                attachment_count: attachments.len() as u32,
+                p_attachments: attachments.as_ptr(),
+                width: swapchain_extent.width,
+                height: swapchain_extent.height,
+                layers: 1,
+            };
+
+            let framebuffer = unsafe {
+                device
+                    .create_framebuffer(&framebuffer_create_info, None)
+                    .expect("Failed to create Framebuffer!")
+            };
+
+            framebuffers.push(framebuffer);
+        }
+
+        framebuffers
+    }
+
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [set_layout];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: 0,
+            p_push_constant_ranges: ptr::null(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
+    /// Returns the forward, G-buffer and lighting pipelines.
+    fn create_pipelines(
+        device: &ash::Device,
+        forward_render_pass: vk::RenderPass,
+        deferred_render_pass: vk::RenderPass,
+        swapchain_extent: vk::Extent2D,
+        scene_pipeline_layout: vk::PipelineLayout,
+        lighting_pipeline_layout: vk::PipelineLayout,
+    ) -> (vk::Pipeline, vk::Pipeline, vk::Pipeline) {
+        let scene_vert_code = include_bytes!("../../shaders/spv/31-scene.vert.spv");
+        let mut lighting_constants = SpecializationConstants::new();
+        lighting_constants.add_int(MAX_LIGHTS_CONSTANT_ID, MAX_LIGHTS as i32);
+
+        let forward_pipeline = VulkanApp31::create_graphics_pipeline(
+            device,
+            forward_render_pass,
+            0,
+            swapchain_extent,
+            scene_pipeline_layout,
+            scene_vert_code,
+            include_bytes!("../../shaders/spv/31-forward.frag.spv"),
+            &lighting_constants,
+            true,
+            1,
+        );
+        let gbuffer_pipeline = VulkanApp31::create_graphics_pipeline(
+            device,
+            deferred_render_pass,
+            0,
+            swapchain_extent,
+            scene_pipeline_layout,
+            scene_vert_code,
+            include_bytes!("../../shaders/spv/31-gbuffer.frag.spv"),
+            &SpecializationConstants::new(),
+            true,
+            3,
+        );
+        // the lighting runs once per pixel on a full-screen triangle.
+        let lighting_pipeline = VulkanApp31::create_graphics_pipeline(
+            device,
+            deferred_render_pass,
+            1,
+            swapchain_extent,
+            lighting_pipeline_layout,
+            include_bytes!("../../shaders/spv/30-post-fullscreen.vert.spv"),
+            include_bytes!("../../shaders/spv/31-deferred-lighting.frag.spv"),
+            &lighting_constants,
+            false,
+            1,
+        );
+
+        (forward_pipeline, gbuffer_pipeline, lighting_pipeline)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_graphics_pipeline(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        subpass: u32,
+        swapchain_extent: vk::Extent2D,
+        pipeline_layout: vk::PipelineLayout,
+        vert_shader_code: &[u8],
+        frag_shader_code: &[u8],
+        frag_constants: &SpecializationConstants,
+        is_scene_geometry: bool,
+        color_attachment_count: usize,
+    ) -> vk::Pipeline {
+        let vert_shader_module = share::create_shader_module(device, vert_shader_code.to_vec());
+        let frag_shader_module = share::create_shader_module(device, frag_shader_code.to_vec());
+
+        let main_function_name = CString::new("main").unwrap(); // the beginning function name in shader code.
+
+        frag_constants
+            .check_against(frag_shader_code)
+            .unwrap_or_else(|err| panic!("Failed to specialize the fragment shader: {}", err));
+        let frag_specialization_info = frag_constants.info();
+
+        let shader_stages = [
+            specialization::shader_stage_create_info(
+                vert_shader_module,
+                vk::ShaderStageFlags::VERTEX,
+                &main_function_name,
+                None,
+            ),
+            specialization::shader_stage_create_info(
+                frag_shader_module,
+                vk::ShaderStageFlags::FRAGMENT,
+                &main_function_name,
+                if frag_constants.is_empty() {
+                    None
+                } else {
+                    Some(&frag_specialization_info)
+                },
+            ),
+        ];
+
+        let binding_description = VertexV3::get_binding_descriptions();
+        let attribute_description = VertexV3::get_attribute_descriptions();
+
+        let vertex_input_state_create_info = if is_scene_geometry {
+            vk::PipelineVertexInputStateCreateInfo {
+                s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+                vertex_attribute_description_count: attribute_description.len() as u32,
+                p_vertex_attribute_descriptions: attribute_description.as_ptr(),
+                vertex_binding_description_count: binding_description.len() as u32,
+                p_vertex_binding_descriptions: binding_description.as_ptr(),
+            }
+        } else {
+            vk::PipelineVertexInputStateCreateInfo {
+                s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+                vertex_attribute_description_count: 0,
+                p_vertex_attribute_descriptions: ptr::null(),
+                vertex_binding_description_count: 0,
+                p_vertex_binding_descriptions: ptr::null(),
+            }
+        };
+        let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
+            flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            primitive_restart_enable: vk::FALSE,
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        };
+
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: swapchain_extent.width as f32,
+            height: swapchain_extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        let scissors = [vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: swapchain_extent,
+        }];
+
+        let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VIEWPORT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineViewportStateCreateFlags::empty(),
+            scissor_count: scissors.len() as u32,
+            p_scissors: scissors.as_ptr(),
+            viewport_count: viewports.len() as u32,
+            p_viewports: viewports.as_ptr(),
+        };
+
+        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
+            depth_clamp_enable: vk::FALSE,
+            cull_mode: if is_scene_geometry {
+                vk::CullModeFlags::BACK
+            } else {
+                vk::CullModeFlags::NONE
+            },
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            polygon_mode: vk::PolygonMode::FILL,
+            rasterizer_discard_enable: vk::FALSE,
+            depth_bias_clamp: 0.0,
+            depth_bias_constant_factor: 0.0,
+            depth_bias_enable: vk::FALSE,
+            depth_bias_slope_factor: 0.0,
+        };
+
+        let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
+            flags: vk::PipelineMultisampleStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            sample_shading_enable: vk::FALSE,
+            min_sample_shading: 0.0,
+            p_sample_mask: ptr::null(),
+            alpha_to_one_enable: vk::FALSE,
+            alpha_to_coverage_enable: vk::FALSE,
+        };
+
+        let stencil_state = vk::StencilOpState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::KEEP,
+            depth_fail_op: vk::StencilOp::KEEP,
+            compare_op: vk::CompareOp::ALWAYS,
+            compare_mask: 0,
+            write_mask: 0,
+            reference: 0,
+        };
+
+        let is_depth_enable = if is_scene_geometry {
+            vk::TRUE
+        } else {
+            vk::FALSE
+        };
+        let depth_state_create_info = vk::PipelineDepthStencilStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
+            depth_test_enable: is_depth_enable,
+            depth_write_enable: is_depth_enable,
+            depth_compare_op: vk::CompareOp::LESS,
+            depth_bounds_test_enable: vk::FALSE,
+            stencil_test_enable: vk::FALSE,
+            front: stencil_state,
+            back: stencil_state,
+            max_depth_bounds: 1.0,
+            min_depth_bounds: 0.0,
+        };
+
+        let color_blend_attachment_states = vec![
+            vk::PipelineColorBlendAttachmentState {
+                blend_enable: vk::FALSE,
+                color_write_mask: vk::ColorComponentFlags::all(),
+                src_color_blend_factor: vk::BlendFactor::ONE,
+                dst_color_blend_factor: vk::BlendFactor::ZERO,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ONE,
+                dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+                alpha_blend_op: vk::BlendOp::ADD,
+            };
+            color_attachment_count
+        ];
+
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineColorBlendStateCreateFlags::empty(),
+            logic_op_enable: vk::FALSE,
+            logic_op: vk::LogicOp::COPY,
+            attachment_count: color_blend_attachment_states.len() as u32,
+            p_attachments: color_blend_attachment_states.as_ptr(),
+            blend_constants: [0.0, 0.0, 0.0, 0.0],
+        };
+
+        let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
+            s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineCreateFlags::empty(),
+            stage_count: shader_stages.len() as u32,
+            p_stages: shader_stages.as_ptr(),
+            p_vertex_input_state: &vertex_input_state_create_info,
+            p_input_assembly_state: &vertex_input_assembly_state_info,
+            p_tessellation_state: ptr::null(),
+            p_viewport_state: &viewport_state_create_info,
+            p_rasterization_state: &rasterization_statue_create_info,
+            p_multisample_state: &multisample_state_create_info,
+            p_depth_stencil_state: &depth_state_create_info,
+            p_color_blend_state: &color_blend_state,
+            p_dynamic_state: ptr::null(),
+            layout: pipeline_layout,
+            render_pass,
+            subpass,
+            base_pipeline_handle: vk::Pipeline::null(),
+            base_pipeline_index: -1,
+        }];
+
+        let graphics_pipelines = unsafe {
+            device
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    &graphic_pipeline_create_infos,
+                    None,
+                )
+                .expect("Failed to create Graphics Pipeline!.")
+        };
+
+        unsafe {
+            device.destroy_shader_module(vert_shader_module, None);
+            device.destroy_shader_module(frag_shader_module, None);
+        }
+
+        graphics_pipelines[0]
+    }
+
+    fn allocate_command_buffers(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        count: usize,
+    ) -> Vec<vk::CommandBuffer> {
+        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            command_buffer_count: count as u32,
+            command_pool,
+            level: vk::CommandBufferLevel::PRIMARY,
+        };
+
+        unsafe {
+            device
+                .allocate_command_buffers(&command_buffer_allocate_info)
+                .expect("Failed to allocate Command Buffers!")
+        }
+    }
+
+    fn begin_render_pass(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        render_pass: vk::RenderPass,
+        framebuffer: vk::Framebuffer,
+        surface_extent: vk::Extent2D,
+        clear_values: &[vk::ClearValue],
+    ) {
+        let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+            p_next: ptr::null(),
+            p_inheritance_info: ptr::null(),
+            flags: vk::CommandBufferUsageFlags::SIMULTANEOUS_USE,
+        };
+
+        let render_pass_begin_info = vk::RenderPassBeginInfo {
+            s_type: vk::StructureType::RENDER_PASS_BEGIN_INFO,
+            p_next: ptr::null(),
+            render_pass,
+            framebuffer,
+            render_area: vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent: surface_extent,
+            },
+            clear_value_count: clear_values.len() as u32,
+            p_clear_values: clear_values.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+                .expect("Failed to begin recording Command Buffer at beginning!");
+            device.cmd_begin_render_pass(
+                command_buffer,
+                &render_pass_begin_info,
+                vk::SubpassContents::INLINE,
+            );
+        }
+    }
+
+    fn end_render_pass(device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            device.cmd_end_render_pass(command_buffer);
+            device
+                .end_command_buffer(command_buffer)
+                .expect("Failed to record Command Buffer at Ending!");
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_scene(
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        pipeline: vk::Pipeline,
+        vertex_buffer: vk::Buffer,
+        index_buffer: vk::Buffer,
+        pipeline_layout: vk::PipelineLayout,
+        descriptor_set: vk::DescriptorSet,
+        index_count: u32,
+    ) {
+        let vertex_buffers = [vertex_buffer];
+        let offsets = [0_u64];
+        let descriptor_sets_to_bind = [descriptor_set];
+
+        unsafe {
+            device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, pipeline);
+            device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets);
+            device.cmd_bind_index_buffer(command_buffer, index_buffer, 0, vk::IndexType::UINT32);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                pipeline_layout,
+                0,
+                &descriptor_sets_to_bind,
+                &[],
+            );
+
+            device.cmd_draw_indexed(command_buffer, index_count, 1, 0, 0, 0);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_forward_command_buffers(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        forward_pipeline: vk::Pipeline,
+        framebuffers: &[vk::Framebuffer],
+        render_pass: vk::RenderPass,
+        surface_extent: vk::Extent2D,
+        vertex_buffer: vk::Buffer,
+        index_buffer: vk::Buffer,
+        pipeline_layout: vk::PipelineLayout,
+        descriptor_sets: &[vk::DescriptorSet],
+        index_count: u32,
+    ) -> Vec<vk::CommandBuffer> {
+        let command_buffers =
+            VulkanApp31::allocate_command_buffers(device, command_pool, framebuffers.len());
+
+        let clear_values = [
+            vk::ClearValue {
+                // clear value for color buffer
+                color: vk::ClearColorValue {
+                    float32: [0.0, 0.0, 0.0, 1.0],
+                },
+            },
+            vk::ClearValue {
+                // clear value for depth buffer
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
+
+        for (i, &command_buffer) in command_buffers.iter().enumerate() {
+            VulkanApp31::begin_render_pass(
+                device,
+                command_buffer,
+                render_pass,
+                framebuffers[i],
+                surface_extent,
+                &clear_values,
+            );
+            VulkanApp31::draw_scene(
+                device,
+                command_buffer,
+                forward_pipeline,
+                vertex_buffer,
+                index_buffer,
+                pipeline_layout,
+                descriptor_sets[i],
+                index_count,
+            );
+            VulkanApp31::end_render_pass(device, command_buffer);
+        }
+
+        command_buffers
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_deferred_command_buffers(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        gbuffer_pipeline: vk::Pipeline,
+        lighting_pipeline: vk::Pipeline,
+        framebuffers: &[vk::Framebuffer],
+        render_pass: vk::RenderPass,
+        surface_extent: vk::Extent2D,
+        vertex_buffer: vk::Buffer,
+        index_buffer: vk::Buffer,
+        scene_pipeline_layout: vk::PipelineLayout,
+        lighting_pipeline_layout: vk::PipelineLayout,
+        scene_descriptor_sets: &[vk::DescriptorSet],
+        lighting_descriptor_sets: &[vk::DescriptorSet],
+        index_count: u32,
+    ) -> Vec<vk::CommandBuffer> {
+        let command_buffers =
+            VulkanApp31::allocate_command_buffers(device, command_pool, framebuffers.len());
+
+        let black = vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: [0.0, 0.0, 0.0, 0.0],
+            },
+        };
+        // position.w stays 0.0 where no geometry was drawn.
+        let clear_values = [
+            black,
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+            black,
+            black,
+            black,
+        ];
+
+        for (i, &command_buffer) in command_buffers.iter().enumerate() {
+            VulkanApp31::begin_render_pass(
+                device,
+                command_buffer,
+                render_pass,
+                framebuffers[i],
+                surface_extent,
+                &clear_values,
+            );
+            VulkanApp31::draw_scene(
+                device,
+                command_buffer,
+                gbuffer_pipeline,
+                vertex_buffer,
+                index_buffer,
+                scene_pipeline_layout,
+                scene_descriptor_sets[i],
+                index_count,
+            );
+
+            let descriptor_sets_to_bind = [lighting_descriptor_sets[i]];
+            unsafe {
+                device.cmd_next_subpass(command_buffer, vk::SubpassContents::INLINE);
+                device.cmd_bind_pipeline(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    lighting_pipeline,
+                );
+                device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    lighting_pipeline_layout,
+                    0,
+                    &descriptor_sets_to_bind,
+                    &[],
+                );
+                device.cmd_draw(command_buffer, 3, 1, 0, 0);
+            }
+
+            VulkanApp31::end_render_pass(device, command_buffer);
+        }
+
+        command_buffers
+    }
+
+    fn update_uniform_buffer(&mut self, current_image: usize, alpha: f32) {
+        self.uniform_transform.model = Matrix4::from_angle_z(Deg(self.model_angle.value(alpha)));
+
+        let ubos = [self.uniform_transform];
+
+        let buffer_size = (std::mem::size_of::<UniformBufferObject>() * ubos.len()) as u64;
+
+        unsafe {
+            let data_ptr =
+                self.device
+                    .map_memory(
+                        self.uniform_buffers_memory[current_image],
+                        0,
+                        buffer_size,
+                        vk::MemoryMapFlags::empty(),
+                    )
+                    .expect("Failed to Map Memory") as *mut UniformBufferObject;
+
+            data_ptr.copy_from_nonoverlapping(ubos.as_ptr(), ubos.len());
+
+            self.device
+                .unmap_memory(self.uniform_buffers_memory[current_image]);
+        }
+    }
+
+    /// Move the lights on rings around the model, the shaders light in view space.
+    fn update_light_buffer(&mut self, current_image: usize, alpha: f32) {
+        let light_time = self.light_time.value(alpha);
+
+        let mut lights_ubo = LightsBufferObject {
+            params: [MAX_LIGHTS as f32, AMBIENT, 0.0, 0.0],
+            lights: [PointLight {
+                position_radius: [0.0; 4],
+                color: [0.0; 4],
+            }; MAX_LIGHTS],
+        };
+
+        for (i, light) in lights_ubo.lights.iter_mut().enumerate() {
+            let ratio = i as f32 / MAX_LIGHTS as f32;
+            let angle = ratio * std::f32::consts::PI * 2.0 + light_time * (0.5 + ratio);
+            let ring_radius = 0.6 + 0.6 * ((i % 4) as f32 / 3.0);
+            let height = 0.1 + 0.5 * ((i % 3) as f32 / 2.0);
+
+            let world_position = Vector4::new(
+                ring_radius * angle.cos(),
+                ring_radius * angle.sin(),
+                height,
+                1.0,
+            );
+            let view_position = self.uniform_transform.view * world_position;
+
+            // spread the colors around the hue circle.
+            let hue = ratio * 6.0;
+            let color = [
+                (hue - 3.0).abs() - 1.0,
+                2.0 - (hue - 2.0).abs(),
+                2.0 - (hue - 4.0).abs(),
+            ];
+
+            light.position_radius = [view_position.x, view_position.y, view_position.z, 1.0];
+            light.color = [
+                color[0].clamp(0.0, 1.0),
+                color[1].clamp(0.0, 1.0),
+                color[2].clamp(0.0, 1.0),
+                1.0,
+            ];
+        }
+
+        let buffer_size = std::mem::size_of::<LightsBufferObject>() as u64;
+
+        unsafe {
+            let data_ptr =
+                self.device
+                    .map_memory(
+                        self.light_buffers_memory[current_image],
+                        0,
+                        buffer_size,
+                        vk::MemoryMapFlags::empty(),
+                    )
+                    .expect("Failed to Map Memory") as *mut LightsBufferObject;
+
+            data_ptr.copy_from_nonoverlapping(&lights_ubo, 1);
+
+            self.device
+                .unmap_memory(self.light_buffers_memory[current_image]);
+        }
+    }
+
+    fn destroy_gbuffer(&self) {
+        unsafe {
+            for i in 0..self.gbuffer.images.len() {
+                self.device
+                    .destroy_image_view(self.gbuffer.image_views[i], None);
+                self.device.destroy_image(self.gbuffer.images[i], None);
+                self.device
+                    .free_memory(self.gbuffer.image_memories[i], None);
+            }
+        }
+    }
+}
+
+impl Drop for VulkanApp31 {
+    fn drop(&mut self) {
+        unsafe {
+            for i in 0..MAX_FRAMES_IN_FLIGHT {
+                self.device
+                    .destroy_semaphore(self.image_available_semaphores[i], None);
+                self.device
+                    .destroy_semaphore(self.render_finished_semaphores[i], None);
+                self.device.destroy_fence(self.in_flight_fences[i], None);
+            }
+
+            self.cleanup_swapchain();
+
+            for i in 0..self.uniform_buffers.len() {
+                self.device.destroy_buffer(self.uniform_buffers[i], None);
+                self.device
+                    .free_memory(self.uniform_buffers_memory[i], None);
+                self.device.destroy_buffer(self.light_buffers[i], None);
+                self.device.free_memory(self.light_buffers_memory[i], None);
+            }
+
+            self.device.destroy_buffer(self.index_buffer, None);
+            self.device.free_memory(self.index_buffer_memory, None);
+
+            self.device.destroy_buffer(self.vertex_buffer, None);
+            self.device.free_memory(self.vertex_buffer_memory, None);
+
+            self.device.destroy_sampler(self.texture_sampler, None);
+            self.device
+                .destroy_image_view(self.texture_image_view, None);
+
+            self.device.destroy_image(self.texture_image, None);
+            self.device.free_memory(self.texture_image_memory, None);
+
+            self.device
+                .destroy_pipeline_layout(self.scene_pipeline_layout, None);
+            self.device
+                .destroy_pipeline_layout(self.lighting_pipeline_layout, None);
+            self.device
+                .destroy_descriptor_set_layout(self.scene_set_layout, None);
+            self.device
+                .destroy_descriptor_set_layout(self.lighting_set_layout, None);
+
+            self.device.destroy_command_pool(self.command_pool, None);
+
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+
+            if VALIDATION.is_enable {
+                self.debug_utils_loader
+                    .destroy_debug_utils_messenger(self.debug_merssager, None);
+            }
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+impl VulkanApp for VulkanApp31 {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+        self.light_time.advance(fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
+        let wait_fences = [self.in_flight_fences[self.current_frame]];
+
+        unsafe {
+            self.device
+                .wait_for_fences(&wait_fences, true, u64::MAX)
+                .expect("Failed to wait for Fence!");
+        }
+
+        let (image_index, _is_sub_optimal) = unsafe {
+            let result = self.swapchain_loader.acquire_next_image(
+                self.swapchain,
+                u64::MAX,
+                self.image_available_semaphores[self.current_frame],
+                vk::Fence::null(),
+            );
+            match result {
+                Ok(image_index) => image_index,
+                Err(vk_result) => match vk_result {
+                    vk::Result::ERROR_OUT_OF_DATE_KHR => {
+                        self.recreate_swapchain();
+                        return;
+                    }
+                    _ => panic!("Failed to acquire Swap Chain Image!"),
+                },
+            }
+        };
+
+        self.update_uniform_buffer(image_index as usize, alpha);
+        self.update_light_buffer(image_index as usize, alpha);
+
+        let command_buffer = match self.render_path {
+            RenderPath::Forward => self.forward_command_buffers[image_index as usize],
+            RenderPath::Deferred => self.deferred_command_buffers[image_index as usize],
+        };
+
+        let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
+        let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let signal_semaphores = [self.render_finished_semaphores[self.current_frame]];
+
+        let submit_infos = [vk::SubmitInfo {
+            s_type: vk::StructureType::SUBMIT_INFO,
+            p_next: ptr::null(),
+            wait_semaphore_count: wait_semaphores.len() as u32,
+            p_wait_semaphores: wait_semaphores.as_ptr(),
+            p_wait_dst_stage_mask: wait_stages.as_ptr(),
+            command_buffer_count: 1,
+            p_command_buffers: &command_buffer,
+            signal_semaphore_count: signal_semaphores.len() as u32,
+            p_signal_semaphores: signal_semaphores.as_ptr(),
+        }];
+
+        unsafe {
+            self.device
+                .reset_fences(&wait_fences)
+                .expect("Failed to reset Fence!");
+
+            self.device
+                .queue_submit(
+                    self.graphics_queue,
+                    &submit_infos,
+                    self.in_flight_fences[self.current_frame],
+                )
+                .expect("Failed to execute queue submit.");
+        }
+
+        let swapchains = [self.swapchain];
+
+        let present_info = vk::PresentInfoKHR {
+            s_type: vk::StructureType::PRESENT_INFO_KHR,
+            p_next: ptr::null(),
+            wait_semaphore_count: 1,
+            p_wait_semaphores: signal_semaphores.as_ptr(),
+            swapchain_count: 1,
+            p_swapchains: swapchains.as_ptr(),
+            p_image_indices: &image_index,
+            p_results: ptr::null_mut(),
+        };
+
+        let result = unsafe {
+            self.swapchain_loader
+                .queue_present(self.present_queue, &present_info)
+        };
+
+        let is_resized = match result {
+            Ok(_) => self.is_framebuffer_resized,
+            Err(vk_result) => match vk_result {
+                vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR => true,
+                _ => panic!("Failed to execute queue present."),
+            },
+        };
+        if is_resized {
+            self.is_framebuffer_resized = false;
+            self.recreate_swapchain();
+        }
+
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+    }
+
+    fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
+        // parameters -------------
+        let window_size = self.window.inner_size();
+        let surface_suff = SurfaceStuff {
+            surface_loader: self.surface_loader.clone(),
+            surface: self.surface,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
+        };
+        // ------------------------
+
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+        self.cleanup_swapchain();
+
+        let swapchain_stuff = share::create_swapchain(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            &self.window,
+            &surface_suff,
+            &self.queue_family,
+        );
+        self.swapchain_loader = swapchain_stuff.swapchain_loader;
+        self.swapchain = swapchain_stuff.swapchain;
+        self.swapchain_images = swapchain_stuff.swapchain_images;
+        self.swapchain_format = swapchain_stuff.swapchain_format;
+        self.swapchain_extent = swapchain_stuff.swapchain_extent;
+
+        self.swapchain_imageviews = share::v1::create_image_views(
+            &self.device,
+            self.swapchain_format,
+            &self.swapchain_images,
+        );
+        let depth_format = share::find_depth_format(&self.instance, self.physical_device);
+        self.forward_render_pass = VulkanApp31::create_forward_render_pass(
+            &self.device,
+            self.swapchain_format,
+            depth_format,
+        );
+        self.deferred_render_pass = VulkanApp31::create_deferred_render_pass(
+            &self.device,
+            self.swapchain_format,
+            depth_format,
+        );
+        let (forward_pipeline, gbuffer_pipeline, lighting_pipeline) = VulkanApp31::create_pipelines(
+            &self.device,
+            self.forward_render_pass,
+            self.deferred_render_pass,
+            self.swapchain_extent,
+            self.scene_pipeline_layout,
+            self.lighting_pipeline_layout,
+        );
+        self.forward_pipeline = forward_pipeline;
+        self.gbuffer_pipeline = gbuffer_pipeline;
+        self.lighting_pipeline = lighting_pipeline;
+
+        let depth_resources = share::v1::create_depth_resources(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            self.command_pool,
+            self.graphics_queue,
+            self.swapchain_extent,
+            &self.memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        );
+        self.depth_image = depth_resources.0;
+        self.depth_image_view = depth_resources.1;
+        self.depth_image_memory = depth_resources.2;
+        self.gbuffer = VulkanApp31::create_gbuffer(
+            &self.device,
+            self.swapchain_extent,
+            &self.memory_properties,
+        );
+
+        self.forward_framebuffers = VulkanApp31::create_framebuffers(
+            &self.device,
+            self.forward_render_pass,
+            &self.swapchain_imageviews,
+            &[self.depth_image_view],
+            self.swapchain_extent,
+        );
+        self.deferred_framebuffers = VulkanApp31::create_framebuffers(
+            &self.device,
+            self.deferred_render_pass,
+            &self.swapchain_imageviews,
+            &[
+                self.depth_image_view,
+                self.gbuffer.image_views[0],
+                self.gbuffer.image_views[1],
+                self.gbuffer.image_views[2],
+            ],
+            self.swapchain_extent,
+        );
+
+        // the lighting sets point at the G-buffer views, so all sets are rebuilt.
+        self.descriptor_pool =
+            VulkanApp31::create_descriptor_pool(&self.device, self.swapchain_images.len());
+        self.scene_descriptor_sets = VulkanApp31::create_scene_descriptor_sets(
+            &self.device,
+            self.descriptor_pool,
+            self.scene_set_layout,
+            &self.uniform_buffers,
+            &self.light_buffers,
+            self.texture_image_view,
+            self.texture_sampler,
+        );
+        self.lighting_descriptor_sets = VulkanApp31::create_lighting_descriptor_sets(
+            &self.device,
+            self.descriptor_pool,
+            self.lighting_set_layout,
+            &self.gbuffer,
+            &self.light_buffers,
+        );
+
+        self.uniform_transform.proj = VulkanApp31::create_projection(self.swapchain_extent);
+
+        self.forward_command_buffers = VulkanApp31::create_forward_command_buffers(
+            &self.device,
+            self.command_pool,
+            self.forward_pipeline,
+            &self.forward_framebuffers,
+            self.forward_render_pass,
+            self.swapchain_extent,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.scene_pipeline_layout,
+            &self.scene_descriptor_sets,
+            self.indices.len() as u32,
+        );
+        self.deferred_command_buffers = VulkanApp31::create_deferred_command_buffers(
+            &self.device,
+            self.command_pool,
+            self.gbuffer_pipeline,
+            self.lighting_pipeline,
+            &self.deferred_framebuffers,
+            self.deferred_render_pass,
+            self.swapchain_extent,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.scene_pipeline_layout,
+            self.lighting_pipeline_layout,
+            &self.scene_descriptor_sets,
+            &self.lighting_descriptor_sets,
+            self.indices.len() as u32,
+        );
+    }
+
+    fn cleanup_swapchain(&self) {
+        unsafe {
+            self.device
+                .free_command_buffers(self.command_pool, &self.forward_command_buffers);
+            self.device
+                .free_command_buffers(self.command_pool, &self.deferred_command_buffers);
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+
+            for &framebuffer in self
+                .forward_framebuffers
+                .iter()
+                .chain(self.deferred_framebuffers.iter())
+            {
+                self.device.destroy_framebuffer(framebuffer, None);
+            }
+
+            self.destroy_gbuffer();
+            self.device.destroy_image_view(self.depth_image_view, None);
+            self.device.destroy_image(self.depth_image, None);
+            self.device.free_memory(self.depth_image_memory, None);
+
+            self.device.destroy_pipeline(self.forward_pipeline, None);
+            self.device.destroy_pipeline(self.gbuffer_pipeline, None);
+            self.device.destroy_pipeline(self.lighting_pipeline, None);
+            self.device
+                .destroy_render_pass(self.forward_render_pass, None);
+            self.device
+                .destroy_render_pass(self.deferred_render_pass, None);
+            for &image_view in self.swapchain_imageviews.iter() {
+                self.device.destroy_image_view(image_view, None);
+            }
+            self.swapchain_loader
+                .destroy_swapchain(self.swapchain, None);
+        }
+    }
+
+    fn wait_device_idle(&self) {
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+    }
+
+    fn resize_framebuffer(&mut self) {
+        self.is_framebuffer_resized = true;
+    }
+
+    fn window_ref(&self) -> &winit::window::Window {
+        &self.window
+    }
+
+    fn on_key_pressed(&mut self, key_code: VirtualKeyCode) {
+        if key_code == VirtualKeyCode::F4 {
+            // both paths are pre-recorded, switching only picks the other command buffers.
+            self.render_path = match self.render_path {
+                RenderPath::Forward => RenderPath::Deferred,
+                RenderPath::Deferred => RenderPath::Forward,
+            };
+            println!("Render path: {:?}", self.render_path);
+        }
+    }
+}
+
+fn main() {
+    let program_proc = ProgramProc::new();
+    let vulkan_app = VulkanApp31::new(&program_proc.event_loop);
+
+    program_proc.main_loop(vulkan_app);
+}
+// -------------------------------------------------------------------------------------------
@@ -0,0 +1,1911 @@
+use vulkan_tutorial_rust::{
+    utility, // the mod define some fixed functions that have been learned before.
+    utility::constants::*,
+    utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
+    utility::queries::{PipelineStatistics, QueryManager},
+    utility::share,
+    utility::specialization::{self, SpecializationConstants},
+    utility::structures::*,
+    utility::window::{ProgramProc, VulkanApp},
+};
+
+use ash::version::DeviceV1_0;
+use ash::version::InstanceV1_0;
+use ash::vk;
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Vector3};
+use winit::event::VirtualKeyCode;
+
+use std::ffi::CString;
+use std::path::Path;
+use std::ptr;
+
+// Constants
+const WINDOW_TITLE: &str = "32.Shadow-Mapping";
+const MODEL_PATH: &str = "assets/chalet.obj";
+const TEXTURE_PATH: &str = "assets/chalet.jpg";
+const SHADOW_MAP_SIZE: u32 = 2048;
+const AMBIENT: f32 = 0.15;
+const CAMERA_POSITION: [f32; 3] = [2.0, 2.0, 2.0];
+const DIRECTIONAL_LIGHT_DIRECTION: [f32; 3] = [-1.0, -0.6, -1.4];
+const SPOT_LIGHT_POSITION: [f32; 3] = [1.4, -1.2, 1.8];
+const SPOT_INNER_ANGLE: f32 = 20.0;
+const SPOT_OUTER_ANGLE: f32 = 28.0;
+const DEPTH_BIAS_CONSTANT: f32 = 1.25;
+const DEPTH_BIAS_SLOPE: f32 = 1.75;
+const DEPTH_BIAS_STEP: f32 = 0.25;
+// the shadow pass and the scene pass.
+const STATISTICS_QUERY_COUNT: u32 = 2;
+// the model is the only object of the scene.
+const OCCLUSION_OBJECT_COUNT: u32 = 1;
+// `layout (constant_id = 0) const bool IS_PCF_ENABLED` in 32-scene.frag.
+const PCF_CONSTANT_ID: u32 = 0;
+const SCENE_FRAGMENT_SHADER: &[u8] = include_bytes!("../../shaders/spv/32-scene.frag.spv");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LightKind {
+    Directional,
+    Spot,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ShadowUniformBufferObject {
+    model: Matrix4<f32>,
+    view: Matrix4<f32>,
+    proj: Matrix4<f32>,
+    light_space: Matrix4<f32>,
+    camera_position: [f32; 4],
+    // xyz: world position of the spot light
+    light_position: [f32; 4],
+    // xyz: direction the light shines to, w: cos of the outer cone angle
+    light_direction: [f32; 4],
+    // x: ambient, y: unused, z: cos of the inner cone angle, w: 1.0 for spot lights
+    params: [f32; 4],
+}
+
+/// Fed to `vkCmdSetDepthBias` in the shadow pass, pushes the stored depth away from the light.
+#[derive(Debug, Clone, Copy)]
+struct DepthBias {
+    constant_factor: f32,
+    slope_factor: f32,
+}
+
+struct VulkanApp32 {
+    window: winit::window::Window,
+
+    // vulkan stuff
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    surface_loader: ash::extensions::khr::Surface,
+    surface: vk::SurfaceKHR,
+    debug_utils_loader: ash::extensions::ext::DebugUtils,
+    debug_merssager: vk::DebugUtilsMessengerEXT,
+
+    physical_device: vk::PhysicalDevice,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    device: ash::Device,
+
+    queue_family: QueueFamilyIndices,
+    graphics_queue: vk::Queue,
+    present_queue: vk::Queue,
+
+    swapchain_loader: ash::extensions::khr::Swapchain,
+    swapchain: vk::SwapchainKHR,
+    swapchain_images: Vec<vk::Image>,
+    swapchain_format: vk::Format,
+    swapchain_extent: vk::Extent2D,
+    swapchain_imageviews: Vec<vk::ImageView>,
+    swapchain_framebuffers: Vec<vk::Framebuffer>,
+
+    render_pass: vk::RenderPass,
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    pipeline_layout: vk::PipelineLayout,
+    graphics_pipeline: vk::Pipeline,
+
+    shadow_render_pass: vk::RenderPass,
+    shadow_pipeline: vk::Pipeline,
+    shadow_framebuffer: vk::Framebuffer,
+    shadow_map_image: vk::Image,
+    shadow_map_image_view: vk::ImageView,
+    shadow_map_image_memory: vk::DeviceMemory,
+    shadow_sampler: vk::Sampler,
+
+    light_kind: LightKind,
+    is_pcf_enabled: bool,
+    depth_bias: DepthBias,
+
+    depth_image: vk::Image,
+    depth_image_view: vk::ImageView,
+    depth_image_memory: vk::DeviceMemory,
+
+    texture_image: vk::Image,
+    texture_image_view: vk::ImageView,
+    texture_sampler: vk::Sampler,
+    texture_image_memory: vk::DeviceMemory,
+
+    _vertices: Vec<VertexV3>,
+    indices: Vec<u32>,
+
+    vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+
+    uniform_transform: ShadowUniformBufferObject,
+    model_angle: InterpolatedValue,
+    uniform_buffers: Vec<vk::Buffer>,
+    uniform_buffers_memory: Vec<vk::DeviceMemory>,
+
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+
+    command_pool: vk::CommandPool,
+    command_buffers: Vec<vk::CommandBuffer>,
+    // one query slot per pre-recorded command buffer.
+    query_manager: QueryManager,
+    pass_statistics: Vec<(String, PipelineStatistics)>,
+    samples_passed: Vec<u64>,
+
+    image_available_semaphores: Vec<vk::Semaphore>,
+    render_finished_semaphores: Vec<vk::Semaphore>,
+    in_flight_fences: Vec<vk::Fence>,
+    current_frame: usize,
+
+    is_framebuffer_resized: bool,
+}
+
+impl VulkanApp32 {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<()>) -> VulkanApp32 {
+        let window =
+            utility::window::init_window(event_loop, WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT);
+
+        // init vulkan stuff
+        let entry = ash::Entry::new().unwrap();
+        let instance = share::create_instance(
+            &entry,
+            WINDOW_TITLE,
+            VALIDATION.is_enable,
+            &VALIDATION.required_validation_layers.to_vec(),
+        );
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
+        let (debug_utils_loader, debug_merssager) =
+            setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
+        let physical_device =
+            share::pick_physical_device(&instance, &surface_stuff, &DEVICE_EXTENSIONS);
+        let physical_device_memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let (device, queue_family) = share::create_logical_device(
+            &instance,
+            physical_device,
+            &VALIDATION,
+            &DEVICE_EXTENSIONS,
+            &surface_stuff,
+        );
+        let graphics_queue =
+            unsafe { device.get_device_queue(queue_family.graphics_family.unwrap(), 0) };
+        let present_queue =
+            unsafe { device.get_device_queue(queue_family.present_family.unwrap(), 0) };
+        let swapchain_stuff = share::create_swapchain(
+            &instance,
+            &device,
+            physical_device,
+            &window,
+            &surface_stuff,
+            &queue_family,
+        );
+        let swapchain_imageviews = share::v1::create_image_views(
+            &device,
+            swapchain_stuff.swapchain_format,
+            &swapchain_stuff.swapchain_images,
+        );
+        let depth_format = share::find_depth_format(&instance, physical_device);
+        let render_pass = VulkanApp32::create_render_pass(
+            &device,
+            swapchain_stuff.swapchain_format,
+            depth_format,
+        );
+        let (shadow_format, shadow_filter) =
+            VulkanApp32::find_shadow_map_format(&instance, physical_device);
+        let shadow_render_pass = VulkanApp32::create_shadow_render_pass(&device, shadow_format);
+        let descriptor_set_layout = VulkanApp32::create_descriptor_set_layout(&device);
+        let pipeline_layout = VulkanApp32::create_pipeline_layout(&device, descriptor_set_layout);
+        let is_pcf_enabled = true;
+        let graphics_pipeline = VulkanApp32::create_graphics_pipeline(
+            &device,
+            render_pass,
+            swapchain_stuff.swapchain_extent,
+            pipeline_layout,
+            false,
+            is_pcf_enabled,
+        );
+        let shadow_pipeline = VulkanApp32::create_graphics_pipeline(
+            &device,
+            shadow_render_pass,
+            vk::Extent2D {
+                width: SHADOW_MAP_SIZE,
+                height: SHADOW_MAP_SIZE,
+            },
+            pipeline_layout,
+            true,
+            // the shadow pass has no fragment stage to specialize.
+            false,
+        );
+        let command_pool = share::v1::create_command_pool(&device, &queue_family);
+        let (depth_image, depth_image_view, depth_image_memory) = share::v1::create_depth_resources(
+            &instance,
+            &device,
+            physical_device,
+            command_pool,
+            graphics_queue,
+            swapchain_stuff.swapchain_extent,
+            &physical_device_memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        );
+        let (shadow_map_image, shadow_map_image_view, shadow_map_image_memory) =
+            VulkanApp32::create_shadow_map(
+                &device,
+                shadow_format,
+                &physical_device_memory_properties,
+            );
+        let shadow_framebuffer = VulkanApp32::create_shadow_framebuffer(
+            &device,
+            shadow_render_pass,
+            shadow_map_image_view,
+        );
+        let shadow_sampler = share::v1::create_shadow_sampler(&device, shadow_filter);
+        let swapchain_framebuffers = VulkanApp32::create_framebuffers(
+            &device,
+            render_pass,
+            &swapchain_imageviews,
+            depth_image_view,
+            swapchain_stuff.swapchain_extent,
+        );
+        let (vertices, indices) = share::load_model(Path::new(MODEL_PATH));
+        let (texture_image, texture_image_memory) = share::v1::create_texture_image(
+            &device,
+            command_pool,
+            graphics_queue,
+            &physical_device_memory_properties,
+            Path::new(TEXTURE_PATH),
+        );
+        let texture_image_view = share::v1::create_texture_image_view(&device, texture_image, 1);
+        let texture_sampler = share::v1::create_texture_sampler(&device);
+        let (vertex_buffer, vertex_buffer_memory) = share::v1::create_vertex_buffer(
+            &device,
+            &physical_device_memory_properties,
+            command_pool,
+            graphics_queue,
+            &vertices,
+        );
+        let (index_buffer, index_buffer_memory) = share::v1::create_index_buffer(
+            &device,
+            &physical_device_memory_properties,
+            command_pool,
+            graphics_queue,
+            &indices,
+        );
+        let (uniform_buffers, uniform_buffers_memory) = VulkanApp32::create_uniform_buffers(
+            &device,
+            &physical_device_memory_properties,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let descriptor_pool =
+            VulkanApp32::create_descriptor_pool(&device, swapchain_stuff.swapchain_images.len());
+        let descriptor_sets = VulkanApp32::create_descriptor_sets(
+            &device,
+            descriptor_pool,
+            descriptor_set_layout,
+            &uniform_buffers,
+            texture_image_view,
+            texture_sampler,
+            shadow_map_image_view,
+            shadow_sampler,
+        );
+        let depth_bias = DepthBias {
+            constant_factor: DEPTH_BIAS_CONSTANT,
+            slope_factor: DEPTH_BIAS_SLOPE,
+        };
+        let mut query_manager = VulkanApp32::create_query_manager(
+            &instance,
+            physical_device,
+            &device,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let command_buffers = VulkanApp32::create_command_buffers(
+            &device,
+            command_pool,
+            &mut query_manager,
+            graphics_pipeline,
+            shadow_pipeline,
+            &swapchain_framebuffers,
+            shadow_framebuffer,
+            render_pass,
+            shadow_render_pass,
+            swapchain_stuff.swapchain_extent,
+            vertex_buffer,
+            index_buffer,
+            pipeline_layout,
+            &descriptor_sets,
+            indices.len() as u32,
+            depth_bias,
+        );
+        let sync_ojbects = share::v1::create_sync_objects(&device, MAX_FRAMES_IN_FLIGHT);
+
+        let camera_position = Point3::from(CAMERA_POSITION);
+
+        // cleanup(); the 'drop' function will take care of it.
+        VulkanApp32 {
+            // winit stuff
+            window,
+
+            // vulkan stuff
+            _entry: entry,
+            instance,
+            surface: surface_stuff.surface,
+            surface_loader: surface_stuff.surface_loader,
+            debug_utils_loader,
+            debug_merssager,
+
+            physical_device,
+            memory_properties: physical_device_memory_properties,
+            device,
+
+            queue_family,
+            graphics_queue,
+            present_queue,
+
+            swapchain_loader: swapchain_stuff.swapchain_loader,
+            swapchain: swapchain_stuff.swapchain,
+            swapchain_format: swapchain_stuff.swapchain_format,
+            swapchain_images: swapchain_stuff.swapchain_images,
+            swapchain_extent: swapchain_stuff.swapchain_extent,
+            swapchain_imageviews,
+            swapchain_framebuffers,
+
+            descriptor_set_layout,
+            pipeline_layout,
+            render_pass,
+            graphics_pipeline,
+
+            shadow_render_pass,
+            shadow_pipeline,
+            shadow_framebuffer,
+            shadow_map_image,
+            shadow_map_image_view,
+            shadow_map_image_memory,
+            shadow_sampler,
+
+            light_kind: LightKind::Directional,
+            is_pcf_enabled,
+            depth_bias,
+
+            depth_image,
+            depth_image_view,
+            depth_image_memory,
+
+            texture_image,
+            texture_image_view,
+            texture_sampler,
+            texture_image_memory,
+
+            _vertices: vertices,
+            indices,
+
+            vertex_buffer,
+            vertex_buffer_memory,
+            index_buffer,
+            index_buffer_memory,
+
+            uniform_transform: ShadowUniformBufferObject {
+                model: Matrix4::from_angle_z(Deg(90.0)),
+                view: Matrix4::look_at(
+                    camera_position,
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 1.0),
+                ),
+                proj: VulkanApp32::create_projection(swapchain_stuff.swapchain_extent),
+                light_space: Matrix4::from_scale(1.0),
+                camera_position: [camera_position.x, camera_position.y, camera_position.z, 1.0],
+                light_position: [0.0; 4],
+                light_direction: [0.0; 4],
+                params: [0.0; 4],
+            },
+            model_angle: InterpolatedValue::new(90.0),
+            uniform_buffers,
+            uniform_buffers_memory,
+
+            descriptor_pool,
+            descriptor_sets,
+
+            command_pool,
+            command_buffers,
+            query_manager,
+            pass_statistics: vec![],
+            samples_passed: vec![],
+
+            image_available_semaphores: sync_ojbects.image_available_semaphores,
+            render_finished_semaphores: sync_ojbects.render_finished_semaphores,
+            in_flight_fences: sync_ojbects.inflight_fences,
+            current_frame: 0,
+
+            is_framebuffer_resized: false,
+        }
+    }
+
+    fn create_projection(extent: vk::Extent2D) -> Matrix4<f32> {
+        let mut proj = cgmath::perspective(
+            Deg(45.0),
+            extent.width as f32 / extent.height as f32,
+            0.1,
+            10.0,
+        );
+        proj[1][1] = -proj[1][1];
+        proj
+    }
+
+    /// The light matrices are sampled against a real depth buffer, so unlike the camera
+    /// projection they also have to map OpenGL's [-1, 1] depth range to Vulkan's [0, 1].
+    #[rustfmt::skip]
+    fn vulkan_clip_correction() -> Matrix4<f32> {
+        Matrix4::new(
+            1.0,  0.0, 0.0, 0.0,
+            0.0, -1.0, 0.0, 0.0,
+            0.0,  0.0, 0.5, 0.0,
+            0.0,  0.0, 0.5, 1.0,
+        )
+    }
+
+    /// Prefer a format the sampler may filter linearly, which adds free 2x2 PCF per tap.
+    fn find_shadow_map_format(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+    ) -> (vk::Format, vk::Filter) {
+        let format = share::find_supported_format(
+            instance,
+            physical_device,
+            &[vk::Format::D32_SFLOAT, vk::Format::D16_UNORM],
+            vk::ImageTiling::OPTIMAL,
+            vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT
+                | vk::FormatFeatureFlags::SAMPLED_IMAGE,
+        );
+
+        let format_properties =
+            unsafe { instance.get_physical_device_format_properties(physical_device, format) };
+        let filter = if format_properties
+            .optimal_tiling_features
+            .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+        {
+            vk::Filter::LINEAR
+        } else {
+            vk::Filter::NEAREST
+        };
+
+        (format, filter)
+    }
+
+    fn create_shadow_map(
+        device: &ash::Device,
+        shadow_format: vk::Format,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    ) -> (vk::Image, vk::ImageView, vk::DeviceMemory) {
+        let (shadow_map_image, shadow_map_image_memory) = share::v1::create_image(
+            device,
+            SHADOW_MAP_SIZE,
+            SHADOW_MAP_SIZE,
+            1,
+            vk::SampleCountFlags::TYPE_1,
+            shadow_format,
+            vk::ImageTiling::OPTIMAL,
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::SAMPLED,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            device_memory_properties,
+        );
+        let shadow_map_image_view = share::v1::create_image_view(
+            device,
+            shadow_map_image,
+            shadow_format,
+            vk::ImageAspectFlags::DEPTH,
+            1,
+        );
+
+        (
+            shadow_map_image,
+            shadow_map_image_view,
+            shadow_map_image_memory,
+        )
+    }
+
+    fn create_shadow_framebuffer(
+        device: &ash::Device,
+        shadow_render_pass: vk::RenderPass,
+        shadow_map_image_view: vk::ImageView,
+    ) -> vk::Framebuffer {
+        let attachments = [shadow_map_image_view];
+
+        let framebuffer_create_info = vk::FramebufferCreateInfo {
+            s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::FramebufferCreateFlags::empty(),
+            render_pass: shadow_render_pass,
+            attachment_count: attachments.len() as u32,
+            p_attachments: attachments.as_ptr(),
+            width: SHADOW_MAP_SIZE,
+            height: SHADOW_MAP_SIZE,
+            layers: 1,
+        };
+
+        unsafe {
+            device
+                .create_framebuffer(&framebuffer_create_info, None)
+                .expect("Failed to create Framebuffer!")
+        }
+    }
+
+    fn create_uniform_buffers(
+        device: &ash::Device,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        swapchain_image_count: usize,
+    ) -> (Vec<vk::Buffer>, Vec<vk::DeviceMemory>) {
+        let buffer_size = std::mem::size_of::<ShadowUniformBufferObject>();
+
+        let mut uniform_buffers = vec![];
+        let mut uniform_buffers_memory = vec![];
+
+        for _ in 0..swapchain_image_count {
+            let (uniform_buffer, uniform_buffer_memory) = share::create_buffer(
+                device,
+                buffer_size as u64,
+                vk::BufferUsageFlags::UNIFORM_BUFFER,
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+                device_memory_properties,
+            );
+            uniform_buffers.push(uniform_buffer);
+            uniform_buffers_memory.push(uniform_buffer_memory);
+        }
+
+        (uniform_buffers, uniform_buffers_memory)
+    }
+
+    fn create_descriptor_set_layout(device: &ash::Device) -> vk::DescriptorSetLayout {
+        let image_binding = |binding, descriptor_type| vk::DescriptorSetLayoutBinding {
+            binding,
+            descriptor_type,
+            descriptor_count: 1,
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            p_immutable_samplers: ptr::null(),
+        };
+
+        let bindings = [
+            vk::DescriptorSetLayoutBinding {
+                // transform and light uniform
+                binding: 0,
+                descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            },
+            image_binding(1, vk::DescriptorType::SAMPLED_IMAGE),
+            image_binding(2, vk::DescriptorType::SAMPLER),
+            image_binding(3, vk::DescriptorType::SAMPLED_IMAGE),
+            image_binding(4, vk::DescriptorType::SAMPLER),
+        ];
+
+        let layout_create_info = vk::DescriptorSetLayoutCreateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
+            binding_count: bindings.len() as u32,
+            p_bindings: bindings.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_descriptor_set_layout(&layout_create_info, None)
+                .expect("Failed to create Descriptor Set Layout!")
+        }
+    }
+
+    fn create_descriptor_pool(
+        device: &ash::Device,
+        swapchain_images_size: usize,
+    ) -> vk::DescriptorPool {
+        let set_count = swapchain_images_size as u32;
+        let pool_sizes = [
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::UNIFORM_BUFFER,
+                descriptor_count: set_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLED_IMAGE,
+                descriptor_count: set_count * 2,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLER,
+                descriptor_count: set_count * 2,
+            },
+        ];
+
+        let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_POOL_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::DescriptorPoolCreateFlags::empty(),
+            max_sets: set_count,
+            pool_size_count: pool_sizes.len() as u32,
+            p_pool_sizes: pool_sizes.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_descriptor_pool(&descriptor_pool_create_info, None)
+                .expect("Failed to create Descriptor Pool!")
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_descriptor_sets(
+        device: &ash::Device,
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        uniforms_buffers: &[vk::Buffer],
+        texture_image_view: vk::ImageView,
+        texture_sampler: vk::Sampler,
+        shadow_map_image_view: vk::ImageView,
+        shadow_sampler: vk::Sampler,
+    ) -> Vec<vk::DescriptorSet> {
+        let layouts = vec![descriptor_set_layout; uniforms_buffers.len()];
+
+        let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            descriptor_pool,
+            descriptor_set_count: layouts.len() as u32,
+            p_set_layouts: layouts.as_ptr(),
+        };
+
+        let descriptor_sets = unsafe {
+            device
+                .allocate_descriptor_sets(&descriptor_set_allocate_info)
+                .expect("Failed to allocate descriptor sets!")
+        };
+
+        let image_info = |image_view| {
+            [vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view,
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            }]
+        };
+        let sampler_info = |sampler| {
+            [vk::DescriptorImageInfo {
+                sampler,
+                image_view: vk::ImageView::null(),
+                image_layout: vk::ImageLayout::UNDEFINED,
+            }]
+        };
+        let texture_infos = image_info(texture_image_view);
+        let texture_sampler_infos = sampler_info(texture_sampler);
+        let shadow_map_infos = image_info(shadow_map_image_view);
+        let shadow_sampler_infos = sampler_info(shadow_sampler);
+
+        for (i, &descriptor_set) in descriptor_sets.iter().enumerate() {
+            let uniform_buffer_infos = [vk::DescriptorBufferInfo {
+                buffer: uniforms_buffers[i],
+                offset: 0,
+                range: std::mem::size_of::<ShadowUniformBufferObject>() as u64,
+            }];
+
+            let write_image =
+                |dst_binding, descriptor_type, image_infos: &[vk::DescriptorImageInfo]| {
+                    vk::WriteDescriptorSet {
+                        s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+                        p_next: ptr::null(),
+                        dst_set: descriptor_set,
+                        dst_binding,
+                        dst_array_element: 0,
+                        descriptor_count: image_infos.len() as u32,
+                        descriptor_type,
+                        p_image_info: image_infos.as_ptr(),
+                        p_buffer_info: ptr::null(),
+                        p_texel_buffer_view: ptr::null(),
+                    }
+                };
+
+            let descriptor_write_sets = [
+                vk::WriteDescriptorSet {
+                    s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+                    p_next: ptr::null(),
+                    dst_set: descriptor_set,
+                    dst_binding: 0,
+                    dst_array_element: 0,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::UNIFORM_BUFFER,
+                    p_image_info: ptr::null(),
+                    p_buffer_info: uniform_buffer_infos.as_ptr(),
+                    p_texel_buffer_view: ptr::null(),
+                },
+                write_image(1, vk::DescriptorType::SAMPLED_IMAGE, &texture_infos),
+                write_image(2, vk::DescriptorType::SAMPLER, &texture_sampler_infos),
+                write_image(3, vk::DescriptorType::SAMPLED_IMAGE, &shadow_map_infos),
+                write_image(4, vk::DescriptorType::SAMPLER, &shadow_sampler_infos),
+            ];
+
+            unsafe {
+                device.update_descriptor_sets(&descriptor_write_sets, &[]);
+            }
+        }
+
+        descriptor_sets
+    }
+}
+
+// Fix content -------------------------------------------------------------------------------
+impl VulkanApp32 {
+    fn create_render_pass(
+        device: &ash::Device,
+        surface_format: vk::Format,
+        depth_format: vk::Format,
+    ) -> vk::RenderPass {
+        let color_attachment = vk::AttachmentDescription {
+            flags: vk::AttachmentDescriptionFlags::empty(),
+            format: surface_format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::STORE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+        };
+
+        let depth_attachment = vk::AttachmentDescription {
+            flags: vk::AttachmentDescriptionFlags::empty(),
+            format: depth_format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::DONT_CARE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+
+        let color_attachment_ref = vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        };
+        let depth_attachment_ref = vk::AttachmentReference {
+            attachment: 1,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+
+        let subpasses = [vk::SubpassDescription {
+            flags: vk::SubpassDescriptionFlags::empty(),
+            pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+            input_attachment_count: 0,
+            p_input_attachments: ptr::null(),
+            color_attachment_count: 1,
+            p_color_attachments: &color_attachment_ref,
+            p_resolve_attachments: ptr::null(),
+            p_depth_stencil_attachment: &depth_attachment_ref,
+            preserve_attachment_count: 0,
+            p_preserve_attachments: ptr::null(),
+        }];
+
+        let render_pass_attachments = [color_attachment, depth_attachment];
+
+        let subpass_dependencies = [vk::SubpassDependency {
+            src_subpass: vk::SUBPASS_EXTERNAL,
+            dst_subpass: 0,
+            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            src_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            dependency_flags: vk::DependencyFlags::empty(),
+        }];
+
+        let renderpass_create_info = vk::RenderPassCreateInfo {
+            s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
+            flags: vk::RenderPassCreateFlags::empty(),
+            p_next: ptr::null(),
+            attachment_count: render_pass_attachments.len() as u32,
+            p_attachments: render_pass_attachments.as_ptr(),
+            subpass_count: subpasses.len() as u32,
+            p_subpasses: subpasses.as_ptr(),
+            dependency_count: subpass_dependencies.len() as u32,
+            p_dependencies: subpass_dependencies.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_render_pass(&renderpass_create_info, None)
+                .expect("Failed to create render pass!")
+        }
+    }
+
+    /// A depth-only pass whose attachment ends up ready to be sampled by the main pass.
+    fn create_shadow_render_pass(
+        device: &ash::Device,
+        shadow_format: vk::Format,
+    ) -> vk::RenderPass {
+        let shadow_attachment = vk::AttachmentDescription {
+            flags: vk::AttachmentDescriptionFlags::empty(),
+            format: shadow_format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::STORE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        };
+
+        let depth_attachment_ref = vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+
+        let subpasses = [vk::SubpassDescription {
+            flags: vk::SubpassDescriptionFlags::empty(),
+            pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+            input_attachment_count: 0,
+            p_input_attachments: ptr::null(),
+            color_attachment_count: 0,
+            p_color_attachments: ptr::null(),
+            p_resolve_attachments: ptr::null(),
+            p_depth_stencil_attachment: &depth_attachment_ref,
+            preserve_attachment_count: 0,
+            p_preserve_attachments: ptr::null(),
+        }];
+
+        let render_pass_attachments = [shadow_attachment];
+
+        let subpass_dependencies = [
+            vk::SubpassDependency {
+                // the previous frame may still be sampling the shadow map.
+                src_subpass: vk::SUBPASS_EXTERNAL,
+                dst_subpass: 0,
+                src_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                dst_stage_mask: vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+                src_access_mask: vk::AccessFlags::SHADER_READ,
+                dst_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                dependency_flags: vk::DependencyFlags::empty(),
+            },
+            vk::SubpassDependency {
+                // the main pass samples the finished shadow map.
+                src_subpass: 0,
+                dst_subpass: vk::SUBPASS_EXTERNAL,
+                src_stage_mask: vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                dst_stage_mask: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                src_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+                dst_access_mask: vk::AccessFlags::SHADER_READ,
+                dependency_flags: vk::DependencyFlags::empty(),
+            },
+        ];
+
+        let renderpass_create_info = vk::RenderPassCreateInfo {
+            s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
+            flags: vk::RenderPassCreateFlags::empty(),
+            p_next: ptr::null(),
+            attachment_count: render_pass_attachments.len() as u32,
+            p_attachments: render_pass_attachments.as_ptr(),
+            subpass_count: subpasses.len() as u32,
+            p_subpasses: subpasses.as_ptr(),
+            dependency_count: subpass_dependencies.len() as u32,
+            p_dependencies: subpass_dependencies.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_render_pass(&renderpass_create_info, None)
+                .expect("Failed to create render pass!")
+        }
+    }
+
+    fn create_framebuffers(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        image_views: &[vk::ImageView],
+        depth_image_view: vk::ImageView,
+        swapchain_extent: vk::Extent2D,
+    ) -> Vec<vk::Framebuffer> {
+        let mut framebuffers = vec![];
+
+        for &image_view in image_views.iter() {
+            let attachments = [image_view, depth_image_view];
+
+            let framebuffer_create_info = vk::FramebufferCreateInfo {
+                s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::FramebufferCreateFlags::empty(),
+                render_pass,
+                attachment_count: attachments.len() as u32,
+                p_attachments: attachments.as_ptr(),
+                width: swapchain_extent.width,
+                height: swapchain_extent.height,
+                layers: 1,
+            };
+
+            let framebuffer = unsafe {
+                device
+                    .create_framebuffer(&framebuffer_create_info, None)
+                    .expect("Failed to create Framebuffer!")
+            };
+
+            framebuffers.push(framebuffer);
+        }
+
+        framebuffers
+    }
+
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [set_layout];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: 0,
+            p_push_constant_ranges: ptr::null(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
+    /// The shadow pipeline has no fragment stage and takes its depth bias from dynamic state.
+    /// Whether the scene pipeline filters the shadow map is a specialization constant, so
+    /// switching PCF needs a new pipeline.
+    fn create_graphics_pipeline(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        extent: vk::Extent2D,
+        pipeline_layout: vk::PipelineLayout,
+        is_shadow_pass: bool,
+        is_pcf_enabled: bool,
+    ) -> vk::Pipeline {
+        let main_function_name = CString::new("main").unwrap(); // the beginning function name in shader code.
+
+        let shader_modules = if is_shadow_pass {
+            vec![(
+                share::create_shader_module(
+                    device,
+                    include_bytes!("../../shaders/spv/32-shadow.vert.spv").to_vec(),
+                ),
+                vk::ShaderStageFlags::VERTEX,
+            )]
+        } else {
+            vec![
+                (
+                    share::create_shader_module(
+                        device,
+                        include_bytes!("../../shaders/spv/32-scene.vert.spv").to_vec(),
+                    ),
+                    vk::ShaderStageFlags::VERTEX,
+                ),
+                (
+                    share::create_shader_module(device, SCENE_FRAGMENT_SHADER.to_vec()),
+                    vk::ShaderStageFlags::FRAGMENT,
+                ),
+            ]
+        };
+
+        let mut fragment_constants = SpecializationConstants::new();
+        fragment_constants.add_bool(PCF_CONSTANT_ID, is_pcf_enabled);
+        fragment_constants
+            .check_against(SCENE_FRAGMENT_SHADER)
+            .unwrap_or_else(|err| panic!("Failed to specialize 32-scene.frag: {}", err));
+        let fragment_specialization_info = fragment_constants.info();
+
+        let shader_stages: Vec<vk::PipelineShaderStageCreateInfo> = shader_modules
+            .iter()
+            .map(|&(module, stage)| {
+                let specialization_info = if stage == vk::ShaderStageFlags::FRAGMENT {
+                    Some(&fragment_specialization_info)
+                } else {
+                    None
+                };
+                specialization::shader_stage_create_info(
+                    module,
+                    stage,
+                    &main_function_name,
+                    specialization_info,
+                )
+            })
+            .collect();
+
+        let binding_description = VertexV3::get_binding_descriptions();
+        let attribute_description = VertexV3::get_attribute_descriptions();
+
+        let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+            vertex_attribute_description_count: attribute_description.len() as u32,
+            p_vertex_attribute_descriptions: attribute_description.as_ptr(),
+            vertex_binding_description_count: binding_description.len() as u32,
+            p_vertex_binding_descriptions: binding_description.as_ptr(),
+        };
+        let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
+            flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            primitive_restart_enable: vk::FALSE,
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        };
+
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: extent.width as f32,
+            height: extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        let scissors = [vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent,
+        }];
+
+        let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VIEWPORT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineViewportStateCreateFlags::empty(),
+            scissor_count: scissors.len() as u32,
+            p_scissors: scissors.as_ptr(),
+            viewport_count: viewports.len() as u32,
+            p_viewports: viewports.as_ptr(),
+        };
+
+        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
+            depth_clamp_enable: vk::FALSE,
+            cull_mode: vk::CullModeFlags::BACK,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            polygon_mode: vk::PolygonMode::FILL,
+            rasterizer_discard_enable: vk::FALSE,
+            // the factors themselves are set by vkCmdSetDepthBias.
+            depth_bias_enable: if is_shadow_pass { vk::TRUE } else { vk::FALSE },
+            depth_bias_clamp: 0.0,
+            depth_bias_constant_factor: 0.0,
+            depth_bias_slope_factor: 0.0,
+        };
+
+        let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
+            flags: vk::PipelineMultisampleStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            sample_shading_enable: vk::FALSE,
+            min_sample_shading: 0.0,
+            p_sample_mask: ptr::null(),
+            alpha_to_one_enable: vk::FALSE,
+            alpha_to_coverage_enable: vk::FALSE,
+        };
+
+        let stencil_state = vk::StencilOpState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::KEEP,
+            depth_fail_op: vk::StencilOp::KEEP,
+            compare_op: vk::CompareOp::ALWAYS,
+            compare_mask: 0,
+            write_mask: 0,
+            reference: 0,
+        };
+
+        let depth_state_create_info = vk::PipelineDepthStencilStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
+            depth_test_enable: vk::TRUE,
+            depth_write_enable: vk::TRUE,
+            depth_compare_op: vk::CompareOp::LESS,
+            depth_bounds_test_enable: vk::FALSE,
+            stencil_test_enable: vk::FALSE,
+            front: stencil_state,
+            back: stencil_state,
+            max_depth_bounds: 1.0,
+            min_depth_bounds: 0.0,
+        };
+
+        let color_blend_attachment_states = if is_shadow_pass {
+            vec![]
+        } else {
+            vec![vk::PipelineColorBlendAttachmentState {
+                blend_enable: vk::FALSE,
+                color_write_mask: vk::ColorComponentFlags::all(),
+                src_color_blend_factor: vk::BlendFactor::ONE,
+                dst_color_blend_factor: vk::BlendFactor::ZERO,
+                color_blend_op: vk::BlendOp::ADD,
+                src_alpha_blend_factor: vk::BlendFactor::ONE,
+                dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+                alpha_blend_op: vk::BlendOp::ADD,
+            }]
+        };
+
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineColorBlendStateCreateFlags::empty(),
+            logic_op_enable: vk::FALSE,
+            logic_op: vk::LogicOp::COPY,
+            attachment_count: color_blend_attachment_states.len() as u32,
+            p_attachments: color_blend_attachment_states.as_ptr(),
+            blend_constants: [0.0, 0.0, 0.0, 0.0],
+        };
+
+        let dynamic_states = [vk::DynamicState::DEPTH_BIAS];
+        let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_DYNAMIC_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineDynamicStateCreateFlags::empty(),
+            dynamic_state_count: dynamic_states.len() as u32,
+            p_dynamic_states: dynamic_states.as_ptr(),
+        };
+
+        let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
+            s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineCreateFlags::empty(),
+            stage_count: shader_stages.len() as u32,
+            p_stages: shader_stages.as_ptr(),
+            p_vertex_input_state: &vertex_input_state_create_info,
+            p_input_assembly_state: &vertex_input_assembly_state_info,
+            p_tessellation_state: ptr::null(),
+            p_viewport_state: &viewport_state_create_info,
+            p_rasterization_state: &rasterization_statue_create_info,
+            p_multisample_state: &multisample_state_create_info,
+            p_depth_stencil_state: &depth_state_create_info,
+            p_color_blend_state: &color_blend_state,
+            p_dynamic_state: if is_shadow_pass {
+                &dynamic_state_create_info
+            } else {
+                ptr::null()
+            },
+            layout: pipeline_layout,
+            render_pass,
+            subpass: 0,
+            base_pipeline_handle: vk::Pipeline::null(),
+            base_pipeline_index: -1,
+        }];
+
+        let graphics_pipelines = unsafe {
+            device
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    &graphic_pipeline_create_infos,
+                    None,
+                )
+                .expect("Failed to create Graphics Pipeline!.")
+        };
+
+        unsafe {
+            for &(module, _stage) in shader_modules.iter() {
+                device.destroy_shader_module(module, None);
+            }
+        }
+
+        graphics_pipelines[0]
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn create_command_buffers(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        query_manager: &mut QueryManager,
+        graphics_pipeline: vk::Pipeline,
+        shadow_pipeline: vk::Pipeline,
+        framebuffers: &[vk::Framebuffer],
+        shadow_framebuffer: vk::Framebuffer,
+        render_pass: vk::RenderPass,
+        shadow_render_pass: vk::RenderPass,
+        surface_extent: vk::Extent2D,
+        vertex_buffer: vk::Buffer,
+        index_buffer: vk::Buffer,
+        pipeline_layout: vk::PipelineLayout,
+        descriptor_sets: &[vk::DescriptorSet],
+        index_count: u32,
+        depth_bias: DepthBias,
+    ) -> Vec<vk::CommandBuffer> {
+        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            command_buffer_count: framebuffers.len() as u32,
+            command_pool,
+            level: vk::CommandBufferLevel::PRIMARY,
+        };
+
+        let command_buffers = unsafe {
+            device
+                .allocate_command_buffers(&command_buffer_allocate_info)
+                .expect("Failed to allocate Command Buffers!")
+        };
+
+        let depth_clear_value = vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: 1.0,
+                stencil: 0,
+            },
+        };
+
+        for (i, &command_buffer) in command_buffers.iter().enumerate() {
+            let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+                s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+                p_next: ptr::null(),
+                p_inheritance_info: ptr::null(),
+                flags: vk::CommandBufferUsageFlags::SIMULTANEOUS_USE,
+            };
+
+            unsafe {
+                device
+                    .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+                    .expect("Failed to begin recording Command Buffer at beginning!");
+            }
+
+            let shadow_clear_values = [depth_clear_value];
+            let shadow_pass_begin_info = vk::RenderPassBeginInfo {
+                s_type: vk::StructureType::RENDER_PASS_BEGIN_INFO,
+                p_next: ptr::null(),
+                render_pass: shadow_render_pass,
+                framebuffer: shadow_framebuffer,
+                render_area: vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent: vk::Extent2D {
+                        width: SHADOW_MAP_SIZE,
+                        height: SHADOW_MAP_SIZE,
+                    },
+                },
+                clear_value_count: shadow_clear_values.len() as u32,
+                p_clear_values: shadow_clear_values.as_ptr(),
+            };
+
+            let clear_values = [
+                vk::ClearValue {
+                    // clear value for color buffer
+                    color: vk::ClearColorValue {
+                        float32: [0.0, 0.0, 0.0, 1.0],
+                    },
+                },
+                depth_clear_value,
+            ];
+            let render_pass_begin_info = vk::RenderPassBeginInfo {
+                s_type: vk::StructureType::RENDER_PASS_BEGIN_INFO,
+                p_next: ptr::null(),
+                render_pass,
+                framebuffer: framebuffers[i],
+                render_area: vk::Rect2D {
+                    offset: vk::Offset2D { x: 0, y: 0 },
+                    extent: surface_extent,
+                },
+                clear_value_count: clear_values.len() as u32,
+                p_clear_values: clear_values.as_ptr(),
+            };
+
+            let vertex_buffers = [vertex_buffer];
+            let offsets = [0_u64];
+            let descriptor_sets_to_bind = [descriptor_sets[i]];
+
+            query_manager.reset(device, command_buffer, i, OCCLUSION_OBJECT_COUNT);
+
+            unsafe {
+                device.cmd_bind_vertex_buffers(command_buffer, 0, &vertex_buffers, &offsets);
+                device.cmd_bind_index_buffer(
+                    command_buffer,
+                    index_buffer,
+                    0,
+                    vk::IndexType::UINT32,
+                );
+                device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    pipeline_layout,
+                    0,
+                    &descriptor_sets_to_bind,
+                    &[],
+                );
+
+                // render the scene from the light into the shadow map.
+                let shadow_statistics =
+                    query_manager.begin_statistics(device, command_buffer, i, "shadow");
+                device.cmd_begin_render_pass(
+                    command_buffer,
+                    &shadow_pass_begin_info,
+                    vk::SubpassContents::INLINE,
+                );
+                device.cmd_bind_pipeline(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    shadow_pipeline,
+                );
+                device.cmd_set_depth_bias(
+                    command_buffer,
+                    depth_bias.constant_factor,
+                    0.0,
+                    depth_bias.slope_factor,
+                );
+                device.cmd_draw_indexed(command_buffer, index_count, 1, 0, 0, 0);
+                device.cmd_end_render_pass(command_buffer);
+                query_manager.end_statistics(device, command_buffer, i, shadow_statistics);
+
+                // then from the camera, looking up the shadow map.
+                let scene_statistics =
+                    query_manager.begin_statistics(device, command_buffer, i, "scene");
+                device.cmd_begin_render_pass(
+                    command_buffer,
+                    &render_pass_begin_info,
+                    vk::SubpassContents::INLINE,
+                );
+                device.cmd_bind_pipeline(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    graphics_pipeline,
+                );
+                query_manager.begin_occlusion(device, command_buffer, i, 0);
+                device.cmd_draw_indexed(command_buffer, index_count, 1, 0, 0, 0);
+                query_manager.end_occlusion(device, command_buffer, i, 0);
+                device.cmd_end_render_pass(command_buffer);
+                query_manager.end_statistics(device, command_buffer, i, scene_statistics);
+
+                device
+                    .end_command_buffer(command_buffer)
+                    .expect("Failed to record Command Buffer at Ending!");
+            }
+        }
+
+        command_buffers
+    }
+
+    fn rerecord_command_buffers(&mut self) {
+        unsafe {
+            self.device
+                .free_command_buffers(self.command_pool, &self.command_buffers);
+        }
+        self.command_buffers = VulkanApp32::create_command_buffers(
+            &self.device,
+            self.command_pool,
+            &mut self.query_manager,
+            self.graphics_pipeline,
+            self.shadow_pipeline,
+            &self.swapchain_framebuffers,
+            self.shadow_framebuffer,
+            self.render_pass,
+            self.shadow_render_pass,
+            self.swapchain_extent,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.pipeline_layout,
+            &self.descriptor_sets,
+            self.indices.len() as u32,
+            self.depth_bias,
+        );
+    }
+
+    fn create_query_manager(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        device: &ash::Device,
+        slot_count: usize,
+    ) -> QueryManager {
+        // share::create_logical_device enables every supported query feature.
+        let enabled_features = unsafe { instance.get_physical_device_features(physical_device) };
+
+        let query_manager = QueryManager::new(
+            device,
+            &enabled_features,
+            slot_count,
+            STATISTICS_QUERY_COUNT,
+            OCCLUSION_OBJECT_COUNT,
+        );
+        if !query_manager.is_statistics_supported() {
+            println!("pipelineStatisticsQuery is not supported, only occlusion is queried.");
+        }
+
+        query_manager
+    }
+
+    fn collect_query_results(&mut self, slot: usize) {
+        if let Some(pass_statistics) = self.query_manager.statistics_results(&self.device, slot) {
+            self.pass_statistics = pass_statistics;
+        }
+        if let Some(samples_passed) = self.query_manager.occlusion_results(&self.device, slot) {
+            self.samples_passed = samples_passed;
+        }
+    }
+
+    fn print_query_results(&self) {
+        for (name, statistics) in self.pass_statistics.iter() {
+            println!(
+                "{}: {} vertices, {} vertex shader invocations, {}/{} primitives clipped/output, {} fragment shader invocations",
+                name,
+                statistics.input_assembly_vertices,
+                statistics.vertex_shader_invocations,
+                statistics.clipping_invocations,
+                statistics.clipping_primitives,
+                statistics.fragment_shader_invocations,
+            );
+        }
+        for (object_index, samples) in self.samples_passed.iter().enumerate() {
+            println!("Object {}: {} samples passed", object_index, samples);
+        }
+    }
+
+    fn set_depth_bias(&mut self, depth_bias: DepthBias) {
+        self.wait_device_idle();
+
+        self.depth_bias = DepthBias {
+            constant_factor: depth_bias.constant_factor.max(0.0),
+            slope_factor: depth_bias.slope_factor.max(0.0),
+        };
+        println!(
+            "Depth bias: constant {:.2}, slope {:.2}",
+            self.depth_bias.constant_factor, self.depth_bias.slope_factor
+        );
+        self.rerecord_command_buffers();
+    }
+
+    fn set_pcf_enabled(&mut self, is_pcf_enabled: bool) {
+        self.wait_device_idle();
+
+        self.is_pcf_enabled = is_pcf_enabled;
+        println!("PCF: {}", if self.is_pcf_enabled { "on" } else { "off" });
+
+        unsafe {
+            self.device.destroy_pipeline(self.graphics_pipeline, None);
+        }
+        self.graphics_pipeline = VulkanApp32::create_graphics_pipeline(
+            &self.device,
+            self.render_pass,
+            self.swapchain_extent,
+            self.pipeline_layout,
+            false,
+            self.is_pcf_enabled,
+        );
+        self.rerecord_command_buffers();
+    }
+
+    /// Returns the light's view-projection matrix, position and direction in world space.
+    fn light_transform(&self) -> (Matrix4<f32>, Point3<f32>, Vector3<f32>) {
+        let target = Point3::new(0.0, 0.0, 0.0);
+        let up = Vector3::new(0.0, 0.0, 1.0);
+
+        match self.light_kind {
+            LightKind::Directional => {
+                let direction = Vector3::from(DIRECTIONAL_LIGHT_DIRECTION).normalize();
+                let position = target - direction * 4.0;
+                let view = Matrix4::look_at(position, target, up);
+                let proj = cgmath::ortho(-1.6, 1.6, -1.6, 1.6, 0.5, 8.0);
+
+                (
+                    VulkanApp32::vulkan_clip_correction() * proj * view,
+                    position,
+                    direction,
+                )
+            }
+            LightKind::Spot => {
+                let position = Point3::from(SPOT_LIGHT_POSITION);
+                let direction = (target - position).normalize();
+                let view = Matrix4::look_at(position, target, up);
+                let proj = cgmath::perspective(Deg(SPOT_OUTER_ANGLE * 2.0), 1.0, 0.5, 8.0);
+
+                (
+                    VulkanApp32::vulkan_clip_correction() * proj * view,
+                    position,
+                    direction,
+                )
+            }
+        }
+    }
+
+    fn update_uniform_buffer(&mut self, current_image: usize, alpha: f32) {
+        self.uniform_transform.model = Matrix4::from_angle_z(Deg(self.model_angle.value(alpha)));
+
+        let (light_space, light_position, light_direction) = self.light_transform();
+        let position = light_position.to_vec();
+        self.uniform_transform.light_space = light_space;
+        self.uniform_transform.light_position = [position.x, position.y, position.z, 1.0];
+        self.uniform_transform.light_direction = [
+            light_direction.x,
+            light_direction.y,
+            light_direction.z,
+            SPOT_OUTER_ANGLE.to_radians().cos(),
+        ];
+        self.uniform_transform.params = [
+            AMBIENT,
+            0.0,
+            SPOT_INNER_ANGLE.to_radians().cos(),
+            if self.light_kind == LightKind::Spot {
+                1.0
+            } else {
+                0.0
+            },
+        ];
+
+        let ubos = [self.uniform_transform];
+
+        let buffer_size = (std::mem::size_of::<ShadowUniformBufferObject>() * ubos.len()) as u64;
+
+        unsafe {
+            let data_ptr = self
+                .device
+                .map_memory(
+                    self.uniform_buffers_memory[current_image],
+                    0,
+                    buffer_size,
+                    vk::MemoryMapFlags::empty(),
+                )
+                .expect("Failed to Map Memory")
+                as *mut ShadowUniformBufferObject;
+
+            data_ptr.copy_from_nonoverlapping(ubos.as_ptr(), ubos.len());
+
+            self.device
+                .unmap_memory(self.uniform_buffers_memory[current_image]);
+        }
+    }
+}
+
+impl Drop for VulkanApp32 {
+    fn drop(&mut self) {
+        unsafe {
+            for i in 0..MAX_FRAMES_IN_FLIGHT {
+                self.device
+                    .destroy_semaphore(self.image_available_semaphores[i], None);
+                self.device
+                    .destroy_semaphore(self.render_finished_semaphores[i], None);
+                self.device.destroy_fence(self.in_flight_fences[i], None);
+            }
+
+            self.cleanup_swapchain();
+
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+
+            for i in 0..self.uniform_buffers.len() {
+                self.device.destroy_buffer(self.uniform_buffers[i], None);
+                self.device
+                    .free_memory(self.uniform_buffers_memory[i], None);
+            }
+
+            self.device.destroy_buffer(self.index_buffer, None);
+            self.device.free_memory(self.index_buffer_memory, None);
+
+            self.device.destroy_buffer(self.vertex_buffer, None);
+            self.device.free_memory(self.vertex_buffer_memory, None);
+
+            self.device.destroy_sampler(self.texture_sampler, None);
+            self.device
+                .destroy_image_view(self.texture_image_view, None);
+
+            self.device.destroy_image(self.texture_image, None);
+            self.device.free_memory(self.texture_image_memory, None);
+
+            self.device.destroy_sampler(self.shadow_sampler, None);
+            self.device
+                .destroy_framebuffer(self.shadow_framebuffer, None);
+            self.device
+                .destroy_image_view(self.shadow_map_image_view, None);
+            self.device.destroy_image(self.shadow_map_image, None);
+            self.device.free_memory(self.shadow_map_image_memory, None);
+            self.device.destroy_pipeline(self.shadow_pipeline, None);
+            self.device
+                .destroy_render_pass(self.shadow_render_pass, None);
+
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+
+            self.query_manager.destroy(&self.device);
+            self.device.destroy_command_pool(self.command_pool, None);
+
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+
+            if VALIDATION.is_enable {
+                self.debug_utils_loader
+                    .destroy_debug_utils_messenger(self.debug_merssager, None);
+            }
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+impl VulkanApp for VulkanApp32 {
+    fn update(&mut self, fixed_dt: f32) {
+        self.model_angle.advance(90.0 * fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
+        let wait_fences = [self.in_flight_fences[self.current_frame]];
+
+        unsafe {
+            self.device
+                .wait_for_fences(&wait_fences, true, u64::MAX)
+                .expect("Failed to wait for Fence!");
+        }
+
+        let (image_index, _is_sub_optimal) = unsafe {
+            let result = self.swapchain_loader.acquire_next_image(
+                self.swapchain,
+                u64::MAX,
+                self.image_available_semaphores[self.current_frame],
+                vk::Fence::null(),
+            );
+            match result {
+                Ok(image_index) => image_index,
+                Err(vk_result) => match vk_result {
+                    vk::Result::ERROR_OUT_OF_DATE_KHR => {
+                        self.recreate_swapchain();
+                        return;
+                    }
+                    _ => panic!("Failed to acquire Swap Chain Image!"),
+                },
+            }
+        };
+
+        self.update_uniform_buffer(image_index as usize, alpha);
+        // the results of the previous submission of this command buffer, if it is done.
+        self.collect_query_results(image_index as usize);
+
+        let wait_semaphores = [self.image_available_semaphores[self.current_frame]];
+        let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let signal_semaphores = [self.render_finished_semaphores[self.current_frame]];
+
+        let submit_infos = [vk::SubmitInfo {
+            s_type: vk::StructureType::SUBMIT_INFO,
+            p_next: ptr::null(),
+            wait_semaphore_count: wait_semaphores.len() as u32,
+            p_wait_semaphores: wait_semaphores.as_ptr(),
+            p_wait_dst_stage_mask: wait_stages.as_ptr(),
+            command_buffer_count: 1,
+            p_command_buffers: &self.command_buffers[image_index as usize],
+            signal_semaphore_count: signal_semaphores.len() as u32,
+            p_signal_semaphores: signal_semaphores.as_ptr(),
+        }];
+
+        unsafe {
+            self.device
+                .reset_fences(&wait_fences)
+                .expect("Failed to reset Fence!");
+
+            self.device
+                .queue_submit(
+                    self.graphics_queue,
+                    &submit_infos,
+                    self.in_flight_fences[self.current_frame],
+                )
+                .expect("Failed to execute queue submit.");
+        }
+
+        let swapchains = [self.swapchain];
+
+        let present_info = vk::PresentInfoKHR {
+            s_type: vk::StructureType::PRESENT_INFO_KHR,
+            p_next: ptr::null(),
+            wait_semaphore_count: 1,
+            p_wait_semaphores: signal_semaphores.as_ptr(),
+            swapchain_count: 1,
+            p_swapchains: swapchains.as_ptr(),
+            p_image_indices: &image_index,
+            p_results: ptr::null_mut(),
+        };
+
+        let result = unsafe {
+            self.swapchain_loader
+                .queue_present(self.present_queue, &present_info)
+        };
+
+        let is_resized = match result {
+            Ok(_) => self.is_framebuffer_resized,
+            Err(vk_result) => match vk_result {
+                vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR => true,
+                _ => panic!("Failed to execute queue present."),
+            },
+        };
+        if is_resized {
+            self.is_framebuffer_resized = false;
+            self.recreate_swapchain();
+        }
+
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+    }
+
+    fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
+        // parameters -------------
+        let window_size = self.window.inner_size();
+        let surface_suff = SurfaceStuff {
+            surface_loader: self.surface_loader.clone(),
+            surface: self.surface,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
+        };
+        // ------------------------
+
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+        self.cleanup_swapchain();
+
+        let swapchain_stuff = share::create_swapchain(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            &self.window,
+            &surface_suff,
+            &self.queue_family,
+        );
+        self.swapchain_loader = swapchain_stuff.swapchain_loader;
+        self.swapchain = swapchain_stuff.swapchain;
+        self.swapchain_images = swapchain_stuff.swapchain_images;
+        self.swapchain_format = swapchain_stuff.swapchain_format;
+        self.swapchain_extent = swapchain_stuff.swapchain_extent;
+
+        self.swapchain_imageviews = share::v1::create_image_views(
+            &self.device,
+            self.swapchain_format,
+            &self.swapchain_images,
+        );
+        let depth_format = share::find_depth_format(&self.instance, self.physical_device);
+        self.render_pass =
+            VulkanApp32::create_render_pass(&self.device, self.swapchain_format, depth_format);
+        self.graphics_pipeline = VulkanApp32::create_graphics_pipeline(
+            &self.device,
+            self.render_pass,
+            self.swapchain_extent,
+            self.pipeline_layout,
+            false,
+            self.is_pcf_enabled,
+        );
+
+        let depth_resources = share::v1::create_depth_resources(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            self.command_pool,
+            self.graphics_queue,
+            self.swapchain_extent,
+            &self.memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        );
+        self.depth_image = depth_resources.0;
+        self.depth_image_view = depth_resources.1;
+        self.depth_image_memory = depth_resources.2;
+
+        self.swapchain_framebuffers = VulkanApp32::create_framebuffers(
+            &self.device,
+            self.render_pass,
+            &self.swapchain_imageviews,
+            self.depth_image_view,
+            self.swapchain_extent,
+        );
+        self.uniform_transform.proj = VulkanApp32::create_projection(self.swapchain_extent);
+
+        // the swapchain image count may have changed, and with it the number of slots.
+        self.query_manager.destroy(&self.device);
+        self.query_manager = VulkanApp32::create_query_manager(
+            &self.instance,
+            self.physical_device,
+            &self.device,
+            self.swapchain_images.len(),
+        );
+        self.command_buffers = VulkanApp32::create_command_buffers(
+            &self.device,
+            self.command_pool,
+            &mut self.query_manager,
+            self.graphics_pipeline,
+            self.shadow_pipeline,
+            &self.swapchain_framebuffers,
+            self.shadow_framebuffer,
+            self.render_pass,
+            self.shadow_render_pass,
+            self.swapchain_extent,
+            self.vertex_buffer,
+            self.index_buffer,
+            self.pipeline_layout,
+            &self.descriptor_sets,
+            self.indices.len() as u32,
+            self.depth_bias,
+        );
+    }
+
+    fn cleanup_swapchain(&self) {
+        unsafe {
+            self.device.destroy_image_view(self.depth_image_view, None);
+            self.device.destroy_image(self.depth_image, None);
+            self.device.free_memory(self.depth_image_memory, None);
+
+            self.device
+                .free_command_buffers(self.command_pool, &self.command_buffers);
+            for &framebuffer in self.swapchain_framebuffers.iter() {
+                self.device.destroy_framebuffer(framebuffer, None);
+            }
+            self.device.destroy_pipeline(self.graphics_pipeline, None);
+            self.device.destroy_render_pass(self.render_pass, None);
+            for &image_view in self.swapchain_imageviews.iter() {
+                self.device.destroy_image_view(image_view, None);
+            }
+            self.swapchain_loader
+                .destroy_swapchain(self.swapchain, None);
+        }
+    }
+
+    fn wait_device_idle(&self) {
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+    }
+
+    fn resize_framebuffer(&mut self) {
+        self.is_framebuffer_resized = true;
+    }
+
+    fn window_ref(&self) -> &winit::window::Window {
+        &self.window
+    }
+
+    fn on_key_pressed(&mut self, key_code: VirtualKeyCode) {
+        let depth_bias = self.depth_bias;
+
+        match key_code {
+            VirtualKeyCode::F5 => {
+                self.light_kind = match self.light_kind {
+                    LightKind::Directional => LightKind::Spot,
+                    LightKind::Spot => LightKind::Directional,
+                };
+                println!("Light: {:?}", self.light_kind);
+            }
+            VirtualKeyCode::F6 => self.set_pcf_enabled(!self.is_pcf_enabled),
+            VirtualKeyCode::F7 => self.print_query_results(),
+            VirtualKeyCode::Up => self.set_depth_bias(DepthBias {
+                constant_factor: depth_bias.constant_factor + DEPTH_BIAS_STEP,
+                ..depth_bias
+            }),
+            VirtualKeyCode::Down => self.set_depth_bias(DepthBias {
+                constant_factor: depth_bias.constant_factor - DEPTH_BIAS_STEP,
+                ..depth_bias
+            }),
+            VirtualKeyCode::Right => self.set_depth_bias(DepthBias {
+                slope_factor: depth_bias.slope_factor + DEPTH_BIAS_STEP,
+                ..depth_bias
+            }),
+            VirtualKeyCode::Left => self.set_depth_bias(DepthBias {
+                slope_factor: depth_bias.slope_factor - DEPTH_BIAS_STEP,
+                ..depth_bias
+            }),
+            _ => {}
+        }
+    }
+}
+
+fn main() {
+    let program_proc = ProgramProc::new();
+    let vulkan_app = VulkanApp32::new(&program_proc.event_loop);
+
+    program_proc.main_loop(vulkan_app);
+}
+// -------------------------------------------------------------------------------------------
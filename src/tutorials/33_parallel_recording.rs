@@ -0,0 +1,1405 @@
+use vulkan_tutorial_rust::{
+    utility, // the mod define some fixed functions that have been learned before.
+    utility::constants::*,
+    utility::debug::*,
+    utility::fixed_timestep::InterpolatedValue,
+    utility::parallel_recording::{ParallelRecorder, RecordJob},
+    utility::share,
+    utility::structures::*,
+    utility::timeline::{FrameSync, SyncKind, UploadTicket},
+    utility::window::{ProgramProc, VulkanApp},
+};
+
+use ash::version::DeviceV1_0;
+use ash::version::InstanceV1_0;
+use ash::vk;
+use cgmath::{Deg, Matrix4, Point3, Rad, Vector3};
+use winit::event::VirtualKeyCode;
+
+use std::ffi::CString;
+use std::ptr;
+use std::time::{Duration, Instant};
+
+// Constants
+const WINDOW_TITLE: &str = "33.Parallel-Recording";
+const GRID_SIZE: usize = 64;
+const OBJECT_COUNT: usize = GRID_SIZE * GRID_SIZE;
+const GRID_EXTENT: f32 = 8.0;
+const CUBE_SCALE: f32 = 0.04;
+const MAX_RECORD_THREADS: usize = 8;
+
+#[rustfmt::skip]
+const CUBE_VERTICES_DATA: [VertexV3; 8] = [
+    VertexV3 { pos: [-1.0, -1.0, -1.0, 1.0], color: [0.2, 0.2, 0.2, 1.0], tex_coord: [0.0, 0.0] },
+    VertexV3 { pos: [ 1.0, -1.0, -1.0, 1.0], color: [1.0, 0.2, 0.2, 1.0], tex_coord: [0.0, 0.0] },
+    VertexV3 { pos: [ 1.0,  1.0, -1.0, 1.0], color: [1.0, 1.0, 0.2, 1.0], tex_coord: [0.0, 0.0] },
+    VertexV3 { pos: [-1.0,  1.0, -1.0, 1.0], color: [0.2, 1.0, 0.2, 1.0], tex_coord: [0.0, 0.0] },
+    VertexV3 { pos: [-1.0, -1.0,  1.0, 1.0], color: [0.2, 0.2, 1.0, 1.0], tex_coord: [0.0, 0.0] },
+    VertexV3 { pos: [ 1.0, -1.0,  1.0, 1.0], color: [1.0, 0.2, 1.0, 1.0], tex_coord: [0.0, 0.0] },
+    VertexV3 { pos: [ 1.0,  1.0,  1.0, 1.0], color: [1.0, 1.0, 1.0, 1.0], tex_coord: [0.0, 0.0] },
+    VertexV3 { pos: [-1.0,  1.0,  1.0, 1.0], color: [0.2, 1.0, 1.0, 1.0], tex_coord: [0.0, 0.0] },
+];
+#[rustfmt::skip]
+const CUBE_INDICES_DATA: [u32; 36] = [
+    0, 2, 1, 0, 3, 2, // bottom
+    4, 5, 6, 4, 6, 7, // top
+    0, 1, 5, 0, 5, 4, // front
+    2, 3, 7, 2, 7, 6, // back
+    0, 4, 7, 0, 7, 3, // left
+    1, 2, 6, 1, 6, 5, // right
+];
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ObjectPushConstants {
+    model: Matrix4<f32>,
+    color: [f32; 4],
+}
+
+/// The copy of the vertex and index data to device local memory, the staging buffers are freed
+/// once the GPU is done with it.
+struct PendingUpload {
+    ticket: UploadTicket,
+    command_buffer: vk::CommandBuffer,
+    staging_buffers: Vec<(vk::Buffer, vk::DeviceMemory)>,
+}
+
+/// Accumulated CPU time spent recording the secondary command buffers.
+struct RecordTiming {
+    total: Duration,
+    frame_count: u32,
+}
+
+struct VulkanApp33 {
+    window: winit::window::Window,
+
+    // vulkan stuff
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    surface_loader: ash::extensions::khr::Surface,
+    surface: vk::SurfaceKHR,
+    debug_utils_loader: ash::extensions::ext::DebugUtils,
+    debug_merssager: vk::DebugUtilsMessengerEXT,
+
+    physical_device: vk::PhysicalDevice,
+    memory_properties: vk::PhysicalDeviceMemoryProperties,
+    device: ash::Device,
+
+    queue_family: QueueFamilyIndices,
+    graphics_queue: vk::Queue,
+    present_queue: vk::Queue,
+
+    swapchain_loader: ash::extensions::khr::Swapchain,
+    swapchain: vk::SwapchainKHR,
+    swapchain_images: Vec<vk::Image>,
+    swapchain_format: vk::Format,
+    swapchain_extent: vk::Extent2D,
+    swapchain_imageviews: Vec<vk::ImageView>,
+    swapchain_framebuffers: Vec<vk::Framebuffer>,
+
+    render_pass: vk::RenderPass,
+    ubo_layout: vk::DescriptorSetLayout,
+    pipeline_layout: vk::PipelineLayout,
+    graphics_pipeline: vk::Pipeline,
+
+    depth_image: vk::Image,
+    depth_image_view: vk::ImageView,
+    depth_image_memory: vk::DeviceMemory,
+
+    vertex_buffer: vk::Buffer,
+    vertex_buffer_memory: vk::DeviceMemory,
+    index_buffer: vk::Buffer,
+    index_buffer_memory: vk::DeviceMemory,
+    pending_upload: Option<PendingUpload>,
+
+    uniform_transform: UniformBufferObject,
+    uniform_buffers: Vec<vk::Buffer>,
+    uniform_buffers_memory: Vec<vk::DeviceMemory>,
+
+    descriptor_pool: vk::DescriptorPool,
+    descriptor_sets: Vec<vk::DescriptorSet>,
+
+    command_pool: vk::CommandPool,
+    // one primary command buffer per frame in flight, re-recorded every frame.
+    command_buffers: Vec<vk::CommandBuffer>,
+    recorder: ParallelRecorder,
+    record_timing: RecordTiming,
+    elapsed_time: InterpolatedValue,
+
+    is_timeline_semaphore_enabled: bool,
+    frame_sync: FrameSync,
+    current_frame: usize,
+
+    is_framebuffer_resized: bool,
+}
+
+impl VulkanApp33 {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<()>) -> VulkanApp33 {
+        let window =
+            utility::window::init_window(event_loop, WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT);
+
+        // init vulkan stuff
+        let entry = ash::Entry::new().unwrap();
+        let instance = share::create_instance(
+            &entry,
+            WINDOW_TITLE,
+            VALIDATION.is_enable,
+            &VALIDATION.required_validation_layers.to_vec(),
+        );
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
+        let (debug_utils_loader, debug_merssager) =
+            setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
+        let physical_device =
+            share::pick_physical_device(&instance, &surface_stuff, &DEVICE_EXTENSIONS);
+        let physical_device_memory_properties =
+            unsafe { instance.get_physical_device_memory_properties(physical_device) };
+        let (device, queue_family, is_timeline_semaphore_enabled) =
+            share::create_logical_device_with_timeline_semaphore(
+                &entry,
+                &instance,
+                physical_device,
+                &VALIDATION,
+                &DEVICE_EXTENSIONS,
+                &surface_stuff,
+            );
+        let graphics_queue =
+            unsafe { device.get_device_queue(queue_family.graphics_family.unwrap(), 0) };
+        let present_queue =
+            unsafe { device.get_device_queue(queue_family.present_family.unwrap(), 0) };
+        let swapchain_stuff = share::create_swapchain(
+            &instance,
+            &device,
+            physical_device,
+            &window,
+            &surface_stuff,
+            &queue_family,
+        );
+        let swapchain_imageviews = share::v1::create_image_views(
+            &device,
+            swapchain_stuff.swapchain_format,
+            &swapchain_stuff.swapchain_images,
+        );
+        let render_pass = VulkanApp33::create_render_pass(
+            &device,
+            swapchain_stuff.swapchain_format,
+            share::find_depth_format(&instance, physical_device),
+        );
+        let ubo_layout = share::v1::create_descriptor_set_layout(&device);
+        let pipeline_layout = VulkanApp33::create_pipeline_layout(&device, ubo_layout);
+        let graphics_pipeline = VulkanApp33::create_graphics_pipeline(
+            &device,
+            render_pass,
+            swapchain_stuff.swapchain_extent,
+            pipeline_layout,
+        );
+        let command_pool = VulkanApp33::create_command_pool(&device, &queue_family);
+        let (depth_image, depth_image_view, depth_image_memory) = share::v1::create_depth_resources(
+            &instance,
+            &device,
+            physical_device,
+            command_pool,
+            graphics_queue,
+            swapchain_stuff.swapchain_extent,
+            &physical_device_memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        );
+        let swapchain_framebuffers = VulkanApp33::create_framebuffers(
+            &device,
+            render_pass,
+            &swapchain_imageviews,
+            depth_image_view,
+            swapchain_stuff.swapchain_extent,
+        );
+        let mut frame_sync = FrameSync::new(
+            &instance,
+            &device,
+            MAX_FRAMES_IN_FLIGHT,
+            if is_timeline_semaphore_enabled {
+                SyncKind::Timeline
+            } else {
+                SyncKind::Binary
+            },
+        );
+        println!(
+            "Synchronizing frames with {:?} semaphores.",
+            frame_sync.kind()
+        );
+        let (
+            vertex_buffer,
+            vertex_buffer_memory,
+            index_buffer,
+            index_buffer_memory,
+            pending_upload,
+        ) = VulkanApp33::upload_cube_buffers(
+            &device,
+            &physical_device_memory_properties,
+            command_pool,
+            graphics_queue,
+            &mut frame_sync,
+        );
+        let (uniform_buffers, uniform_buffers_memory) = share::v1::create_uniform_buffers(
+            &device,
+            &physical_device_memory_properties,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let descriptor_pool =
+            share::v1::create_descriptor_pool(&device, swapchain_stuff.swapchain_images.len());
+        let descriptor_sets = share::v1::create_descriptor_sets(
+            &device,
+            descriptor_pool,
+            ubo_layout,
+            &uniform_buffers,
+            swapchain_stuff.swapchain_images.len(),
+        );
+        let command_buffers =
+            VulkanApp33::allocate_command_buffers(&device, command_pool, MAX_FRAMES_IN_FLIGHT);
+        let recorder = ParallelRecorder::new(
+            &device,
+            queue_family.graphics_family.unwrap(),
+            ParallelRecorder::default_thread_count(MAX_RECORD_THREADS),
+            MAX_FRAMES_IN_FLIGHT,
+        );
+        println!(
+            "Recording {} objects on {} threads.",
+            OBJECT_COUNT,
+            recorder.thread_count()
+        );
+
+        // cleanup(); the 'drop' function will take care of it.
+        VulkanApp33 {
+            // winit stuff
+            window,
+
+            // vulkan stuff
+            _entry: entry,
+            instance,
+            surface: surface_stuff.surface,
+            surface_loader: surface_stuff.surface_loader,
+            debug_utils_loader,
+            debug_merssager,
+
+            physical_device,
+            memory_properties: physical_device_memory_properties,
+            device,
+
+            queue_family,
+            graphics_queue,
+            present_queue,
+
+            swapchain_loader: swapchain_stuff.swapchain_loader,
+            swapchain: swapchain_stuff.swapchain,
+            swapchain_format: swapchain_stuff.swapchain_format,
+            swapchain_images: swapchain_stuff.swapchain_images,
+            swapchain_extent: swapchain_stuff.swapchain_extent,
+            swapchain_imageviews,
+            swapchain_framebuffers,
+
+            ubo_layout,
+            pipeline_layout,
+            render_pass,
+            graphics_pipeline,
+
+            depth_image,
+            depth_image_view,
+            depth_image_memory,
+
+            vertex_buffer,
+            vertex_buffer_memory,
+            index_buffer,
+            index_buffer_memory,
+            pending_upload: Some(pending_upload),
+
+            uniform_transform: UniformBufferObject {
+                model: Matrix4::from_scale(1.0),
+                view: Matrix4::look_at(
+                    Point3::new(0.0, -6.5, 5.0),
+                    Point3::new(0.0, 0.0, 0.0),
+                    Vector3::new(0.0, 0.0, 1.0),
+                ),
+                proj: VulkanApp33::create_projection(swapchain_stuff.swapchain_extent),
+            },
+            uniform_buffers,
+            uniform_buffers_memory,
+
+            descriptor_pool,
+            descriptor_sets,
+
+            command_pool,
+            command_buffers,
+            recorder,
+            record_timing: RecordTiming {
+                total: Duration::from_secs(0),
+                frame_count: 0,
+            },
+            elapsed_time: InterpolatedValue::new(0.0),
+
+            is_timeline_semaphore_enabled,
+            frame_sync,
+            current_frame: 0,
+
+            is_framebuffer_resized: false,
+        }
+    }
+
+    fn create_projection(extent: vk::Extent2D) -> Matrix4<f32> {
+        let mut proj = cgmath::perspective(
+            Deg(45.0),
+            extent.width as f32 / extent.height as f32,
+            0.1,
+            20.0,
+        );
+        proj[1][1] = -proj[1][1];
+        proj
+    }
+
+    /// Primary command buffers are reset one by one, so the pool needs `RESET_COMMAND_BUFFER`.
+    fn create_command_pool(
+        device: &ash::Device,
+        queue_families: &QueueFamilyIndices,
+    ) -> vk::CommandPool {
+        let command_pool_create_info = vk::CommandPoolCreateInfo {
+            s_type: vk::StructureType::COMMAND_POOL_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+            queue_family_index: queue_families.graphics_family.unwrap(),
+        };
+
+        unsafe {
+            device
+                .create_command_pool(&command_pool_create_info, None)
+                .expect("Failed to create Command Pool!")
+        }
+    }
+
+    fn allocate_command_buffers(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        count: usize,
+    ) -> Vec<vk::CommandBuffer> {
+        let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            command_buffer_count: count as u32,
+            command_pool,
+            level: vk::CommandBufferLevel::PRIMARY,
+        };
+
+        unsafe {
+            device
+                .allocate_command_buffers(&command_buffer_allocate_info)
+                .expect("Failed to allocate Command Buffers!")
+        }
+    }
+
+    /// Copy the cube to device local buffers without waiting for the copy to finish.
+    ///
+    /// A barrier at the end of the copy makes the data visible to every later submission on the
+    /// queue, so frames only need the upload to be over to free the staging buffers.
+    fn upload_cube_buffers(
+        device: &ash::Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        command_pool: vk::CommandPool,
+        submit_queue: vk::Queue,
+        frame_sync: &mut FrameSync,
+    ) -> (
+        vk::Buffer,
+        vk::DeviceMemory,
+        vk::Buffer,
+        vk::DeviceMemory,
+        PendingUpload,
+    ) {
+        let command_buffer = share::begin_single_time_command(device, command_pool);
+
+        let (vertex_buffer, vertex_buffer_memory, vertex_staging) =
+            VulkanApp33::record_buffer_upload(
+                device,
+                memory_properties,
+                command_buffer,
+                &CUBE_VERTICES_DATA,
+                vk::BufferUsageFlags::VERTEX_BUFFER,
+            );
+        let (index_buffer, index_buffer_memory, index_staging) = VulkanApp33::record_buffer_upload(
+            device,
+            memory_properties,
+            command_buffer,
+            &CUBE_INDICES_DATA,
+            vk::BufferUsageFlags::INDEX_BUFFER,
+        );
+
+        let memory_barriers = [vk::MemoryBarrier {
+            s_type: vk::StructureType::MEMORY_BARRIER,
+            p_next: ptr::null(),
+            src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+            dst_access_mask: vk::AccessFlags::VERTEX_ATTRIBUTE_READ | vk::AccessFlags::INDEX_READ,
+        }];
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::DependencyFlags::empty(),
+                &memory_barriers,
+                &[],
+                &[],
+            );
+            device
+                .end_command_buffer(command_buffer)
+                .expect("Failed to record Command Buffer at Ending!");
+        }
+
+        let ticket = frame_sync.submit_upload(device, submit_queue, command_buffer);
+
+        (
+            vertex_buffer,
+            vertex_buffer_memory,
+            index_buffer,
+            index_buffer_memory,
+            PendingUpload {
+                ticket,
+                command_buffer,
+                staging_buffers: vec![vertex_staging, index_staging],
+            },
+        )
+    }
+
+    /// Fill a staging buffer with `data` and record its copy to a new device local buffer.
+    fn record_buffer_upload<T>(
+        device: &ash::Device,
+        memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        command_buffer: vk::CommandBuffer,
+        data: &[T],
+        usage: vk::BufferUsageFlags,
+    ) -> (vk::Buffer, vk::DeviceMemory, (vk::Buffer, vk::DeviceMemory)) {
+        let buffer_size = std::mem::size_of_val(data) as vk::DeviceSize;
+
+        let (staging_buffer, staging_buffer_memory) = share::create_buffer(
+            device,
+            buffer_size,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+            memory_properties,
+        );
+
+        unsafe {
+            let data_ptr = device
+                .map_memory(
+                    staging_buffer_memory,
+                    0,
+                    buffer_size,
+                    vk::MemoryMapFlags::empty(),
+                )
+                .expect("Failed to Map Memory") as *mut T;
+
+            data_ptr.copy_from_nonoverlapping(data.as_ptr(), data.len());
+
+            device.unmap_memory(staging_buffer_memory);
+        }
+
+        let (buffer, buffer_memory) = share::create_buffer(
+            device,
+            buffer_size,
+            vk::BufferUsageFlags::TRANSFER_DST | usage,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            memory_properties,
+        );
+
+        let copy_regions = [vk::BufferCopy {
+            src_offset: 0,
+            dst_offset: 0,
+            size: buffer_size,
+        }];
+
+        unsafe {
+            device.cmd_copy_buffer(command_buffer, staging_buffer, buffer, &copy_regions);
+        }
+
+        (
+            buffer,
+            buffer_memory,
+            (staging_buffer, staging_buffer_memory),
+        )
+    }
+
+    /// Free the staging buffers of the upload once the GPU is done with it, or right away after
+    /// waiting for it if `is_forced`.
+    fn release_pending_upload(&mut self, is_forced: bool) {
+        let is_complete = match &self.pending_upload {
+            Some(upload) => {
+                if is_forced {
+                    self.frame_sync.wait_for_upload(&self.device, upload.ticket);
+                }
+                is_forced
+                    || self
+                        .frame_sync
+                        .is_upload_complete(&self.device, upload.ticket)
+            }
+            None => false,
+        };
+
+        if is_complete {
+            let upload = self.pending_upload.take().unwrap();
+            unsafe {
+                self.device
+                    .free_command_buffers(self.command_pool, &[upload.command_buffer]);
+                for &(buffer, buffer_memory) in upload.staging_buffers.iter() {
+                    self.device.destroy_buffer(buffer, None);
+                    self.device.free_memory(buffer_memory, None);
+                }
+            }
+        }
+    }
+
+    fn set_sync_kind(&mut self, kind: SyncKind) {
+        self.wait_device_idle();
+        self.release_pending_upload(true);
+
+        self.frame_sync.destroy(&self.device);
+        self.frame_sync = FrameSync::new(&self.instance, &self.device, MAX_FRAMES_IN_FLIGHT, kind);
+        println!(
+            "Synchronizing frames with {:?} semaphores.",
+            self.frame_sync.kind()
+        );
+    }
+
+    /// Transform and color of one cube of the grid at `time`, this is the per object CPU work.
+    fn object_push_constants(index: usize, time: f32) -> ObjectPushConstants {
+        let column = index % GRID_SIZE;
+        let row = index / GRID_SIZE;
+        let spacing = GRID_EXTENT / GRID_SIZE as f32;
+        let x = (column as f32 + 0.5) * spacing - GRID_EXTENT * 0.5;
+        let y = (row as f32 + 0.5) * spacing - GRID_EXTENT * 0.5;
+
+        let phase = (x * 0.8).sin() + (y * 0.6).cos();
+        let height = (time * 2.0 + phase * 2.0).sin() * 0.25;
+
+        let model = Matrix4::from_translation(Vector3::new(x, y, height))
+            * Matrix4::from_angle_z(Rad(time + phase))
+            * Matrix4::from_angle_x(Rad(time * 0.7 + phase))
+            * Matrix4::from_scale(CUBE_SCALE);
+
+        ObjectPushConstants {
+            model,
+            color: [
+                column as f32 / GRID_SIZE as f32,
+                row as f32 / GRID_SIZE as f32,
+                0.5 + 0.5 * phase.sin(),
+                1.0,
+            ],
+        }
+    }
+
+    fn set_record_thread_count(&mut self, thread_count: usize) {
+        self.wait_device_idle();
+
+        if self.record_timing.frame_count > 0 {
+            println!(
+                "{} threads: {:.3} ms per frame to record {} objects.",
+                self.recorder.thread_count(),
+                self.record_timing.total.as_secs_f64() * 1000.0
+                    / self.record_timing.frame_count as f64,
+                OBJECT_COUNT,
+            );
+        }
+
+        self.recorder.destroy(&self.device);
+        self.recorder = ParallelRecorder::new(
+            &self.device,
+            self.queue_family.graphics_family.unwrap(),
+            thread_count,
+            MAX_FRAMES_IN_FLIGHT,
+        );
+        self.record_timing = RecordTiming {
+            total: Duration::from_secs(0),
+            frame_count: 0,
+        };
+        println!(
+            "Recording {} objects on {} threads.",
+            OBJECT_COUNT,
+            self.recorder.thread_count()
+        );
+    }
+}
+
+// Fix content -------------------------------------------------------------------------------
+impl VulkanApp33 {
+    fn create_render_pass(
+        device: &ash::Device,
+        surface_format: vk::Format,
+        depth_format: vk::Format,
+    ) -> vk::RenderPass {
+        let color_attachment = vk::AttachmentDescription {
+            flags: vk::AttachmentDescriptionFlags::empty(),
+            format: surface_format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::STORE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+        };
+
+        let depth_attachment = vk::AttachmentDescription {
+            flags: vk::AttachmentDescriptionFlags::empty(),
+            format: depth_format,
+            samples: vk::SampleCountFlags::TYPE_1,
+            load_op: vk::AttachmentLoadOp::CLEAR,
+            store_op: vk::AttachmentStoreOp::DONT_CARE,
+            stencil_load_op: vk::AttachmentLoadOp::DONT_CARE,
+            stencil_store_op: vk::AttachmentStoreOp::DONT_CARE,
+            initial_layout: vk::ImageLayout::UNDEFINED,
+            final_layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+
+        let color_attachment_ref = vk::AttachmentReference {
+            attachment: 0,
+            layout: vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+        };
+        let depth_attachment_ref = vk::AttachmentReference {
+            attachment: 1,
+            layout: vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+        };
+
+        let subpasses = [vk::SubpassDescription {
+            flags: vk::SubpassDescriptionFlags::empty(),
+            pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+            input_attachment_count: 0,
+            p_input_attachments: ptr::null(),
+            color_attachment_count: 1,
+            p_color_attachments: &color_attachment_ref,
+            p_resolve_attachments: ptr::null(),
+            p_depth_stencil_attachment: &depth_attachment_ref,
+            preserve_attachment_count: 0,
+            p_preserve_attachments: ptr::null(),
+        }];
+
+        let render_pass_attachments = [color_attachment, depth_attachment];
+
+        let subpass_dependencies = [vk::SubpassDependency {
+            src_subpass: vk::SUBPASS_EXTERNAL,
+            dst_subpass: 0,
+            src_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+            dst_stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS,
+            src_access_mask: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            dst_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            dependency_flags: vk::DependencyFlags::empty(),
+        }];
+
+        let renderpass_create_info = vk::RenderPassCreateInfo {
+            s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
+            flags: vk::RenderPassCreateFlags::empty(),
+            p_next: ptr::null(),
+            attachment_count: render_pass_attachments.len() as u32,
+            p_attachments: render_pass_attachments.as_ptr(),
+            subpass_count: subpasses.len() as u32,
+            p_subpasses: subpasses.as_ptr(),
+            dependency_count: subpass_dependencies.len() as u32,
+            p_dependencies: subpass_dependencies.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_render_pass(&renderpass_create_info, None)
+                .expect("Failed to create render pass!")
+        }
+    }
+
+    fn create_framebuffers(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        image_views: &[vk::ImageView],
+        depth_image_view: vk::ImageView,
+        swapchain_extent: vk::Extent2D,
+    ) -> Vec<vk::Framebuffer> {
+        let mut framebuffers = vec![];
+
+        for &image_view in image_views.iter() {
+            let attachments = [image_view, depth_image_view];
+
+            let framebuffer_create_info = vk::FramebufferCreateInfo {
+                s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::FramebufferCreateFlags::empty(),
+                render_pass,
+                attachment_count: attachments.len() as u32,
+                p_attachments: attachments.as_ptr(),
+                width: swapchain_extent.width,
+                height: swapchain_extent.height,
+                layers: 1,
+            };
+
+            let framebuffer = unsafe {
+                device
+                    .create_framebuffer(&framebuffer_create_info, None)
+                    .expect("Failed to create Framebuffer!")
+            };
+
+            framebuffers.push(framebuffer);
+        }
+
+        framebuffers
+    }
+
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        ubo_set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [ubo_set_layout];
+        let push_constant_ranges = [vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::VERTEX,
+            offset: 0,
+            size: std::mem::size_of::<ObjectPushConstants>() as u32,
+        }];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: push_constant_ranges.len() as u32,
+            p_push_constant_ranges: push_constant_ranges.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
+    fn create_graphics_pipeline(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        swapchain_extent: vk::Extent2D,
+        pipeline_layout: vk::PipelineLayout,
+    ) -> vk::Pipeline {
+        let vert_shader_module = share::create_shader_module(
+            device,
+            include_bytes!("../../shaders/spv/33-object.vert.spv").to_vec(),
+        );
+        let frag_shader_module = share::create_shader_module(
+            device,
+            include_bytes!("../../shaders/spv/33-object.frag.spv").to_vec(),
+        );
+
+        let main_function_name = CString::new("main").unwrap(); // the beginning function name in shader code.
+
+        let shader_stages = [
+            vk::PipelineShaderStageCreateInfo {
+                // Vertex Shader
+                s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineShaderStageCreateFlags::empty(),
+                module: vert_shader_module,
+                p_name: main_function_name.as_ptr(),
+                p_specialization_info: ptr::null(),
+                stage: vk::ShaderStageFlags::VERTEX,
+            },
+            vk::PipelineShaderStageCreateInfo {
+                // Fragment Shader
+                s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineShaderStageCreateFlags::empty(),
+                module: frag_shader_module,
+                p_name: main_function_name.as_ptr(),
+                p_specialization_info: ptr::null(),
+                stage: vk::ShaderStageFlags::FRAGMENT,
+            },
+        ];
+
+        let binding_description = VertexV3::get_binding_descriptions();
+        let attribute_description = VertexV3::get_attribute_descriptions();
+
+        let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+            vertex_attribute_description_count: attribute_description.len() as u32,
+            p_vertex_attribute_descriptions: attribute_description.as_ptr(),
+            vertex_binding_description_count: binding_description.len() as u32,
+            p_vertex_binding_descriptions: binding_description.as_ptr(),
+        };
+        let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
+            flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            primitive_restart_enable: vk::FALSE,
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        };
+
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: swapchain_extent.width as f32,
+            height: swapchain_extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+
+        let scissors = [vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: swapchain_extent,
+        }];
+
+        let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VIEWPORT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineViewportStateCreateFlags::empty(),
+            scissor_count: scissors.len() as u32,
+            p_scissors: scissors.as_ptr(),
+            viewport_count: viewports.len() as u32,
+            p_viewports: viewports.as_ptr(),
+        };
+
+        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
+            depth_clamp_enable: vk::FALSE,
+            cull_mode: vk::CullModeFlags::BACK,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            polygon_mode: vk::PolygonMode::FILL,
+            rasterizer_discard_enable: vk::FALSE,
+            depth_bias_clamp: 0.0,
+            depth_bias_constant_factor: 0.0,
+            depth_bias_enable: vk::FALSE,
+            depth_bias_slope_factor: 0.0,
+        };
+
+        let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
+            flags: vk::PipelineMultisampleStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            sample_shading_enable: vk::FALSE,
+            min_sample_shading: 0.0,
+            p_sample_mask: ptr::null(),
+            alpha_to_one_enable: vk::FALSE,
+            alpha_to_coverage_enable: vk::FALSE,
+        };
+
+        let stencil_state = vk::StencilOpState {
+            fail_op: vk::StencilOp::KEEP,
+            pass_op: vk::StencilOp::KEEP,
+            depth_fail_op: vk::StencilOp::KEEP,
+            compare_op: vk::CompareOp::ALWAYS,
+            compare_mask: 0,
+            write_mask: 0,
+            reference: 0,
+        };
+
+        let depth_state_create_info = vk::PipelineDepthStencilStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_DEPTH_STENCIL_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineDepthStencilStateCreateFlags::empty(),
+            depth_test_enable: vk::TRUE,
+            depth_write_enable: vk::TRUE,
+            depth_compare_op: vk::CompareOp::LESS,
+            depth_bounds_test_enable: vk::FALSE,
+            stencil_test_enable: vk::FALSE,
+            front: stencil_state,
+            back: stencil_state,
+            max_depth_bounds: 1.0,
+            min_depth_bounds: 0.0,
+        };
+
+        let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::FALSE,
+            color_write_mask: vk::ColorComponentFlags::all(),
+            src_color_blend_factor: vk::BlendFactor::ONE,
+            dst_color_blend_factor: vk::BlendFactor::ZERO,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+            alpha_blend_op: vk::BlendOp::ADD,
+        }];
+
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineColorBlendStateCreateFlags::empty(),
+            logic_op_enable: vk::FALSE,
+            logic_op: vk::LogicOp::COPY,
+            attachment_count: color_blend_attachment_states.len() as u32,
+            p_attachments: color_blend_attachment_states.as_ptr(),
+            blend_constants: [0.0, 0.0, 0.0, 0.0],
+        };
+
+        let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
+            s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineCreateFlags::empty(),
+            stage_count: shader_stages.len() as u32,
+            p_stages: shader_stages.as_ptr(),
+            p_vertex_input_state: &vertex_input_state_create_info,
+            p_input_assembly_state: &vertex_input_assembly_state_info,
+            p_tessellation_state: ptr::null(),
+            p_viewport_state: &viewport_state_create_info,
+            p_rasterization_state: &rasterization_statue_create_info,
+            p_multisample_state: &multisample_state_create_info,
+            p_depth_stencil_state: &depth_state_create_info,
+            p_color_blend_state: &color_blend_state,
+            p_dynamic_state: ptr::null(),
+            layout: pipeline_layout,
+            render_pass,
+            subpass: 0,
+            base_pipeline_handle: vk::Pipeline::null(),
+            base_pipeline_index: -1,
+        }];
+
+        let graphics_pipelines = unsafe {
+            device
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    &graphic_pipeline_create_infos,
+                    None,
+                )
+                .expect("Failed to create Graphics Pipeline!.")
+        };
+
+        unsafe {
+            device.destroy_shader_module(vert_shader_module, None);
+            device.destroy_shader_module(frag_shader_module, None);
+        }
+
+        graphics_pipelines[0]
+    }
+
+    /// Record the frame: the draws are split over the worker threads into secondary command
+    /// buffers, which the primary command buffer executes inside the render pass.
+    fn record_command_buffer(&mut self, image_index: usize, time: f32) -> vk::CommandBuffer {
+        let command_buffer = self.command_buffers[self.current_frame];
+
+        let record_start = Instant::now();
+
+        let device = &self.device;
+        let graphics_pipeline = self.graphics_pipeline;
+        let pipeline_layout = self.pipeline_layout;
+        let vertex_buffer = self.vertex_buffer;
+        let index_buffer = self.index_buffer;
+        let descriptor_set = self.descriptor_sets[image_index];
+
+        let secondary_command_buffers = self.recorder.record(
+            device,
+            self.current_frame,
+            self.render_pass,
+            0,
+            self.swapchain_framebuffers[image_index],
+            OBJECT_COUNT,
+            |job: &RecordJob| {
+                let command_buffer = job.command_buffer;
+                let vertex_buffers = [vertex_buffer];
+                let offsets = [0_u64];
+                let descriptor_sets_to_bind = [descriptor_set];
+
+                unsafe {
+                    job.device.cmd_bind_pipeline(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        graphics_pipeline,
+                    );
+                    job.device.cmd_bind_vertex_buffers(
+                        command_buffer,
+                        0,
+                        &vertex_buffers,
+                        &offsets,
+                    );
+                    job.device.cmd_bind_index_buffer(
+                        command_buffer,
+                        index_buffer,
+                        0,
+                        vk::IndexType::UINT32,
+                    );
+                    job.device.cmd_bind_descriptor_sets(
+                        command_buffer,
+                        vk::PipelineBindPoint::GRAPHICS,
+                        pipeline_layout,
+                        0,
+                        &descriptor_sets_to_bind,
+                        &[],
+                    );
+                }
+
+                for index in job.items.clone() {
+                    let push_constants = VulkanApp33::object_push_constants(index, time);
+
+                    unsafe {
+                        job.device.cmd_push_constants(
+                            command_buffer,
+                            pipeline_layout,
+                            vk::ShaderStageFlags::VERTEX,
+                            0,
+                            std::slice::from_raw_parts(
+                                &push_constants as *const ObjectPushConstants as *const u8,
+                                std::mem::size_of::<ObjectPushConstants>(),
+                            ),
+                        );
+                        job.device.cmd_draw_indexed(
+                            command_buffer,
+                            CUBE_INDICES_DATA.len() as u32,
+                            1,
+                            0,
+                            0,
+                            0,
+                        );
+                    }
+                }
+            },
+        );
+
+        self.record_timing.total += record_start.elapsed();
+        self.record_timing.frame_count += 1;
+
+        let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+            s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+            p_next: ptr::null(),
+            p_inheritance_info: ptr::null(),
+            flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+        };
+
+        let clear_values = [
+            vk::ClearValue {
+                color: vk::ClearColorValue {
+                    float32: [0.05, 0.05, 0.08, 1.0],
+                },
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue {
+                    depth: 1.0,
+                    stencil: 0,
+                },
+            },
+        ];
+
+        let render_pass_begin_info = vk::RenderPassBeginInfo {
+            s_type: vk::StructureType::RENDER_PASS_BEGIN_INFO,
+            p_next: ptr::null(),
+            render_pass: self.render_pass,
+            framebuffer: self.swapchain_framebuffers[image_index],
+            render_area: vk::Rect2D {
+                offset: vk::Offset2D { x: 0, y: 0 },
+                extent: self.swapchain_extent,
+            },
+            clear_value_count: clear_values.len() as u32,
+            p_clear_values: clear_values.as_ptr(),
+        };
+
+        unsafe {
+            self.device
+                .reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())
+                .expect("Failed to reset Command Buffer!");
+            self.device
+                .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+                .expect("Failed to begin recording Command Buffer at beginning!");
+            self.device.cmd_begin_render_pass(
+                command_buffer,
+                &render_pass_begin_info,
+                vk::SubpassContents::SECONDARY_COMMAND_BUFFERS,
+            );
+            self.device
+                .cmd_execute_commands(command_buffer, &secondary_command_buffers);
+            self.device.cmd_end_render_pass(command_buffer);
+            self.device
+                .end_command_buffer(command_buffer)
+                .expect("Failed to record Command Buffer at Ending!");
+        }
+
+        command_buffer
+    }
+
+    fn update_uniform_buffer(&mut self, current_image: usize) {
+        let ubos = [self.uniform_transform];
+
+        let buffer_size = (std::mem::size_of::<UniformBufferObject>() * ubos.len()) as u64;
+
+        unsafe {
+            let data_ptr =
+                self.device
+                    .map_memory(
+                        self.uniform_buffers_memory[current_image],
+                        0,
+                        buffer_size,
+                        vk::MemoryMapFlags::empty(),
+                    )
+                    .expect("Failed to Map Memory") as *mut UniformBufferObject;
+
+            data_ptr.copy_from_nonoverlapping(ubos.as_ptr(), ubos.len());
+
+            self.device
+                .unmap_memory(self.uniform_buffers_memory[current_image]);
+        }
+    }
+}
+
+impl Drop for VulkanApp33 {
+    fn drop(&mut self) {
+        self.release_pending_upload(true);
+
+        unsafe {
+            self.frame_sync.destroy(&self.device);
+
+            self.cleanup_swapchain();
+
+            self.device
+                .destroy_descriptor_pool(self.descriptor_pool, None);
+
+            for i in 0..self.uniform_buffers.len() {
+                self.device.destroy_buffer(self.uniform_buffers[i], None);
+                self.device
+                    .free_memory(self.uniform_buffers_memory[i], None);
+            }
+
+            self.device.destroy_buffer(self.index_buffer, None);
+            self.device.free_memory(self.index_buffer_memory, None);
+
+            self.device.destroy_buffer(self.vertex_buffer, None);
+            self.device.free_memory(self.vertex_buffer_memory, None);
+
+            self.device
+                .destroy_pipeline_layout(self.pipeline_layout, None);
+            self.device
+                .destroy_descriptor_set_layout(self.ubo_layout, None);
+
+            self.recorder.destroy(&self.device);
+            self.device.destroy_command_pool(self.command_pool, None);
+
+            self.device.destroy_device(None);
+            self.surface_loader.destroy_surface(self.surface, None);
+
+            if VALIDATION.is_enable {
+                self.debug_utils_loader
+                    .destroy_debug_utils_messenger(self.debug_merssager, None);
+            }
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+impl VulkanApp for VulkanApp33 {
+    fn update(&mut self, fixed_dt: f32) {
+        self.elapsed_time.advance(fixed_dt);
+    }
+
+    fn render(&mut self, alpha: f32) {
+        self.frame_sync
+            .wait_for_frame(&self.device, self.current_frame);
+        self.release_pending_upload(false);
+
+        let (image_index, _is_sub_optimal) = unsafe {
+            let result = self.swapchain_loader.acquire_next_image(
+                self.swapchain,
+                u64::MAX,
+                self.frame_sync
+                    .image_available_semaphore(self.current_frame),
+                vk::Fence::null(),
+            );
+            match result {
+                Ok(image_index) => image_index,
+                Err(vk_result) => match vk_result {
+                    vk::Result::ERROR_OUT_OF_DATE_KHR => {
+                        self.recreate_swapchain();
+                        return;
+                    }
+                    _ => panic!("Failed to acquire Swap Chain Image!"),
+                },
+            }
+        };
+
+        self.update_uniform_buffer(image_index as usize);
+        // the wait above guarantees the command buffers of this frame are no longer in use.
+        let command_buffer =
+            self.record_command_buffer(image_index as usize, self.elapsed_time.value(alpha));
+
+        self.frame_sync.submit_frame(
+            &self.device,
+            self.graphics_queue,
+            self.current_frame,
+            command_buffer,
+        );
+
+        let signal_semaphores = [self
+            .frame_sync
+            .render_finished_semaphore(self.current_frame)];
+        let swapchains = [self.swapchain];
+
+        let present_info = vk::PresentInfoKHR {
+            s_type: vk::StructureType::PRESENT_INFO_KHR,
+            p_next: ptr::null(),
+            wait_semaphore_count: 1,
+            p_wait_semaphores: signal_semaphores.as_ptr(),
+            swapchain_count: 1,
+            p_swapchains: swapchains.as_ptr(),
+            p_image_indices: &image_index,
+            p_results: ptr::null_mut(),
+        };
+
+        let result = unsafe {
+            self.swapchain_loader
+                .queue_present(self.present_queue, &present_info)
+        };
+
+        let is_resized = match result {
+            Ok(_) => self.is_framebuffer_resized,
+            Err(vk_result) => match vk_result {
+                vk::Result::ERROR_OUT_OF_DATE_KHR | vk::Result::SUBOPTIMAL_KHR => true,
+                _ => panic!("Failed to execute queue present."),
+            },
+        };
+        if is_resized {
+            self.is_framebuffer_resized = false;
+            self.recreate_swapchain();
+        }
+
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+    }
+
+    fn recreate_swapchain(&mut self) {
+        if share::is_window_minimized(&self.window) {
+            // no swapchain can be created with a zero size, wait until the window is restored.
+            self.is_framebuffer_resized = true;
+            return;
+        }
+
+        // parameters -------------
+        let window_size = self.window.inner_size();
+        let surface_suff = SurfaceStuff {
+            surface_loader: self.surface_loader.clone(),
+            surface: self.surface,
+            screen_width: window_size.width,
+            screen_height: window_size.height,
+        };
+        // ------------------------
+
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+        self.cleanup_swapchain();
+
+        let swapchain_stuff = share::create_swapchain(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            &self.window,
+            &surface_suff,
+            &self.queue_family,
+        );
+        self.swapchain_loader = swapchain_stuff.swapchain_loader;
+        self.swapchain = swapchain_stuff.swapchain;
+        self.swapchain_images = swapchain_stuff.swapchain_images;
+        self.swapchain_format = swapchain_stuff.swapchain_format;
+        self.swapchain_extent = swapchain_stuff.swapchain_extent;
+
+        self.swapchain_imageviews = share::v1::create_image_views(
+            &self.device,
+            self.swapchain_format,
+            &self.swapchain_images,
+        );
+        self.render_pass = VulkanApp33::create_render_pass(
+            &self.device,
+            self.swapchain_format,
+            share::find_depth_format(&self.instance, self.physical_device),
+        );
+        self.graphics_pipeline = VulkanApp33::create_graphics_pipeline(
+            &self.device,
+            self.render_pass,
+            self.swapchain_extent,
+            self.pipeline_layout,
+        );
+
+        let depth_resources = share::v1::create_depth_resources(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            self.command_pool,
+            self.graphics_queue,
+            self.swapchain_extent,
+            &self.memory_properties,
+            vk::SampleCountFlags::TYPE_1,
+        );
+        self.depth_image = depth_resources.0;
+        self.depth_image_view = depth_resources.1;
+        self.depth_image_memory = depth_resources.2;
+
+        self.swapchain_framebuffers = VulkanApp33::create_framebuffers(
+            &self.device,
+            self.render_pass,
+            &self.swapchain_imageviews,
+            self.depth_image_view,
+            self.swapchain_extent,
+        );
+        self.uniform_transform.proj = VulkanApp33::create_projection(self.swapchain_extent);
+    }
+
+    fn cleanup_swapchain(&self) {
+        unsafe {
+            self.device.destroy_image_view(self.depth_image_view, None);
+            self.device.destroy_image(self.depth_image, None);
+            self.device.free_memory(self.depth_image_memory, None);
+
+            for &framebuffer in self.swapchain_framebuffers.iter() {
+                self.device.destroy_framebuffer(framebuffer, None);
+            }
+            self.device.destroy_pipeline(self.graphics_pipeline, None);
+            self.device.destroy_render_pass(self.render_pass, None);
+            for &image_view in self.swapchain_imageviews.iter() {
+                self.device.destroy_image_view(image_view, None);
+            }
+            self.swapchain_loader
+                .destroy_swapchain(self.swapchain, None);
+        }
+    }
+
+    fn wait_device_idle(&self) {
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+    }
+
+    fn resize_framebuffer(&mut self) {
+        self.is_framebuffer_resized = true;
+    }
+
+    fn window_ref(&self) -> &winit::window::Window {
+        &self.window
+    }
+
+    fn on_key_pressed(&mut self, key_code: VirtualKeyCode) {
+        if key_code == VirtualKeyCode::F1 {
+            // compare recording on the main thread only with recording on all workers.
+            let thread_count = if self.recorder.thread_count() == 1 {
+                ParallelRecorder::default_thread_count(MAX_RECORD_THREADS)
+            } else {
+                1
+            };
+            self.set_record_thread_count(thread_count);
+        } else if key_code == VirtualKeyCode::F2 {
+            if self.is_timeline_semaphore_enabled {
+                let kind = match self.frame_sync.kind() {
+                    SyncKind::Binary => SyncKind::Timeline,
+                    SyncKind::Timeline => SyncKind::Binary,
+                };
+                self.set_sync_kind(kind);
+            } else {
+                println!("Timeline semaphores are not supported by this device.");
+            }
+        }
+    }
+}
+
+fn main() {
+    let program_proc = ProgramProc::new();
+    let vulkan_app = VulkanApp33::new(&program_proc.event_loop);
+
+    program_proc.main_loop(vulkan_app);
+}
+// -------------------------------------------------------------------------------------------
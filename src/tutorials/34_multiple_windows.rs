@@ -0,0 +1,337 @@
+use vulkan_tutorial_rust::{
+    utility, // the mod define some fixed functions that have been learned before.
+    utility::constants::*,
+    utility::debug::*,
+    utility::share,
+    utility::structures::*,
+    utility::window::{ProgramProc, VulkanApp},
+    utility::window_target::WindowTarget,
+};
+
+use ash::version::DeviceV1_0;
+use ash::version::InstanceV1_0;
+use ash::vk;
+use winit::window::WindowId;
+
+// Constants
+const WINDOW_TITLE: &str = "34.Multiple Windows";
+const PREVIEW_WINDOW_COUNT: usize = 2;
+const PREVIEW_WIDTH: u32 = 400;
+const PREVIEW_HEIGHT: u32 = 300;
+
+/// A window and the render targets drawn into it, which depend on the format and the extent of
+/// its swapchain.
+struct WindowView {
+    target: WindowTarget,
+
+    render_pass: vk::RenderPass,
+    pipeline_layout: vk::PipelineLayout,
+    graphics_pipeline: vk::Pipeline,
+    framebuffers: Vec<vk::Framebuffer>,
+    command_buffers: Vec<vk::CommandBuffer>,
+}
+
+impl WindowView {
+    fn new(
+        device: &ash::Device,
+        command_pool: vk::CommandPool,
+        target: WindowTarget,
+    ) -> WindowView {
+        let mut view = WindowView {
+            target,
+
+            render_pass: vk::RenderPass::null(),
+            pipeline_layout: vk::PipelineLayout::null(),
+            graphics_pipeline: vk::Pipeline::null(),
+            framebuffers: vec![],
+            command_buffers: vec![],
+        };
+        view.create_render_targets(device, command_pool);
+
+        view
+    }
+
+    fn create_render_targets(&mut self, device: &ash::Device, command_pool: vk::CommandPool) {
+        let extent = self.target.extent();
+
+        self.render_pass = share::v1::create_render_pass(device, self.target.format());
+        let (graphics_pipeline, pipeline_layout) =
+            share::v1::create_graphics_pipeline(device, self.render_pass, extent);
+        self.graphics_pipeline = graphics_pipeline;
+        self.pipeline_layout = pipeline_layout;
+
+        self.framebuffers = share::v1::create_framebuffers(
+            device,
+            self.render_pass,
+            &self.target.image_views,
+            extent,
+        );
+        self.command_buffers = share::v1::create_command_buffers(
+            device,
+            command_pool,
+            self.graphics_pipeline,
+            &self.framebuffers,
+            self.render_pass,
+            extent,
+        );
+    }
+
+    /// Destroy the render targets, before the swapchain they were created for goes away.
+    fn cleanup_render_targets(&self, device: &ash::Device, command_pool: vk::CommandPool) {
+        unsafe {
+            device.free_command_buffers(command_pool, &self.command_buffers);
+            for &framebuffer in self.framebuffers.iter() {
+                device.destroy_framebuffer(framebuffer, None);
+            }
+            device.destroy_pipeline(self.graphics_pipeline, None);
+            device.destroy_pipeline_layout(self.pipeline_layout, None);
+            device.destroy_render_pass(self.render_pass, None);
+        }
+    }
+}
+
+struct VulkanApp34 {
+    // vulkan stuff
+    _entry: ash::Entry,
+    instance: ash::Instance,
+    debug_utils_loader: ash::extensions::ext::DebugUtils,
+    debug_merssager: vk::DebugUtilsMessengerEXT,
+
+    physical_device: vk::PhysicalDevice,
+    device: ash::Device,
+
+    queue_family: QueueFamilyIndices,
+    graphics_queue: vk::Queue,
+    present_queue: vk::Queue,
+
+    command_pool: vk::CommandPool,
+
+    // the main window comes first, closing it quits the app. The previews can close on their own.
+    views: Vec<WindowView>,
+}
+
+impl VulkanApp34 {
+    pub fn new(event_loop: &winit::event_loop::EventLoop<()>) -> VulkanApp34 {
+        let window =
+            utility::window::init_window(event_loop, WINDOW_TITLE, WINDOW_WIDTH, WINDOW_HEIGHT);
+
+        let entry = ash::Entry::new().unwrap();
+        let instance = share::create_instance(
+            &entry,
+            WINDOW_TITLE,
+            VALIDATION.is_enable,
+            &VALIDATION.required_validation_layers.to_vec(),
+        );
+        // the device is picked for the main window, the previews are checked as they are added.
+        let surface_stuff = share::create_surface(&entry, &instance, &window);
+        let (debug_utils_loader, debug_merssager) =
+            setup_debug_utils(VALIDATION.is_enable, &entry, &instance);
+        let physical_device =
+            share::pick_physical_device(&instance, &surface_stuff, &DEVICE_EXTENSIONS);
+        let (device, queue_family) = share::create_logical_device(
+            &instance,
+            physical_device,
+            &VALIDATION,
+            &DEVICE_EXTENSIONS,
+            &surface_stuff,
+        );
+        let graphics_queue =
+            unsafe { device.get_device_queue(queue_family.graphics_family.unwrap(), 0) };
+        let present_queue =
+            unsafe { device.get_device_queue(queue_family.present_family.unwrap(), 0) };
+        let command_pool = share::v1::create_command_pool(&device, &queue_family);
+
+        let main_target = WindowTarget::from_surface(
+            &instance,
+            &device,
+            physical_device,
+            &queue_family,
+            window,
+            surface_stuff,
+        );
+        let mut views = vec![WindowView::new(&device, command_pool, main_target)];
+
+        for preview_index in 0..PREVIEW_WINDOW_COUNT {
+            let preview_title = format!("{} - Preview {}", WINDOW_TITLE, preview_index + 1);
+            let preview_window = utility::window::init_window(
+                event_loop,
+                &preview_title,
+                PREVIEW_WIDTH,
+                PREVIEW_HEIGHT,
+            );
+            let preview_target = WindowTarget::new(
+                &entry,
+                &instance,
+                &device,
+                physical_device,
+                &queue_family,
+                preview_window,
+            );
+            views.push(WindowView::new(&device, command_pool, preview_target));
+        }
+
+        // cleanup(); the 'drop' function will take care of it.
+        VulkanApp34 {
+            // vulkan stuff
+            _entry: entry,
+            instance,
+            debug_utils_loader,
+            debug_merssager,
+
+            physical_device,
+            device,
+
+            queue_family,
+            graphics_queue,
+            present_queue,
+
+            command_pool,
+
+            views,
+        }
+    }
+
+    fn find_view(&self, window_id: WindowId) -> Option<usize> {
+        self.views
+            .iter()
+            .position(|view| view.target.id() == window_id)
+    }
+
+    fn draw_view(&mut self, view_index: usize) {
+        let view = &mut self.views[view_index];
+        if view.target.is_minimized() {
+            return;
+        }
+
+        let image_index = match view.target.acquire_next_image(&self.device) {
+            Some(image_index) => image_index,
+            None => {
+                self.recreate_view(view_index);
+                return;
+            }
+        };
+
+        let is_out_of_date = view.target.submit_and_present(
+            &self.device,
+            self.graphics_queue,
+            self.present_queue,
+            view.command_buffers[image_index as usize],
+            image_index,
+        );
+        if is_out_of_date {
+            self.recreate_view(view_index);
+        }
+    }
+
+    fn recreate_view(&mut self, view_index: usize) {
+        let view = &mut self.views[view_index];
+        if view.target.is_minimized() {
+            // no swapchain can be created with a zero size, the view is recreated once restored.
+            return;
+        }
+
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+        view.cleanup_render_targets(&self.device, self.command_pool);
+        view.target.recreate_swapchain(
+            &self.instance,
+            &self.device,
+            self.physical_device,
+            &self.queue_family,
+        );
+        view.create_render_targets(&self.device, self.command_pool);
+    }
+}
+
+impl Drop for VulkanApp34 {
+    fn drop(&mut self) {
+        unsafe {
+            for view in self.views.iter() {
+                view.cleanup_render_targets(&self.device, self.command_pool);
+                view.target.destroy(&self.device);
+            }
+
+            self.device.destroy_command_pool(self.command_pool, None);
+
+            self.device.destroy_device(None);
+
+            if VALIDATION.is_enable {
+                self.debug_utils_loader
+                    .destroy_debug_utils_messenger(self.debug_merssager, None);
+            }
+            self.instance.destroy_instance(None);
+        }
+    }
+}
+
+impl VulkanApp for VulkanApp34 {
+    fn render(&mut self, _alpha: f32) {
+        // every window acquires and presents on its own, one after the other.
+        for view_index in 0..self.views.len() {
+            self.draw_view(view_index);
+        }
+    }
+
+    fn recreate_swapchain(&mut self) {
+        self.recreate_view(0);
+    }
+
+    fn cleanup_swapchain(&self) {
+        // the swapchains themselves belong to the window targets.
+        for view in self.views.iter() {
+            view.cleanup_render_targets(&self.device, self.command_pool);
+        }
+    }
+
+    fn wait_device_idle(&self) {
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+    }
+
+    fn resize_framebuffer(&mut self) {
+        self.views[0].target.mark_resized();
+    }
+
+    fn window_ref(&self) -> &winit::window::Window {
+        &self.views[0].target.window
+    }
+
+    fn has_window(&self, window_id: WindowId) -> bool {
+        self.find_view(window_id).is_some()
+    }
+
+    fn resize_window(&mut self, window_id: WindowId) {
+        if let Some(view_index) = self.find_view(window_id) {
+            self.views[view_index].target.mark_resized();
+        }
+    }
+
+    fn close_window(&mut self, window_id: WindowId) {
+        if let Some(view_index) = self.find_view(window_id) {
+            self.wait_device_idle();
+
+            // dropping the view closes its window.
+            let view = self.views.remove(view_index);
+            view.cleanup_render_targets(&self.device, self.command_pool);
+            view.target.destroy(&self.device);
+        }
+    }
+    fn is_minimized(&self) -> bool {
+        // a minimized window is skipped by `draw_view`, the others keep drawing.
+        self.views.iter().all(|view| view.target.is_minimized())
+    }
+}
+
+fn main() {
+    let program_proc = ProgramProc::new();
+    let vulkan_app = VulkanApp34::new(&program_proc.event_loop);
+
+    program_proc.main_loop(vulkan_app);
+}
+// -------------------------------------------------------------------------------------------
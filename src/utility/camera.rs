@@ -0,0 +1,381 @@
+//! Cameras that produce the `view` and `proj` matrices of `UniformBufferObject`.
+//!
+//! The world is Z up, like the `look_at` views the examples started with. `OrbitCamera` turns
+//! around a target while the left mouse button is dragged, pans with the right or middle button
+//! and zooms with the wheel. `FlyCamera` moves with the `move_*` actions and looks around while
+//! the right mouse button is held. Both keep a `Projection` whose aspect ratio follows the
+//! swapchain extent, with the Y axis flipped for the Vulkan clip space.
+
+use ash::vk;
+use cgmath::{Deg, EuclideanSpace, InnerSpace, Matrix4, Point3, Rad, Vector3, Zero};
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode};
+
+use crate::utility::input::{ActionMap, Binding};
+use crate::utility::structures::UniformBufferObject;
+
+pub const MOVE_FORWARD: &str = "move_forward";
+pub const MOVE_BACKWARD: &str = "move_backward";
+pub const MOVE_LEFT: &str = "move_left";
+pub const MOVE_RIGHT: &str = "move_right";
+pub const MOVE_UP: &str = "move_up";
+pub const MOVE_DOWN: &str = "move_down";
+
+const WORLD_UP: Vector3<f32> = Vector3 {
+    x: 0.0,
+    y: 0.0,
+    z: 1.0,
+};
+// keep the pitch away from the poles, where the view direction and the up vector line up.
+const MAX_PITCH: Deg<f32> = Deg(89.0);
+// radians per pixel of mouse motion.
+const DEFAULT_MOUSE_SENSITIVITY: f32 = 0.005;
+// how many pixels of a touchpad scroll count as one wheel line.
+const PIXELS_PER_LINE: f32 = 20.0;
+
+/// Register the `move_*` actions used by `FlyCamera`, bound to WASD, E and Q.
+pub fn register_actions(actions: &mut ActionMap) {
+    actions.register(MOVE_FORWARD, &[Binding::key(VirtualKeyCode::W)]);
+    actions.register(MOVE_BACKWARD, &[Binding::key(VirtualKeyCode::S)]);
+    actions.register(MOVE_LEFT, &[Binding::key(VirtualKeyCode::A)]);
+    actions.register(MOVE_RIGHT, &[Binding::key(VirtualKeyCode::D)]);
+    actions.register(MOVE_UP, &[Binding::key(VirtualKeyCode::E)]);
+    actions.register(MOVE_DOWN, &[Binding::key(VirtualKeyCode::Q)]);
+}
+
+/// A perspective projection for the Vulkan clip space.
+#[derive(Debug, Clone, Copy)]
+pub struct Projection {
+    pub fov_y: Deg<f32>,
+    pub near: f32,
+    pub far: f32,
+    aspect: f32,
+}
+
+impl Projection {
+    pub fn new(fov_y: Deg<f32>, near: f32, far: f32, extent: vk::Extent2D) -> Projection {
+        let mut projection = Projection {
+            fov_y,
+            near,
+            far,
+            aspect: 1.0,
+        };
+        projection.set_extent(extent);
+
+        projection
+    }
+
+    /// Follow the size of a new swapchain.
+    pub fn set_extent(&mut self, extent: vk::Extent2D) {
+        // a minimized window has a zero sized extent, keep the last aspect ratio then.
+        if extent.width > 0 && extent.height > 0 {
+            self.aspect = extent.width as f32 / extent.height as f32;
+        }
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.aspect
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        let mut proj = cgmath::perspective(self.fov_y, self.aspect, self.near, self.far);
+        // the Y axis of the Vulkan clip space points down, unlike OpenGL which cgmath follows.
+        proj[1][1] = -proj[1][1];
+
+        proj
+    }
+}
+
+/// The direction a camera at `yaw` and `pitch` looks to.
+fn direction(yaw: Rad<f32>, pitch: Rad<f32>) -> Vector3<f32> {
+    Vector3::new(
+        pitch.0.cos() * yaw.0.cos(),
+        pitch.0.cos() * yaw.0.sin(),
+        pitch.0.sin(),
+    )
+}
+
+/// The yaw and pitch of the direction of `offset`, the inverse of `direction`. A zero offset has
+/// no direction, the X axis is used instead.
+fn yaw_and_pitch(offset: Vector3<f32>) -> (Rad<f32>, Rad<f32>) {
+    let distance = offset.magnitude();
+    if distance == 0.0 {
+        return (Rad(0.0), Rad(0.0));
+    }
+
+    let yaw = Rad(offset.y.atan2(offset.x));
+    // rounding may push the sine just past 1.
+    let pitch = Rad((offset.z / distance).clamp(-1.0, 1.0).asin());
+    (yaw, clamp_pitch(pitch))
+}
+
+fn clamp_pitch(pitch: Rad<f32>) -> Rad<f32> {
+    let max_pitch = Rad::from(MAX_PITCH).0;
+    Rad(pitch.0.clamp(-max_pitch, max_pitch))
+}
+
+fn scroll_lines(delta: MouseScrollDelta) -> f32 {
+    match delta {
+        MouseScrollDelta::LineDelta(_, lines) => lines,
+        MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE,
+    }
+}
+
+/// The input hooks and matrices shared by the camera controllers.
+///
+/// Apps forward their `VulkanApp` hooks of the same names, the hooks a camera does not need do
+/// nothing by default.
+pub trait Camera {
+    /// The view matrix `alpha` of the way from the previous fixed update to the last one.
+    fn view(&self, alpha: f32) -> Matrix4<f32>;
+    fn projection(&self) -> &Projection;
+    fn projection_mut(&mut self) -> &mut Projection;
+
+    /// Read the held actions, once per frame.
+    fn on_input(&mut self, _actions: &ActionMap) {}
+    /// Move by one fixed update of `fixed_dt` seconds.
+    fn update(&mut self, _fixed_dt: f32) {}
+    fn on_mouse_motion(&mut self, _delta: (f64, f64)) {}
+    fn on_mouse_button(&mut self, _button: MouseButton, _state: ElementState) {}
+    fn on_mouse_wheel(&mut self, _delta: MouseScrollDelta) {}
+    /// Stop dragging, for when the window loses focus and misses the button releases.
+    fn release_mouse(&mut self) {}
+
+    fn set_extent(&mut self, extent: vk::Extent2D) {
+        self.projection_mut().set_extent(extent);
+    }
+
+    /// Write the view and projection into `ubo`, leaving its model matrix alone.
+    fn apply(&self, ubo: &mut UniformBufferObject, alpha: f32) {
+        ubo.view = self.view(alpha);
+        ubo.proj = self.projection().matrix();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Drag {
+    Rotate,
+    Pan,
+}
+
+/// Turn around a target point, for looking at a single model.
+pub struct OrbitCamera {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    pub sensitivity: f32,
+    projection: Projection,
+    drag: Option<Drag>,
+}
+
+impl OrbitCamera {
+    /// Look at `target` from `eye`, zooming stays within the near and far planes of `projection`.
+    pub fn new(eye: Point3<f32>, target: Point3<f32>, projection: Projection) -> OrbitCamera {
+        let min_distance = projection.near * 2.0;
+        let (yaw, pitch) = yaw_and_pitch(eye - target);
+
+        OrbitCamera {
+            target,
+            distance: (eye - target).magnitude().max(min_distance),
+            yaw,
+            pitch,
+            min_distance,
+            max_distance: projection.far * 0.5,
+            sensitivity: DEFAULT_MOUSE_SENSITIVITY,
+            projection,
+            drag: None,
+        }
+    }
+
+    pub fn eye(&self) -> Point3<f32> {
+        self.target + direction(self.yaw, self.pitch) * self.distance
+    }
+}
+
+impl Camera for OrbitCamera {
+    fn view(&self, _alpha: f32) -> Matrix4<f32> {
+        Matrix4::look_at(self.eye(), self.target, WORLD_UP)
+    }
+
+    fn projection(&self) -> &Projection {
+        &self.projection
+    }
+
+    fn projection_mut(&mut self) -> &mut Projection {
+        &mut self.projection
+    }
+
+    fn on_mouse_motion(&mut self, delta: (f64, f64)) {
+        let (dx, dy) = (delta.0 as f32, delta.1 as f32);
+
+        match self.drag {
+            Some(Drag::Rotate) => {
+                self.yaw -= Rad(dx * self.sensitivity);
+                self.pitch = clamp_pitch(self.pitch + Rad(dy * self.sensitivity));
+            }
+            Some(Drag::Pan) => {
+                let forward = -direction(self.yaw, self.pitch);
+                let right = forward.cross(WORLD_UP).normalize();
+                let up = right.cross(forward);
+                // move the target as far as the point under the cursor moves on screen.
+                let scale = self.distance * self.sensitivity;
+                self.target += (up * dy - right * dx) * scale;
+            }
+            None => {}
+        }
+    }
+
+    fn on_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        let drag = match button {
+            MouseButton::Left => Drag::Rotate,
+            MouseButton::Right | MouseButton::Middle => Drag::Pan,
+            MouseButton::Other(_) => return,
+        };
+
+        match state {
+            ElementState::Pressed => self.drag = Some(drag),
+            ElementState::Released => {
+                if self.drag == Some(drag) {
+                    self.drag = None;
+                }
+            }
+        }
+    }
+
+    fn on_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        // every line zooms by the same ratio, so the zoom feels the same near and far.
+        self.distance = (self.distance * 0.9_f32.powf(scroll_lines(delta)))
+            .clamp(self.min_distance, self.max_distance);
+    }
+
+    fn release_mouse(&mut self) {
+        self.drag = None;
+    }
+}
+
+/// Move freely through the scene like a first person spectator.
+pub struct FlyCamera {
+    pub yaw: Rad<f32>,
+    pub pitch: Rad<f32>,
+    /// Units per second.
+    pub speed: f32,
+    pub sensitivity: f32,
+    projection: Projection,
+    position: Point3<f32>,
+    previous_position: Point3<f32>,
+    // the held move actions as (forward, right, up), each from -1 to 1.
+    movement: Vector3<f32>,
+    is_looking: bool,
+}
+
+impl FlyCamera {
+    pub fn new(eye: Point3<f32>, target: Point3<f32>, projection: Projection) -> FlyCamera {
+        let (yaw, pitch) = yaw_and_pitch(target - eye);
+
+        FlyCamera {
+            yaw,
+            pitch,
+            speed: 2.0,
+            sensitivity: DEFAULT_MOUSE_SENSITIVITY,
+            projection,
+            position: eye,
+            previous_position: eye,
+            movement: Vector3::zero(),
+            is_looking: false,
+        }
+    }
+
+    pub fn position(&self) -> Point3<f32> {
+        self.position
+    }
+
+    pub fn set_position(&mut self, position: Point3<f32>) {
+        self.position = position;
+        self.previous_position = position;
+    }
+}
+
+impl Camera for FlyCamera {
+    fn view(&self, alpha: f32) -> Matrix4<f32> {
+        let eye = self.previous_position + (self.position - self.previous_position) * alpha;
+        Matrix4::look_at_dir(eye, direction(self.yaw, self.pitch), WORLD_UP)
+    }
+
+    fn projection(&self) -> &Projection {
+        &self.projection
+    }
+
+    fn projection_mut(&mut self) -> &mut Projection {
+        &mut self.projection
+    }
+
+    fn on_input(&mut self, actions: &ActionMap) {
+        let axis = |positive: &str, negative: &str| {
+            actions.is_held(positive) as i32 as f32 - actions.is_held(negative) as i32 as f32
+        };
+
+        self.movement = Vector3::new(
+            axis(MOVE_FORWARD, MOVE_BACKWARD),
+            axis(MOVE_RIGHT, MOVE_LEFT),
+            axis(MOVE_UP, MOVE_DOWN),
+        );
+    }
+
+    fn update(&mut self, fixed_dt: f32) {
+        self.previous_position = self.position;
+
+        // walk in the horizontal plane whatever the pitch, and rise along the world up.
+        let forward = direction(self.yaw, Rad(0.0));
+        let right = forward.cross(WORLD_UP);
+        let velocity =
+            forward * self.movement.x + right * self.movement.y + WORLD_UP * self.movement.z;
+
+        if velocity != Vector3::zero() {
+            self.position += velocity.normalize() * self.speed * fixed_dt;
+        }
+    }
+
+    fn on_mouse_motion(&mut self, delta: (f64, f64)) {
+        if self.is_looking {
+            self.yaw -= Rad(delta.0 as f32 * self.sensitivity);
+            self.pitch = clamp_pitch(self.pitch - Rad(delta.1 as f32 * self.sensitivity));
+        }
+    }
+
+    fn on_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        if button == MouseButton::Right {
+            self.is_looking = state == ElementState::Pressed;
+        }
+    }
+
+    fn on_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        self.speed = (self.speed * 1.25_f32.powf(scroll_lines(delta))).max(0.01);
+    }
+
+    fn release_mouse(&mut self) {
+        self.is_looking = false;
+    }
+}
+
+impl From<&OrbitCamera> for FlyCamera {
+    /// Fly on from where an orbit camera looks.
+    fn from(orbit_camera: &OrbitCamera) -> FlyCamera {
+        FlyCamera::new(
+            orbit_camera.eye(),
+            orbit_camera.target,
+            orbit_camera.projection,
+        )
+    }
+}
+
+impl From<&FlyCamera> for OrbitCamera {
+    /// Orbit around the point a fly camera looks at, at the distance of the origin.
+    fn from(fly_camera: &FlyCamera) -> OrbitCamera {
+        let distance = fly_camera.position.to_vec().magnitude().max(1.0);
+        let target = fly_camera.position + direction(fly_camera.yaw, fly_camera.pitch) * distance;
+
+        OrbitCamera::new(fly_camera.position, target, fly_camera.projection)
+    }
+}
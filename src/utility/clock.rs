@@ -0,0 +1,96 @@
+//! The time source of the main loop.
+//!
+//! The simulation only sees the time a clock reports, so a clock that does not depend on how
+//! long frames really take makes the animation, and so the rendered images, reproducible.
+
+use std::cell::Cell;
+use std::rc::Rc;
+use std::time::Instant;
+
+pub trait Clock {
+    /// The time in seconds since the previous frame, called once per frame.
+    fn tick(&mut self) -> f32;
+}
+
+/// Follow the system clock.
+#[derive(Default)]
+pub struct RealTimeClock {
+    // `None` until the first frame, so the time spent on setup is not simulated.
+    last_tick: Option<Instant>,
+}
+
+impl RealTimeClock {
+    pub fn new() -> RealTimeClock {
+        RealTimeClock { last_tick: None }
+    }
+}
+
+impl Clock for RealTimeClock {
+    fn tick(&mut self) -> f32 {
+        let now = Instant::now();
+        let delta_time = self
+            .last_tick
+            .map_or(0.0, |last_tick| (now - last_tick).as_secs_f32());
+        self.last_tick = Some(now);
+
+        delta_time
+    }
+}
+
+/// Advance by the same step every frame, however long the frame takes.
+pub struct FixedStepClock {
+    step: f32,
+}
+
+impl FixedStepClock {
+    pub fn new(step: f32) -> FixedStepClock {
+        assert!(step >= 0.0, "The clock step can not be negative.");
+
+        FixedStepClock { step }
+    }
+}
+
+impl Clock for FixedStepClock {
+    fn tick(&mut self) -> f32 {
+        self.step
+    }
+}
+
+/// Only advance when told to, through any `ManualClockHandle` of the clock.
+#[derive(Default)]
+pub struct ManualClock {
+    pending: Rc<Cell<f32>>,
+}
+
+/// Advance a `ManualClock` from outside of the main loop, for example from a key press.
+#[derive(Clone)]
+pub struct ManualClockHandle {
+    pending: Rc<Cell<f32>>,
+}
+
+impl ManualClock {
+    pub fn new() -> ManualClock {
+        ManualClock {
+            pending: Rc::new(Cell::new(0.0)),
+        }
+    }
+
+    pub fn handle(&self) -> ManualClockHandle {
+        ManualClockHandle {
+            pending: self.pending.clone(),
+        }
+    }
+}
+
+impl ManualClockHandle {
+    /// Move the clock forward by `seconds`, the next frame sees all of it at once.
+    pub fn advance(&self, seconds: f32) {
+        self.pending.set(self.pending.get() + seconds);
+    }
+}
+
+impl Clock for ManualClock {
+    fn tick(&mut self) -> f32 {
+        self.pending.replace(0.0)
+    }
+}
@@ -0,0 +1,182 @@
+//! Windowed, borderless fullscreen and exclusive fullscreen display modes.
+//!
+//! Borderless fullscreen covers a monitor at its current resolution, exclusive fullscreen also
+//! switches the monitor to a video mode. Either way the surface changes size, so the swapchain
+//! has to be recreated after switching, the same as after a resize.
+
+use winit::monitor::{MonitorHandle, VideoMode};
+use winit::window::{Fullscreen, Window};
+
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisplayMode {
+    Windowed,
+    Borderless,
+    Exclusive,
+}
+
+impl FromStr for DisplayMode {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<DisplayMode, String> {
+        match text {
+            "windowed" => Ok(DisplayMode::Windowed),
+            "borderless" => Ok(DisplayMode::Borderless),
+            "exclusive" => Ok(DisplayMode::Exclusive),
+            _ => Err(format!("unknown display mode '{}'", text)),
+        }
+    }
+}
+
+/// The resolution, and optionally the refresh rate, wanted for exclusive fullscreen.
+///
+/// Written as `<width>x<height>` or `<width>x<height>@<refresh rate>`, like `1920x1080@144`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VideoModeRequest {
+    pub width: u32,
+    pub height: u32,
+    pub refresh_rate: Option<u16>,
+}
+
+impl FromStr for VideoModeRequest {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<VideoModeRequest, String> {
+        let invalid = || {
+            format!(
+                "invalid video mode '{}', expected <width>x<height>[@<hz>]",
+                text
+            )
+        };
+
+        let mut parts = text.splitn(2, '@');
+        let size = parts.next().unwrap();
+        let refresh_rate = match parts.next() {
+            Some(refresh_rate) => Some(refresh_rate.parse().map_err(|_| invalid())?),
+            None => None,
+        };
+
+        let mut dimensions = size.splitn(2, 'x');
+        let width = dimensions.next().unwrap().parse().map_err(|_| invalid())?;
+        let height = dimensions
+            .next()
+            .ok_or_else(invalid)?
+            .parse()
+            .map_err(|_| invalid())?;
+
+        Ok(VideoModeRequest {
+            width,
+            height,
+            refresh_rate,
+        })
+    }
+}
+
+/// How the window is shown, and on which monitor when it is fullscreen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DisplaySettings {
+    pub mode: DisplayMode,
+    /// The index of the monitor in `Window::available_monitors`, `None` for the primary one.
+    pub monitor: Option<usize>,
+    /// The video mode of exclusive fullscreen, `None` for the native resolution of the monitor.
+    pub video_mode: Option<VideoModeRequest>,
+}
+
+impl Default for DisplaySettings {
+    fn default() -> DisplaySettings {
+        DisplaySettings {
+            mode: DisplayMode::Windowed,
+            monitor: None,
+            video_mode: None,
+        }
+    }
+}
+
+impl DisplaySettings {
+    /// The fullscreen mode the fullscreen toggle switches to, borderless if the settings are
+    /// for a window.
+    pub fn fullscreen_mode(&self) -> DisplayMode {
+        match self.mode {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            mode => mode,
+        }
+    }
+
+    /// The monitor to go fullscreen on, the primary one if the chosen monitor is not there.
+    pub fn select_monitor(&self, window: &Window) -> MonitorHandle {
+        if let Some(monitor_index) = self.monitor {
+            match window.available_monitors().nth(monitor_index) {
+                Some(monitor) => return monitor,
+                None => {
+                    println!(
+                        "Monitor {} not found, the available monitors are:",
+                        monitor_index
+                    );
+                    for (index, monitor) in window.available_monitors().enumerate() {
+                        let name = monitor.name().unwrap_or_else(|| "unnamed".to_string());
+                        println!(
+                            "\t{}: {} ({}x{})",
+                            index,
+                            name,
+                            monitor.size().width,
+                            monitor.size().height
+                        );
+                    }
+                }
+            }
+        }
+
+        window.primary_monitor()
+    }
+
+    /// Switch `window` to `mode` on the monitor and video mode of the settings.
+    pub fn apply(&self, window: &Window, mode: DisplayMode) {
+        let fullscreen = match mode {
+            DisplayMode::Windowed => None,
+            DisplayMode::Borderless => Some(Fullscreen::Borderless(self.select_monitor(window))),
+            DisplayMode::Exclusive => {
+                let monitor = self.select_monitor(window);
+                match select_video_mode(&monitor, self.video_mode) {
+                    Some(video_mode) => {
+                        println!("Exclusive fullscreen in {}", video_mode);
+                        Some(Fullscreen::Exclusive(video_mode))
+                    }
+                    None => {
+                        println!("The monitor reports no video mode, use borderless fullscreen.");
+                        Some(Fullscreen::Borderless(monitor))
+                    }
+                }
+            }
+        };
+
+        window.set_fullscreen(fullscreen);
+    }
+}
+
+/// The video mode of `monitor` closest to `request`, the native resolution if there is no
+/// request. Among modes of the same size the highest refresh rate and bit depth win, unless the
+/// request asks for a refresh rate.
+pub fn select_video_mode(
+    monitor: &MonitorHandle,
+    request: Option<VideoModeRequest>,
+) -> Option<VideoMode> {
+    let (width, height) = match request {
+        Some(request) => (request.width, request.height),
+        None => (monitor.size().width, monitor.size().height),
+    };
+    let refresh_rate = request.and_then(|request| request.refresh_rate);
+
+    monitor.video_modes().max_by_key(|video_mode| {
+        let size = video_mode.size();
+        (
+            size.width == width && size.height == height,
+            // otherwise the largest mode that fits.
+            size.width <= width && size.height <= height,
+            Some(video_mode.refresh_rate()) == refresh_rate,
+            size.width * size.height,
+            video_mode.refresh_rate(),
+            video_mode.bit_depth(),
+        )
+    })
+}
@@ -0,0 +1,86 @@
+//! Fixed rate simulation updates decoupled from the frame rate.
+//!
+//! The time of every frame goes into an accumulator, and the simulation advances by whole fixed
+//! steps as long as there is enough time in it. What is left over, as a fraction of a step, is
+//! the interpolation factor a frame uses to draw between the last two simulation states.
+
+pub const DEFAULT_UPDATE_RATE: f32 = 60.0;
+pub const DEFAULT_MAX_UPDATE_STEPS: usize = 5;
+
+pub struct FixedTimestep {
+    // seconds per update.
+    step: f32,
+    accumulator: f32,
+    max_steps: usize,
+}
+
+impl FixedTimestep {
+    /// Run `update_rate` updates per second, at most `max_steps` in one frame.
+    pub fn new(update_rate: f32, max_steps: usize) -> FixedTimestep {
+        assert!(update_rate > 0.0, "The update rate must be positive.");
+        assert!(
+            max_steps > 0,
+            "At least one update step per frame is needed."
+        );
+
+        FixedTimestep {
+            step: 1.0 / update_rate,
+            accumulator: 0.0,
+            max_steps,
+        }
+    }
+
+    /// The simulated time of one update, in seconds.
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+
+    /// Add the time of a frame and return how many updates to run.
+    ///
+    /// After a frame longer than `max_steps` updates, the simulation drops the time it cannot
+    /// catch up with instead of running further behind every frame.
+    pub fn advance(&mut self, delta_time: f32) -> usize {
+        self.accumulator += delta_time;
+
+        let step_count = (self.accumulator / self.step) as usize;
+        if step_count > self.max_steps {
+            self.accumulator %= self.step;
+            self.max_steps
+        } else {
+            self.accumulator -= step_count as f32 * self.step;
+            step_count
+        }
+    }
+
+    /// How far the current time is past the last update, from 0 to 1 of a step.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).min(1.0)
+    }
+}
+
+/// A value changed by fixed updates that keeps its previous state to interpolate in between.
+#[derive(Debug, Clone, Copy)]
+pub struct InterpolatedValue {
+    previous: f32,
+    current: f32,
+}
+
+impl InterpolatedValue {
+    pub fn new(value: f32) -> InterpolatedValue {
+        InterpolatedValue {
+            previous: value,
+            current: value,
+        }
+    }
+
+    /// Move the value by `delta` in an update.
+    pub fn advance(&mut self, delta: f32) {
+        self.previous = self.current;
+        self.current += delta;
+    }
+
+    /// The value `alpha` of the way from the previous update to the last one.
+    pub fn value(&self, alpha: f32) -> f32 {
+        self.previous + (self.current - self.previous) * alpha
+    }
+}
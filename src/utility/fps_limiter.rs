@@ -1,67 +1,220 @@
+//! Frame pacing and frame time statistics.
+//!
+//! `thread::sleep` only promises to sleep at least as long as asked, and usually oversleeps by
+//! up to a scheduler tick, so the limiter sleeps until shortly before the target and spins for
+//! the rest of the frame.
+
+use std::collections::VecDeque;
+use std::fmt;
 use std::thread;
 use std::time::Duration;
 use std::time::Instant;
 
-const SAMPLE_COUNT: usize = 5;
-const SAMPLE_COUNT_FLOAT: f32 = SAMPLE_COUNT as f32;
+pub const DEFAULT_STATS_WINDOW: usize = 1000;
+const DEFAULT_SPIN_THRESHOLD: Duration = Duration::from_micros(2000);
+const HISTOGRAM_BUCKET_COUNT: usize = 10;
+const HISTOGRAM_BAR_WIDTH: usize = 40;
+
+/// Frame time statistics over the frames of the stats window.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    pub frame_count: usize,
+    pub mean_ms: f32,
+    pub min_ms: f32,
+    pub max_ms: f32,
+    /// The average FPS of the slowest 1% of the frames.
+    pub low_1_percent_fps: f32,
+    /// The average FPS of the slowest 0.1% of the frames.
+    pub low_0_1_percent_fps: f32,
+    /// Frame counts of equal width buckets spanning `min_ms` to `max_ms`.
+    pub histogram: Vec<usize>,
+}
+
+impl FrameStats {
+    fn new(samples: &VecDeque<Duration>) -> FrameStats {
+        let mut frame_times: Vec<f32> = samples
+            .iter()
+            .map(|sample| sample.as_secs_f32() * 1000.0)
+            .collect();
+        // slowest first.
+        frame_times.sort_by(|a, b| b.partial_cmp(a).unwrap());
+
+        let frame_count = frame_times.len();
+        let max_ms = frame_times[0];
+        let min_ms = frame_times[frame_count - 1];
+        let mean_ms = frame_times.iter().sum::<f32>() / frame_count as f32;
+
+        let low_fps = |fraction: f32| {
+            let count = ((frame_count as f32 * fraction).ceil() as usize).max(1);
+            let mean_ms = frame_times[..count].iter().sum::<f32>() / count as f32;
+            1000.0 / mean_ms
+        };
+
+        let bucket_width = (max_ms - min_ms) / HISTOGRAM_BUCKET_COUNT as f32;
+        let mut histogram = vec![0; HISTOGRAM_BUCKET_COUNT];
+        for &frame_time in frame_times.iter() {
+            let bucket = if bucket_width > 0.0 {
+                ((frame_time - min_ms) / bucket_width) as usize
+            } else {
+                0
+            };
+            histogram[bucket.min(HISTOGRAM_BUCKET_COUNT - 1)] += 1;
+        }
+
+        FrameStats {
+            frame_count,
+            mean_ms,
+            min_ms,
+            max_ms,
+            low_1_percent_fps: low_fps(0.01),
+            low_0_1_percent_fps: low_fps(0.001),
+            histogram,
+        }
+    }
+
+    pub fn mean_fps(&self) -> f32 {
+        1000.0 / self.mean_ms
+    }
+}
+
+impl fmt::Display for FrameStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} frames: {:.1} FPS, {:.3} ms mean, {:.3} ms min, {:.3} ms max",
+            self.frame_count,
+            self.mean_fps(),
+            self.mean_ms,
+            self.min_ms,
+            self.max_ms,
+        )?;
+        writeln!(
+            f,
+            "1% low: {:.1} FPS, 0.1% low: {:.1} FPS",
+            self.low_1_percent_fps, self.low_0_1_percent_fps,
+        )?;
+
+        let bucket_width = (self.max_ms - self.min_ms) / self.histogram.len() as f32;
+        let largest_bucket = self.histogram.iter().cloned().max().unwrap_or(0).max(1);
+        for (i, &count) in self.histogram.iter().enumerate() {
+            writeln!(
+                f,
+                "{:>8.3} ms | {:<bar_width$} {}",
+                self.min_ms + bucket_width * i as f32,
+                "#".repeat(count * HISTOGRAM_BAR_WIDTH / largest_bucket),
+                count,
+                bar_width = HISTOGRAM_BAR_WIDTH,
+            )?;
+        }
+
+        Ok(())
+    }
+}
 
 pub struct FPSLimiter {
     counter: Instant,
-    frame_time_prefer: u32, // unit microseconds
-    samples: [u32; SAMPLE_COUNT],
-    current_frame: usize,
-    delta_frame: u32,
+    // `None` means the frame rate is not limited.
+    frame_time_prefer: Option<Duration>,
+    // sleep until this long before the end of the frame, then spin.
+    spin_threshold: Duration,
+    delta_frame: Duration,
+    samples: VecDeque<Duration>,
+    stats_window: usize,
 }
 
 impl FPSLimiter {
+    /// Create a limiter without a frame rate limit.
     pub fn new() -> FPSLimiter {
-        const DEFAULT_PREFER_FPS: f32 = 60.0;
-
         FPSLimiter {
             counter: Instant::now(),
-            frame_time_prefer: (1000_000.0_f32 / DEFAULT_PREFER_FPS) as u32,
-            samples: [0; SAMPLE_COUNT],
-            current_frame: 0,
-            delta_frame: 0,
+            frame_time_prefer: None,
+            spin_threshold: DEFAULT_SPIN_THRESHOLD,
+            delta_frame: Duration::from_secs(0),
+            samples: VecDeque::with_capacity(DEFAULT_STATS_WINDOW),
+            stats_window: DEFAULT_STATS_WINDOW,
         }
     }
 
-    pub fn set_prefer_fps(&mut self, prefer_fps: f32) {
-        self.frame_time_prefer = (1000_000.0_f32 / prefer_fps) as u32;
+    /// Limit the frame rate to `prefer_fps`, or remove the limit with `None`. A frame rate that
+    /// is not a positive finite number removes the limit as well.
+    pub fn set_prefer_fps(&mut self, prefer_fps: Option<f32>) {
+        self.frame_time_prefer = prefer_fps
+            .filter(|prefer_fps| prefer_fps.is_finite() && *prefer_fps > 0.0)
+            .and_then(|prefer_fps| Duration::try_from_secs_f32(1.0 / prefer_fps).ok());
+    }
+
+    /// How long before the end of the frame `keep_fps` stops sleeping and starts spinning.
+    /// Larger values are more accurate on coarse schedulers, at the cost of CPU time.
+    pub fn set_spin_threshold(&mut self, spin_threshold: Duration) {
+        self.spin_threshold = spin_threshold;
+    }
+
+    /// Compute the statistics over the last `frame_count` frames.
+    pub fn set_stats_window(&mut self, frame_count: usize) {
+        assert!(
+            frame_count > 0,
+            "The stats window needs at least one frame."
+        );
+
+        self.stats_window = frame_count;
+        while self.samples.len() > frame_count {
+            self.samples.pop_front();
+        }
     }
 
     /// Call this function in game loop to update its inner status.
     pub fn tick_frame(&mut self) {
-        let time_elapsed = self.counter.elapsed();
-        self.counter = Instant::now();
+        let now = Instant::now();
+        self.delta_frame = now - self.counter;
+        self.counter = now;
 
-        self.delta_frame = time_elapsed.subsec_micros();
-        self.samples[self.current_frame] = self.delta_frame;
-        self.current_frame = (self.current_frame + 1) % SAMPLE_COUNT;
+        if self.samples.len() == self.stats_window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(self.delta_frame);
     }
 
-    // TODO: this function seems not work.
+    /// Block until the preferred frame time has passed since the last `tick_frame`.
+    ///
+    /// A frame that is already longer than the preferred frame time returns at once, and the
+    /// next frame starts from there instead of trying to catch up.
     pub fn keep_fps(&self) {
-        if self.frame_time_prefer > self.delta_frame {
-            let delay = Duration::from_micros((self.frame_time_prefer - self.delta_frame) as u64);
+        let frame_time_prefer = match self.frame_time_prefer {
+            Some(frame_time_prefer) => frame_time_prefer,
+            None => return,
+        };
+        let deadline = self.counter + frame_time_prefer;
 
-            thread::sleep(delay);
+        let now = Instant::now();
+        if deadline > now + self.spin_threshold {
+            thread::sleep(deadline - now - self.spin_threshold);
+        }
+        while Instant::now() < deadline {
+            std::hint::spin_loop();
         }
     }
 
-    /// Calculate the current FPS.
+    /// Calculate the mean FPS over the stats window.
     pub fn fps(&self) -> f32 {
-        let mut sum = 0_u32;
-        self.samples.iter().for_each(|val| {
-            sum += val;
-        });
+        if self.samples.is_empty() {
+            return 0.0;
+        }
+
+        let sum: Duration = self.samples.iter().sum();
+        self.samples.len() as f32 / sum.as_secs_f32()
+    }
 
-        1000_000.0_f32 / (sum as f32 / SAMPLE_COUNT_FLOAT)
+    /// The frame time statistics over the stats window, `None` before the first frame.
+    pub fn stats(&self) -> Option<FrameStats> {
+        if self.samples.is_empty() {
+            None
+        } else {
+            Some(FrameStats::new(&self.samples))
+        }
     }
 
-    /// Return current delta time in seconds
-    /// this function ignore its second part, since the second is mostly zero.
+    /// Return the duration of the last frame in seconds.
     pub fn delta_time(&self) -> f32 {
-        self.delta_frame as f32 / 1000_000.0_f32 // time in second
+        self.delta_frame.as_secs_f32()
     }
 }
@@ -0,0 +1,235 @@
+//! Primary command buffers that are either pre-recorded once per swapchain image, or reset and
+//! re-recorded from the draw list every frame.
+//!
+//! Pre-recorded command buffers are cheap to submit, but any change to what is drawn means
+//! waiting for the device and recording all of them again. Re-recording every frame costs some
+//! CPU time per frame, and in return the draw list and the render state can change freely.
+
+use ash::version::DeviceV1_0;
+use ash::vk;
+
+use std::ptr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingMode {
+    /// One command buffer per swapchain image, recorded again only when invalidated.
+    PreRecorded,
+    /// One command buffer per frame in flight, reset and recorded again every frame.
+    PerFrame,
+}
+
+impl RecordingMode {
+    pub fn toggle(self) -> RecordingMode {
+        match self {
+            RecordingMode::PreRecorded => RecordingMode::PerFrame,
+            RecordingMode::PerFrame => RecordingMode::PreRecorded,
+        }
+    }
+}
+
+/// One indexed draw of a draw list.
+#[derive(Debug, Clone, Copy)]
+pub struct DrawItem {
+    pub index_count: u32,
+    pub instance_count: u32,
+    pub first_index: u32,
+    pub vertex_offset: i32,
+}
+
+impl DrawItem {
+    pub fn cmd_draw(&self, device: &ash::Device, command_buffer: vk::CommandBuffer) {
+        unsafe {
+            device.cmd_draw_indexed(
+                command_buffer,
+                self.index_count,
+                self.instance_count,
+                self.first_index,
+                self.vertex_offset,
+                0,
+            );
+        }
+    }
+}
+
+pub struct FrameCommandBuffers {
+    mode: RecordingMode,
+    command_pool: vk::CommandPool,
+    // one per swapchain image.
+    pre_recorded: Vec<vk::CommandBuffer>,
+    // one per frame in flight.
+    per_frame: Vec<vk::CommandBuffer>,
+    is_outdated: bool,
+}
+
+impl FrameCommandBuffers {
+    pub fn new(
+        device: &ash::Device,
+        queue_family_index: u32,
+        mode: RecordingMode,
+        image_count: usize,
+        frame_count: usize,
+    ) -> FrameCommandBuffers {
+        // every command buffer is reset on its own, so the pool must allow it.
+        let command_pool_create_info = vk::CommandPoolCreateInfo {
+            s_type: vk::StructureType::COMMAND_POOL_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::CommandPoolCreateFlags::RESET_COMMAND_BUFFER,
+            queue_family_index,
+        };
+
+        let command_pool = unsafe {
+            device
+                .create_command_pool(&command_pool_create_info, None)
+                .expect("Failed to create Command Pool!")
+        };
+
+        FrameCommandBuffers {
+            mode,
+            command_pool,
+            pre_recorded: allocate_command_buffers(device, command_pool, image_count),
+            per_frame: allocate_command_buffers(device, command_pool, frame_count),
+            is_outdated: true,
+        }
+    }
+
+    pub fn mode(&self) -> RecordingMode {
+        self.mode
+    }
+
+    pub fn set_mode(&mut self, mode: RecordingMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            // the pre-recorded command buffers missed every change made meanwhile.
+            self.is_outdated = true;
+        }
+    }
+
+    /// Mark the pre-recorded command buffers as out of date, they are recorded again before the
+    /// next submission. This has no cost in `RecordingMode::PerFrame`.
+    pub fn invalidate(&mut self) {
+        self.is_outdated = true;
+    }
+
+    /// Reallocate the pre-recorded command buffers for a new swapchain.
+    ///
+    /// The device must be idle.
+    pub fn resize(&mut self, device: &ash::Device, image_count: usize) {
+        unsafe {
+            device.free_command_buffers(self.command_pool, &self.pre_recorded);
+        }
+        self.pre_recorded = allocate_command_buffers(device, self.command_pool, image_count);
+        self.is_outdated = true;
+    }
+
+    /// The command buffer to submit for `frame` rendering into swapchain image `image_index`.
+    ///
+    /// `record` is called with a command buffer that has begun and the swapchain image it
+    /// targets. In `RecordingMode::PerFrame` it runs every call for the command buffer of
+    /// `frame`, which must no longer be in use by the GPU. In `RecordingMode::PreRecorded` it
+    /// only runs after an invalidation, once for every swapchain image.
+    pub fn command_buffer<F>(
+        &mut self,
+        device: &ash::Device,
+        frame: usize,
+        image_index: usize,
+        record: F,
+    ) -> vk::CommandBuffer
+    where
+        F: Fn(vk::CommandBuffer, usize),
+    {
+        match self.mode {
+            RecordingMode::PreRecorded => {
+                if self.is_outdated {
+                    // other frames in flight may still execute the old command buffers.
+                    unsafe {
+                        device
+                            .device_wait_idle()
+                            .expect("Failed to wait device idle!");
+                    }
+
+                    for (i, &command_buffer) in self.pre_recorded.iter().enumerate() {
+                        record_command_buffer(
+                            device,
+                            command_buffer,
+                            vk::CommandBufferUsageFlags::SIMULTANEOUS_USE,
+                            |command_buffer| record(command_buffer, i),
+                        );
+                    }
+                    self.is_outdated = false;
+                }
+
+                self.pre_recorded[image_index]
+            }
+            RecordingMode::PerFrame => {
+                let command_buffer = self.per_frame[frame];
+                record_command_buffer(
+                    device,
+                    command_buffer,
+                    vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT,
+                    |command_buffer| record(command_buffer, image_index),
+                );
+
+                command_buffer
+            }
+        }
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_command_pool(self.command_pool, None);
+        }
+    }
+}
+
+fn allocate_command_buffers(
+    device: &ash::Device,
+    command_pool: vk::CommandPool,
+    count: usize,
+) -> Vec<vk::CommandBuffer> {
+    let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+        s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+        p_next: ptr::null(),
+        command_buffer_count: count as u32,
+        command_pool,
+        level: vk::CommandBufferLevel::PRIMARY,
+    };
+
+    unsafe {
+        device
+            .allocate_command_buffers(&command_buffer_allocate_info)
+            .expect("Failed to allocate Command Buffers!")
+    }
+}
+
+fn record_command_buffer<F>(
+    device: &ash::Device,
+    command_buffer: vk::CommandBuffer,
+    flags: vk::CommandBufferUsageFlags,
+    record: F,
+) where
+    F: FnOnce(vk::CommandBuffer),
+{
+    let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+        s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+        p_next: ptr::null(),
+        p_inheritance_info: ptr::null(),
+        flags,
+    };
+
+    unsafe {
+        device
+            .reset_command_buffer(command_buffer, vk::CommandBufferResetFlags::empty())
+            .expect("Failed to reset Command Buffer!");
+        device
+            .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+            .expect("Failed to begin recording Command Buffer at beginning!");
+    }
+
+    record(command_buffer);
+
+    unsafe {
+        device
+            .end_command_buffer(command_buffer)
+            .expect("Failed to record Command Buffer at Ending!");
+    }
+}
@@ -0,0 +1,267 @@
+//! GPU time per render pass and named region, measured with timestamp queries.
+//!
+//! Every slot owns a query pool. A slot is recorded into one command buffer (one per frame in
+//! flight, or one per swapchain image for pre-recorded command buffers) and read back the next
+//! time the same slot comes around, so the results lag behind by the frames in flight and
+//! reading them never waits for the GPU.
+
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use ash::vk;
+
+use std::fmt;
+use std::ptr;
+
+/// The GPU time spent in one region of a frame.
+#[derive(Debug, Clone)]
+pub struct RegionTiming {
+    pub name: String,
+    /// How many regions this region is nested in.
+    pub depth: usize,
+    pub milliseconds: f64,
+}
+
+/// The regions of one frame, in the order they began.
+#[derive(Debug, Clone, Default)]
+pub struct FrameTimings {
+    pub regions: Vec<RegionTiming>,
+}
+
+impl FrameTimings {
+    /// The time spent in the outermost regions.
+    pub fn total_milliseconds(&self) -> f64 {
+        self.regions
+            .iter()
+            .filter(|region| region.depth == 0)
+            .map(|region| region.milliseconds)
+            .sum()
+    }
+}
+
+impl fmt::Display for FrameTimings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "GPU time: {:.3} ms", self.total_milliseconds())?;
+        for region in self.regions.iter() {
+            writeln!(
+                f,
+                "{:indent$}{}: {:.3} ms",
+                "",
+                region.name,
+                region.milliseconds,
+                indent = 2 + region.depth * 2,
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RegionId(u32);
+
+struct ProfilerSlot {
+    query_pool: vk::QueryPool,
+    regions: Vec<(String, usize)>,
+    open_regions: usize,
+    // whether the queries of the slot have been recorded at all.
+    is_recorded: bool,
+}
+
+pub struct GpuProfiler {
+    slots: Vec<ProfilerSlot>,
+    max_regions: u32,
+    // nanoseconds per timestamp tick.
+    timestamp_period: f64,
+    timestamp_mask: u64,
+    latest: Option<FrameTimings>,
+}
+
+impl GpuProfiler {
+    /// Create a profiler with `slot_count` query pools of `max_regions` regions each.
+    ///
+    /// Return `None` if the queue family does not support timestamps.
+    pub fn new(
+        instance: &ash::Instance,
+        physical_device: vk::PhysicalDevice,
+        device: &ash::Device,
+        queue_family_index: u32,
+        slot_count: usize,
+        max_regions: u32,
+    ) -> Option<GpuProfiler> {
+        let queue_families =
+            unsafe { instance.get_physical_device_queue_family_properties(physical_device) };
+        let timestamp_valid_bits = queue_families[queue_family_index as usize].timestamp_valid_bits;
+        if timestamp_valid_bits == 0 {
+            return None;
+        }
+
+        let properties = unsafe { instance.get_physical_device_properties(physical_device) };
+
+        let slots = (0..slot_count)
+            .map(|_| {
+                let query_pool_create_info = vk::QueryPoolCreateInfo {
+                    s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::QueryPoolCreateFlags::empty(),
+                    query_type: vk::QueryType::TIMESTAMP,
+                    query_count: max_regions * 2,
+                    pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(),
+                };
+
+                let query_pool = unsafe {
+                    device
+                        .create_query_pool(&query_pool_create_info, None)
+                        .expect("Failed to create Query Pool!")
+                };
+
+                ProfilerSlot {
+                    query_pool,
+                    regions: vec![],
+                    open_regions: 0,
+                    is_recorded: false,
+                }
+            })
+            .collect();
+
+        Some(GpuProfiler {
+            slots,
+            max_regions,
+            timestamp_period: properties.limits.timestamp_period as f64,
+            timestamp_mask: if timestamp_valid_bits >= 64 {
+                u64::MAX
+            } else {
+                (1_u64 << timestamp_valid_bits) - 1
+            },
+            latest: None,
+        })
+    }
+
+    /// Reset the queries of `slot`, this must be recorded outside of a render pass and before
+    /// any region of the slot.
+    pub fn begin_frame(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+    ) {
+        let slot = &mut self.slots[slot];
+
+        unsafe {
+            device.cmd_reset_query_pool(command_buffer, slot.query_pool, 0, self.max_regions * 2);
+        }
+
+        slot.regions.clear();
+        slot.open_regions = 0;
+        slot.is_recorded = true;
+    }
+
+    pub fn begin_region(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+        name: &str,
+    ) -> RegionId {
+        let max_regions = self.max_regions;
+        let slot = &mut self.slots[slot];
+        assert!(
+            (slot.regions.len() as u32) < max_regions,
+            "GpuProfiler is out of regions, increase max_regions."
+        );
+
+        let region = RegionId(slot.regions.len() as u32);
+        slot.regions.push((name.to_string(), slot.open_regions));
+        slot.open_regions += 1;
+
+        unsafe {
+            device.cmd_write_timestamp(
+                command_buffer,
+                vk::PipelineStageFlags::TOP_OF_PIPE,
+                slot.query_pool,
+                region.0 * 2,
+            );
+        }
+
+        region
+    }
+
+    pub fn end_region(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+        region: RegionId,
+    ) {
+        let slot = &mut self.slots[slot];
+        slot.open_regions -= 1;
+
+        unsafe {
+            device.cmd_write_timestamp(
+                command_buffer,
+                vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                slot.query_pool,
+                region.0 * 2 + 1,
+            );
+        }
+    }
+
+    /// Read back the results of the last submission of `slot` if the GPU is done with it.
+    ///
+    /// This never waits; if the results are not available yet, the previous ones are kept.
+    /// Command buffers recorded once keep their queries, so reading them back again is fine.
+    pub fn collect(&mut self, device: &ash::Device, slot: usize) -> Option<&FrameTimings> {
+        let slot = &self.slots[slot];
+        if !slot.is_recorded || slot.regions.is_empty() {
+            return None;
+        }
+
+        let query_count = slot.regions.len() * 2;
+        let mut timestamps: Vec<u64> = vec![0; query_count];
+        let result = unsafe {
+            device.get_query_pool_results(
+                slot.query_pool,
+                0,
+                query_count as u32,
+                &mut timestamps,
+                vk::QueryResultFlags::TYPE_64,
+            )
+        };
+
+        match result {
+            Ok(()) => {}
+            Err(vk::Result::NOT_READY) => return None,
+            Err(_) => panic!("Failed to get Query Pool results!"),
+        }
+
+        let regions = slot
+            .regions
+            .iter()
+            .enumerate()
+            .map(|(i, (name, depth))| {
+                let ticks =
+                    timestamps[i * 2 + 1].wrapping_sub(timestamps[i * 2]) & self.timestamp_mask;
+
+                RegionTiming {
+                    name: name.clone(),
+                    depth: *depth,
+                    milliseconds: ticks as f64 * self.timestamp_period / 1_000_000.0,
+                }
+            })
+            .collect();
+
+        self.latest = Some(FrameTimings { regions });
+        self.latest.as_ref()
+    }
+
+    /// The most recent timings read back by `collect`.
+    pub fn latest(&self) -> Option<&FrameTimings> {
+        self.latest.as_ref()
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        for slot in self.slots.iter() {
+            unsafe {
+                device.destroy_query_pool(slot.query_pool, None);
+            }
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Named input actions bound to keys and mouse buttons.
+//!
+//! Apps ask whether an action was pressed, is held or was released during the current frame
+//! instead of matching on key codes, so the bindings can change without touching the app. A
+//! config file rebinds actions with one line per action:
+//!
+//! ```text
+//! # comments start with '#'
+//! quit = Escape
+//! toggle_fullscreen = F11, Alt+Return
+//! toggle_wireframe = Ctrl+Mouse:Right
+//! ```
+//!
+//! Key names are the names of `VirtualKeyCode`, mouse buttons are `Mouse:Left`, `Mouse:Right`,
+//! `Mouse:Middle` or `Mouse:<number>`, and modifiers are `Shift`, `Ctrl`, `Alt` and `Logo`.
+
+use winit::event::{ElementState, ModifiersState, MouseButton, VirtualKeyCode};
+
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+pub const QUIT: &str = "quit";
+pub const TOGGLE_WIREFRAME: &str = "toggle_wireframe";
+pub const TOGGLE_FULLSCREEN: &str = "toggle_fullscreen";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Input {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+/// An input along with the modifiers that must be down, and no others. A binding without
+/// modifiers matches whatever modifiers are down, unless another binding of the same input
+/// declares exactly those.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Binding {
+    pub input: Input,
+    pub modifiers: ModifiersState,
+}
+
+impl Binding {
+    pub fn key(key_code: VirtualKeyCode) -> Binding {
+        Binding {
+            input: Input::Key(key_code),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    pub fn mouse(button: MouseButton) -> Binding {
+        Binding {
+            input: Input::Mouse(button),
+            modifiers: ModifiersState::empty(),
+        }
+    }
+
+    pub fn with_modifiers(self, modifiers: ModifiersState) -> Binding {
+        Binding { modifiers, ..self }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Binding, String> {
+        let mut parts: Vec<&str> = text.split('+').map(|part| part.trim()).collect();
+        let input_name = parts.pop().unwrap();
+
+        let mut modifiers = ModifiersState::empty();
+        for modifier in parts {
+            modifiers |= match modifier {
+                "Shift" => ModifiersState::SHIFT,
+                "Ctrl" => ModifiersState::CTRL,
+                "Alt" => ModifiersState::ALT,
+                "Logo" => ModifiersState::LOGO,
+                _ => return Err(format!("unknown modifier '{}'", modifier)),
+            };
+        }
+
+        let input = if let Some(button_name) = input_name.strip_prefix("Mouse:") {
+            Input::Mouse(match button_name {
+                "Left" => MouseButton::Left,
+                "Right" => MouseButton::Right,
+                "Middle" => MouseButton::Middle,
+                _ => MouseButton::Other(
+                    button_name
+                        .parse()
+                        .map_err(|_| format!("unknown mouse button '{}'", button_name))?,
+                ),
+            })
+        } else {
+            Input::Key(
+                parse_key_code(input_name)
+                    .ok_or_else(|| format!("unknown key '{}'", input_name))?,
+            )
+        };
+
+        Ok(Binding { input, modifiers })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(modifier, name) in [
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::CTRL, "Ctrl"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::LOGO, "Logo"),
+        ]
+        .iter()
+        {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.input {
+            Input::Key(key_code) => write!(f, "{:?}", key_code),
+            Input::Mouse(MouseButton::Other(button)) => write!(f, "Mouse:{}", button),
+            Input::Mouse(button) => write!(f, "Mouse:{:?}", button),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ActionState {
+    // the bound inputs that are down, the action is held until all of them are released.
+    held_inputs: Vec<Input>,
+    is_pressed: bool,
+    is_released: bool,
+}
+
+impl ActionState {
+    fn is_held(&self) -> bool {
+        !self.held_inputs.is_empty()
+    }
+}
+
+pub struct ActionMap {
+    // in registration order, so listing the actions is stable.
+    actions: Vec<String>,
+    bindings: HashMap<String, Vec<Binding>>,
+    states: HashMap<String, ActionState>,
+    modifiers: ModifiersState,
+}
+
+impl ActionMap {
+    /// An action map with only the built-in actions and their default bindings.
+    pub fn new() -> ActionMap {
+        let mut action_map = ActionMap {
+            actions: vec![],
+            bindings: HashMap::new(),
+            states: HashMap::new(),
+            modifiers: ModifiersState::empty(),
+        };
+
+        action_map.register(QUIT, &[Binding::key(VirtualKeyCode::Escape)]);
+        action_map.register(TOGGLE_WIREFRAME, &[Binding::key(VirtualKeyCode::F9)]);
+        action_map.register(
+            TOGGLE_FULLSCREEN,
+            &[
+                Binding::key(VirtualKeyCode::F11),
+                Binding::key(VirtualKeyCode::Return).with_modifiers(ModifiersState::ALT),
+            ],
+        );
+
+        action_map
+    }
+
+    /// Add `action` with its default bindings, replacing them if it is already registered.
+    pub fn register(&mut self, action: &str, default_bindings: &[Binding]) {
+        if !self.bindings.contains_key(action) {
+            self.actions.push(action.to_string());
+        }
+        self.bindings
+            .insert(action.to_string(), default_bindings.to_vec());
+        self.states
+            .insert(action.to_string(), ActionState::default());
+    }
+
+    /// Replace the bindings of a registered action.
+    pub fn rebind(&mut self, action: &str, bindings: &[Binding]) -> Result<(), String> {
+        match self.bindings.get_mut(action) {
+            Some(action_bindings) => {
+                *action_bindings = bindings.to_vec();
+                Ok(())
+            }
+            None => Err(format!("unknown action '{}'", action)),
+        }
+    }
+
+    pub fn actions(&self) -> &[String] {
+        &self.actions
+    }
+
+    pub fn bindings(&self, action: &str) -> &[Binding] {
+        self.bindings
+            .get(action)
+            .map_or(&[], |bindings| bindings.as_slice())
+    }
+
+    /// Rebind the actions listed in the config file at `path`, the others keep their bindings.
+    pub fn load_config(&mut self, path: &Path) -> io::Result<()> {
+        let config = fs::read_to_string(path)?;
+        self.parse_config(&config)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+    }
+
+    /// Rebind the actions listed in `config`, see the module documentation for the format.
+    pub fn parse_config(&mut self, config: &str) -> Result<(), String> {
+        for (line_index, line) in config.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let parse_line = || -> Result<(&str, Vec<Binding>), String> {
+                let mut sides = line.splitn(2, '=');
+                let action = sides.next().unwrap().trim();
+                let bindings = sides
+                    .next()
+                    .ok_or_else(|| "expected 'action = bindings'".to_string())?
+                    .split(',')
+                    .map(|binding| binding.trim())
+                    .filter(|binding| !binding.is_empty())
+                    .map(|binding| binding.parse())
+                    .collect::<Result<Vec<Binding>, String>>()?;
+
+                Ok((action, bindings))
+            };
+
+            parse_line()
+                .and_then(|(action, bindings)| self.rebind(action, &bindings))
+                .map_err(|message| format!("line {}: {}", line_index + 1, message))?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+
+    pub fn handle_key(&mut self, key_code: VirtualKeyCode, state: ElementState) {
+        self.handle_input(Input::Key(key_code), state);
+    }
+
+    pub fn handle_mouse_button(&mut self, button: MouseButton, state: ElementState) {
+        self.handle_input(Input::Mouse(button), state);
+    }
+
+    fn handle_input(&mut self, input: Input, state: ElementState) {
+        let modifiers = self.modifiers;
+        // Alt+Return must not trigger the actions bound to plain Return as well.
+        let has_exact_binding = self.bindings.values().flatten().any(|binding| {
+            binding.input == input
+                && !binding.modifiers.is_empty()
+                && binding.modifiers == modifiers
+        });
+
+        for (action, bindings) in self.bindings.iter() {
+            let action_state = self.states.get_mut(action).unwrap();
+
+            match state {
+                ElementState::Pressed => {
+                    let is_bound = bindings.iter().any(|binding| {
+                        binding.input == input
+                            && (binding.modifiers == modifiers
+                                || (binding.modifiers.is_empty() && !has_exact_binding))
+                    });
+                    // key repeats press an input that is already held.
+                    if is_bound && !action_state.held_inputs.contains(&input) {
+                        if !action_state.is_held() {
+                            action_state.is_pressed = true;
+                        }
+                        action_state.held_inputs.push(input);
+                    }
+                }
+                ElementState::Released => {
+                    // modifiers may have changed since the press, so they do not matter here.
+                    if let Some(index) = action_state
+                        .held_inputs
+                        .iter()
+                        .position(|&held_input| held_input == input)
+                    {
+                        action_state.held_inputs.remove(index);
+                        if !action_state.is_held() {
+                            action_state.is_released = true;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Release every held action, for when the window loses focus and misses the releases.
+    pub fn release_all(&mut self) {
+        for action_state in self.states.values_mut() {
+            if action_state.is_held() {
+                action_state.held_inputs.clear();
+                action_state.is_released = true;
+            }
+        }
+    }
+
+    /// Whether `action` went down during this frame.
+    pub fn is_pressed(&self, action: &str) -> bool {
+        self.states
+            .get(action)
+            .is_some_and(|action_state| action_state.is_pressed)
+    }
+
+    pub fn is_held(&self, action: &str) -> bool {
+        self.states
+            .get(action)
+            .is_some_and(|action_state| action_state.is_held())
+    }
+
+    /// Whether `action` went up during this frame.
+    pub fn is_released(&self, action: &str) -> bool {
+        self.states
+            .get(action)
+            .is_some_and(|action_state| action_state.is_released)
+    }
+
+    /// Forget the presses and releases of this frame, called after every frame.
+    pub fn end_frame(&mut self) {
+        for action_state in self.states.values_mut() {
+            action_state.is_pressed = false;
+            action_state.is_released = false;
+        }
+    }
+}
+
+impl Default for ActionMap {
+    fn default() -> ActionMap {
+        ActionMap::new()
+    }
+}
+
+macro_rules! key_codes {
+    ($($key_code: ident),* $(,)?) => {
+        /// The `VirtualKeyCode` named `name`, as printed by its `Debug` implementation.
+        pub fn parse_key_code(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key_code) => Some(VirtualKeyCode::$key_code),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+#[rustfmt::skip]
+key_codes![
+    Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9, Key0,
+    A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    Escape, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+    F13, F14, F15, F16, F17, F18, F19, F20, F21, F22, F23, F24,
+    Snapshot, Scroll, Pause, Insert, Home, Delete, End, PageDown, PageUp,
+    Left, Up, Right, Down, Back, Return, Space, Compose, Caret, Numlock,
+    Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    AbntC1, AbntC2, Add, Apostrophe, Apps, At, Ax, Backslash, Calculator, Capital, Colon, Comma,
+    Convert, Decimal, Divide, Equals, Grave, Kana, Kanji, LAlt, LBracket, LControl, LShift, LWin,
+    Mail, MediaSelect, MediaStop, Minus, Multiply, Mute, MyComputer, NavigateForward,
+    NavigateBackward, NextTrack, NoConvert, NumpadComma, NumpadEnter, NumpadEquals, OEM102,
+    Period, PlayPause, Power, PrevTrack, RAlt, RBracket, RControl, RShift, RWin, Semicolon,
+    Slash, Sleep, Stop, Subtract, Sysrq, Tab, Underline, Unlabeled, VolumeDown, VolumeUp, Wake,
+    WebBack, WebFavorites, WebForward, WebHome, WebRefresh, WebSearch, WebStop, Yen, Copy, Paste,
+    Cut,
+];
@@ -0,0 +1,270 @@
+//! Record the input events and frame times of a session, and replay them later.
+//!
+//! A recording is a text file with one entry per line. The input events of a frame come before
+//! the `frame` line with the time the clock reported for it:
+//!
+//! ```text
+//! key W pressed
+//! mouse_motion 3 -1.5
+//! frame 0.016667
+//! ```
+//!
+//! Replaying feeds the recorded events to the app in place of the live input and advances the
+//! simulation by the recorded frame times, so the app goes through the same states as in the
+//! recorded session, whatever the frame rate of the replay. Window resizes are not recorded.
+
+use winit::dpi::{LogicalPosition, PhysicalPosition};
+use winit::event::{DeviceEvent, ElementState, Event, KeyboardInput, WindowEvent};
+use winit::event::{ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode};
+use winit::window::WindowId;
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::str::FromStr;
+
+use super::input::parse_key_code;
+
+/// The input events that drive an app, as recorded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    Key(VirtualKeyCode, ElementState),
+    MouseButton(MouseButton, ElementState),
+    CursorMoved(PhysicalPosition<i32>),
+    MouseMotion((f64, f64)),
+    MouseWheel(MouseScrollDelta),
+    Modifiers(ModifiersState),
+    Focused(bool),
+}
+
+impl InputEvent {
+    /// The input event in `event`, if it is one for the window `window_id` or a device event.
+    pub fn from_event<T>(event: &Event<T>, window_id: WindowId) -> Option<InputEvent> {
+        match event {
+            Event::WindowEvent {
+                window_id: event_window_id,
+                event,
+            } if *event_window_id == window_id => match event {
+                WindowEvent::KeyboardInput {
+                    input:
+                        KeyboardInput {
+                            virtual_keycode: Some(key_code),
+                            state,
+                            ..
+                        },
+                    ..
+                } => Some(InputEvent::Key(*key_code, *state)),
+                WindowEvent::MouseInput { button, state, .. } => {
+                    Some(InputEvent::MouseButton(*button, *state))
+                }
+                WindowEvent::CursorMoved { position, .. } => {
+                    Some(InputEvent::CursorMoved(*position))
+                }
+                WindowEvent::MouseWheel { delta, .. } => Some(InputEvent::MouseWheel(*delta)),
+                WindowEvent::Focused(is_focused) => Some(InputEvent::Focused(*is_focused)),
+                _ => None,
+            },
+            Event::DeviceEvent {
+                event: DeviceEvent::MouseMotion { delta },
+                ..
+            } => Some(InputEvent::MouseMotion(*delta)),
+            Event::DeviceEvent {
+                event: DeviceEvent::ModifiersChanged(modifiers),
+                ..
+            } => Some(InputEvent::Modifiers(*modifiers)),
+            _ => None,
+        }
+    }
+}
+
+fn state_name(state: ElementState) -> &'static str {
+    match state {
+        ElementState::Pressed => "pressed",
+        ElementState::Released => "released",
+    }
+}
+
+impl fmt::Display for InputEvent {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            InputEvent::Key(key_code, state) => {
+                write!(f, "key {:?} {}", key_code, state_name(state))
+            }
+            InputEvent::MouseButton(MouseButton::Other(button), state) => {
+                write!(f, "mouse_button {} {}", button, state_name(state))
+            }
+            InputEvent::MouseButton(button, state) => {
+                write!(f, "mouse_button {:?} {}", button, state_name(state))
+            }
+            InputEvent::CursorMoved(position) => {
+                write!(f, "cursor_moved {} {}", position.x, position.y)
+            }
+            InputEvent::MouseMotion((dx, dy)) => write!(f, "mouse_motion {} {}", dx, dy),
+            InputEvent::MouseWheel(MouseScrollDelta::LineDelta(x, y)) => {
+                write!(f, "mouse_wheel line {} {}", x, y)
+            }
+            InputEvent::MouseWheel(MouseScrollDelta::PixelDelta(position)) => {
+                write!(f, "mouse_wheel pixel {} {}", position.x, position.y)
+            }
+            InputEvent::Modifiers(modifiers) => write!(f, "modifiers {}", modifiers.bits()),
+            InputEvent::Focused(is_focused) => write!(f, "focused {}", is_focused),
+        }
+    }
+}
+
+fn parse_field<T: FromStr>(fields: &[&str], index: usize) -> Result<T, String> {
+    let field = fields
+        .get(index)
+        .ok_or_else(|| format!("'{}' expects {} values", fields[0], index))?;
+    field
+        .parse()
+        .map_err(|_| format!("invalid value '{}'", field))
+}
+
+fn parse_state(fields: &[&str], index: usize) -> Result<ElementState, String> {
+    match fields.get(index) {
+        Some(&"pressed") => Ok(ElementState::Pressed),
+        Some(&"released") => Ok(ElementState::Released),
+        _ => Err(format!("'{}' expects pressed or released", fields[0])),
+    }
+}
+
+impl FromStr for InputEvent {
+    type Err = String;
+
+    fn from_str(line: &str) -> Result<InputEvent, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+
+        let input_event = match fields[0] {
+            "key" => {
+                let name = fields.get(1).copied().unwrap_or("");
+                let key_code =
+                    parse_key_code(name).ok_or_else(|| format!("unknown key '{}'", name))?;
+                InputEvent::Key(key_code, parse_state(&fields, 2)?)
+            }
+            "mouse_button" => {
+                let button = match fields.get(1) {
+                    Some(&"Left") => MouseButton::Left,
+                    Some(&"Right") => MouseButton::Right,
+                    Some(&"Middle") => MouseButton::Middle,
+                    _ => MouseButton::Other(parse_field(&fields, 1)?),
+                };
+                InputEvent::MouseButton(button, parse_state(&fields, 2)?)
+            }
+            "cursor_moved" => InputEvent::CursorMoved(PhysicalPosition::new(
+                parse_field(&fields, 1)?,
+                parse_field(&fields, 2)?,
+            )),
+            "mouse_motion" => {
+                InputEvent::MouseMotion((parse_field(&fields, 1)?, parse_field(&fields, 2)?))
+            }
+            "mouse_wheel" => {
+                let delta = match fields.get(1) {
+                    Some(&"line") => MouseScrollDelta::LineDelta(
+                        parse_field(&fields, 2)?,
+                        parse_field(&fields, 3)?,
+                    ),
+                    Some(&"pixel") => MouseScrollDelta::PixelDelta(LogicalPosition::new(
+                        parse_field(&fields, 2)?,
+                        parse_field(&fields, 3)?,
+                    )),
+                    _ => return Err("'mouse_wheel' expects line or pixel".to_string()),
+                };
+                InputEvent::MouseWheel(delta)
+            }
+            "modifiers" => {
+                InputEvent::Modifiers(ModifiersState::from_bits_truncate(parse_field(&fields, 1)?))
+            }
+            "focused" => InputEvent::Focused(parse_field(&fields, 1)?),
+            other => return Err(format!("unknown entry '{}'", other)),
+        };
+
+        Ok(input_event)
+    }
+}
+
+/// Write the input of a session to a file as it happens, so a crash keeps what led to it.
+pub struct InputRecorder {
+    writer: BufWriter<File>,
+}
+
+impl InputRecorder {
+    pub fn create(path: &Path) -> io::Result<InputRecorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "# input recording, replay with --replay-input")?;
+
+        Ok(InputRecorder { writer })
+    }
+
+    pub fn record_event(&mut self, input_event: &InputEvent) -> io::Result<()> {
+        writeln!(self.writer, "{}", input_event)
+    }
+
+    /// End the current frame, which took `delta_time` seconds of the clock.
+    pub fn record_frame(&mut self, delta_time: f32) -> io::Result<()> {
+        writeln!(self.writer, "frame {}", delta_time)?;
+        self.writer.flush()
+    }
+}
+
+/// The input events of a frame and the time the clock reported for it.
+#[derive(Debug, Clone)]
+pub struct RecordedFrame {
+    pub events: Vec<InputEvent>,
+    pub delta_time: f32,
+}
+
+/// The frames of a recording, handed out one by one.
+pub struct InputPlayback {
+    frames: VecDeque<RecordedFrame>,
+}
+
+impl InputPlayback {
+    pub fn load(path: &Path) -> io::Result<InputPlayback> {
+        let recording = fs::read_to_string(path)?;
+        InputPlayback::parse(&recording)
+            .map_err(|message| io::Error::new(io::ErrorKind::InvalidData, message))
+    }
+
+    /// Read a recording, see the module documentation for the format.
+    pub fn parse(recording: &str) -> Result<InputPlayback, String> {
+        let mut frames = VecDeque::new();
+        let mut events = vec![];
+
+        for (line_index, line) in recording.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let line_error = |message: String| format!("line {}: {}", line_index + 1, message);
+
+            match line.strip_prefix("frame ") {
+                Some(delta_time) => {
+                    let delta_time = delta_time.trim().parse().map_err(|_| {
+                        line_error(format!("invalid frame time '{}'", delta_time.trim()))
+                    })?;
+                    frames.push_back(RecordedFrame {
+                        events: std::mem::take(&mut events),
+                        delta_time,
+                    });
+                }
+                None => events.push(line.parse().map_err(line_error)?),
+            }
+        }
+        // the events after the last frame never reached the app, so they are dropped.
+
+        Ok(InputPlayback { frames })
+    }
+
+    /// The next frame to replay, `None` once the recording is over.
+    pub fn next_frame(&mut self) -> Option<RecordedFrame> {
+        self.frames.pop_front()
+    }
+
+    pub fn remaining_frames(&self) -> usize {
+        self.frames.len()
+    }
+}
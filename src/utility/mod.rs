@@ -1,11 +1,28 @@
 //! The utility mod define some fixed function using in this tutorial.
 //! Help to simplify the code.
 
+pub mod camera;
+pub mod clock;
 pub mod constants;
 pub mod debug;
+pub mod display;
+pub mod fixed_timestep;
 pub mod fps_limiter;
+pub mod frame_recording;
+pub mod gpu_profiler;
+pub mod input;
+pub mod input_replay;
+pub mod parallel_recording;
 pub mod platforms;
+pub mod post_process;
+pub mod queries;
+pub mod render_graph;
+pub mod render_state;
+pub mod screenshot;
 pub mod share;
+pub mod specialization;
 pub mod structures;
+pub mod timeline;
 pub mod tools;
 pub mod window;
+pub mod window_target;
@@ -0,0 +1,213 @@
+//! Record secondary command buffers on worker threads, one command pool per thread and frame.
+//!
+//! A command pool must only be used by one thread at a time, so every worker records into a
+//! pool of its own. Each frame in flight has its own set of pools, which are reset as a whole
+//! once the frame using them has finished on the GPU.
+
+use ash::version::DeviceV1_0;
+use ash::vk;
+
+use std::ops::Range;
+use std::ptr;
+use std::thread;
+
+/// Everything a job needs to record its part of the draw list.
+pub struct RecordJob<'a> {
+    pub device: &'a ash::Device,
+    /// A secondary command buffer that has already begun, inside the render pass.
+    pub command_buffer: vk::CommandBuffer,
+    pub thread_index: usize,
+    /// The items of the draw list this job is responsible for.
+    pub items: Range<usize>,
+}
+
+pub struct ParallelRecorder {
+    thread_count: usize,
+    // [frame][thread]
+    command_pools: Vec<Vec<vk::CommandPool>>,
+    command_buffers: Vec<Vec<vk::CommandBuffer>>,
+}
+
+impl ParallelRecorder {
+    pub fn new(
+        device: &ash::Device,
+        queue_family_index: u32,
+        thread_count: usize,
+        frame_count: usize,
+    ) -> ParallelRecorder {
+        assert!(thread_count > 0, "ParallelRecorder needs at least one thread.");
+
+        let mut command_pools = vec![];
+        let mut command_buffers = vec![];
+
+        for _ in 0..frame_count {
+            let mut frame_pools = vec![];
+            let mut frame_buffers = vec![];
+
+            for _ in 0..thread_count {
+                let command_pool_create_info = vk::CommandPoolCreateInfo {
+                    s_type: vk::StructureType::COMMAND_POOL_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::CommandPoolCreateFlags::TRANSIENT,
+                    queue_family_index,
+                };
+
+                let command_pool = unsafe {
+                    device
+                        .create_command_pool(&command_pool_create_info, None)
+                        .expect("Failed to create Command Pool!")
+                };
+
+                let command_buffer_allocate_info = vk::CommandBufferAllocateInfo {
+                    s_type: vk::StructureType::COMMAND_BUFFER_ALLOCATE_INFO,
+                    p_next: ptr::null(),
+                    command_buffer_count: 1,
+                    command_pool,
+                    level: vk::CommandBufferLevel::SECONDARY,
+                };
+
+                let command_buffer = unsafe {
+                    device
+                        .allocate_command_buffers(&command_buffer_allocate_info)
+                        .expect("Failed to allocate Command Buffers!")[0]
+                };
+
+                frame_pools.push(command_pool);
+                frame_buffers.push(command_buffer);
+            }
+
+            command_pools.push(frame_pools);
+            command_buffers.push(frame_buffers);
+        }
+
+        ParallelRecorder {
+            thread_count,
+            command_pools,
+            command_buffers,
+        }
+    }
+
+    /// A worker count based on the available cores, at least one and at most `max_thread_count`.
+    pub fn default_thread_count(max_thread_count: usize) -> usize {
+        thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1)
+            .min(max_thread_count)
+            .max(1)
+    }
+
+    pub fn thread_count(&self) -> usize {
+        self.thread_count
+    }
+
+    /// Split `item_count` items over the worker threads and record them in parallel.
+    ///
+    /// `record_job` runs once per non-empty share of the items, on its own thread, with a
+    /// secondary command buffer continuing `subpass` of `render_pass`. The returned command
+    /// buffers are in item order and ready for `vkCmdExecuteCommands`.
+    ///
+    /// The command buffers of `frame` must no longer be in use by the GPU.
+    #[allow(clippy::too_many_arguments)]
+    pub fn record<F>(
+        &self,
+        device: &ash::Device,
+        frame: usize,
+        render_pass: vk::RenderPass,
+        subpass: u32,
+        framebuffer: vk::Framebuffer,
+        item_count: usize,
+        record_job: F,
+    ) -> Vec<vk::CommandBuffer>
+    where
+        F: Fn(&RecordJob) + Sync,
+    {
+        for &command_pool in self.command_pools[frame].iter() {
+            unsafe {
+                device
+                    .reset_command_pool(command_pool, vk::CommandPoolResetFlags::empty())
+                    .expect("Failed to reset Command Pool!");
+            }
+        }
+
+        let jobs: Vec<(usize, Range<usize>)> = split_items(item_count, self.thread_count)
+            .into_iter()
+            .enumerate()
+            .filter(|(_thread_index, items)| !items.is_empty())
+            .collect();
+        let record_job = &record_job;
+
+        thread::scope(|scope| {
+            for (thread_index, items) in jobs.iter().cloned() {
+                let command_buffer = self.command_buffers[frame][thread_index];
+
+                scope.spawn(move || {
+                    let inheritance_info = vk::CommandBufferInheritanceInfo {
+                        s_type: vk::StructureType::COMMAND_BUFFER_INHERITANCE_INFO,
+                        p_next: ptr::null(),
+                        render_pass,
+                        subpass,
+                        framebuffer,
+                        occlusion_query_enable: vk::FALSE,
+                        query_flags: vk::QueryControlFlags::empty(),
+                        pipeline_statistics: vk::QueryPipelineStatisticFlags::empty(),
+                    };
+
+                    let command_buffer_begin_info = vk::CommandBufferBeginInfo {
+                        s_type: vk::StructureType::COMMAND_BUFFER_BEGIN_INFO,
+                        p_next: ptr::null(),
+                        p_inheritance_info: &inheritance_info,
+                        flags: vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT
+                            | vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE,
+                    };
+
+                    unsafe {
+                        device
+                            .begin_command_buffer(command_buffer, &command_buffer_begin_info)
+                            .expect("Failed to begin recording Command Buffer at beginning!");
+                    }
+
+                    record_job(&RecordJob {
+                        device,
+                        command_buffer,
+                        thread_index,
+                        items,
+                    });
+
+                    unsafe {
+                        device
+                            .end_command_buffer(command_buffer)
+                            .expect("Failed to record Command Buffer at Ending!");
+                    }
+                });
+            }
+        });
+
+        jobs.iter()
+            .map(|&(thread_index, _)| self.command_buffers[frame][thread_index])
+            .collect()
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        for &command_pool in self.command_pools.iter().flatten() {
+            unsafe {
+                device.destroy_command_pool(command_pool, None);
+            }
+        }
+    }
+}
+
+/// Split `item_count` items into `part_count` contiguous ranges whose sizes differ by one at most.
+pub fn split_items(item_count: usize, part_count: usize) -> Vec<Range<usize>> {
+    let base_size = item_count / part_count;
+    let remainder = item_count % part_count;
+
+    let mut start = 0;
+    (0..part_count)
+        .map(|part| {
+            let size = base_size + if part < remainder { 1 } else { 0 };
+            let range = start..start + size;
+            start += size;
+            range
+        })
+        .collect()
+}
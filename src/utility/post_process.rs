@@ -0,0 +1,759 @@
+//! A chain of full-screen post-processing passes on top of the render graph.
+//!
+//! Every effect is a small struct implementing `PostEffect`: it names its fragment shader and
+//! packs its parameters into one `vec4`. All effects share the full-screen triangle vertex
+//! shader, the descriptor set layout (the input image and a sampler) and the pipeline layout.
+
+use ash::version::DeviceV1_0;
+use ash::vk;
+
+use std::ffi::CString;
+use std::ptr;
+
+use crate::utility::render_graph::{
+    AttachmentLoad, ImageDesc, ImageHandle, ImageUsage, PassContext, PassHandle, PassKind,
+    RenderGraph,
+};
+use crate::utility::share;
+
+/// The push constants every post shader receives.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct PostParams {
+    pub params: [f32; 4],
+    pub texel_size: [f32; 2],
+}
+
+pub trait PostEffect {
+    fn name(&self) -> &str;
+
+    /// SPIR-V code of the fragment shader, it samples set 0 and writes location 0.
+    fn fragment_shader_code(&self) -> Vec<u8>;
+
+    fn params(&self) -> [f32; 4];
+}
+
+/// Map HDR colors into [0, 1] with the ACES filmic curve.
+#[derive(Debug, Clone, Copy)]
+pub struct Tonemap {
+    pub exposure: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Gamma {
+    pub gamma: f32,
+}
+
+/// Fast approximate anti-aliasing, run it on tonemapped colors.
+#[derive(Debug, Clone, Copy)]
+pub struct Fxaa {
+    pub span_max: f32,
+    pub reduce_mul: f32,
+    pub reduce_min: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Vignette {
+    pub strength: f32,
+    pub radius: f32,
+    pub softness: f32,
+}
+
+impl Default for Tonemap {
+    fn default() -> Tonemap {
+        Tonemap { exposure: 1.0 }
+    }
+}
+
+impl Default for Gamma {
+    fn default() -> Gamma {
+        Gamma { gamma: 2.2 }
+    }
+}
+
+impl Default for Fxaa {
+    fn default() -> Fxaa {
+        Fxaa {
+            span_max: 8.0,
+            reduce_mul: 1.0 / 8.0,
+            reduce_min: 1.0 / 128.0,
+        }
+    }
+}
+
+impl Default for Vignette {
+    fn default() -> Vignette {
+        Vignette {
+            strength: 0.8,
+            radius: 0.75,
+            softness: 0.45,
+        }
+    }
+}
+
+impl PostEffect for Tonemap {
+    fn name(&self) -> &str {
+        "tonemap"
+    }
+
+    fn fragment_shader_code(&self) -> Vec<u8> {
+        include_bytes!("../../shaders/spv/30-post-tonemap.frag.spv").to_vec()
+    }
+
+    fn params(&self) -> [f32; 4] {
+        [self.exposure, 0.0, 0.0, 0.0]
+    }
+}
+
+impl PostEffect for Gamma {
+    fn name(&self) -> &str {
+        "gamma"
+    }
+
+    fn fragment_shader_code(&self) -> Vec<u8> {
+        include_bytes!("../../shaders/spv/30-post-gamma.frag.spv").to_vec()
+    }
+
+    fn params(&self) -> [f32; 4] {
+        [self.gamma, 0.0, 0.0, 0.0]
+    }
+}
+
+impl PostEffect for Fxaa {
+    fn name(&self) -> &str {
+        "fxaa"
+    }
+
+    fn fragment_shader_code(&self) -> Vec<u8> {
+        include_bytes!("../../shaders/spv/30-post-fxaa.frag.spv").to_vec()
+    }
+
+    fn params(&self) -> [f32; 4] {
+        [self.span_max, self.reduce_mul, self.reduce_min, 0.0]
+    }
+}
+
+impl PostEffect for Vignette {
+    fn name(&self) -> &str {
+        "vignette"
+    }
+
+    fn fragment_shader_code(&self) -> Vec<u8> {
+        include_bytes!("../../shaders/spv/30-post-vignette.frag.spv").to_vec()
+    }
+
+    fn params(&self) -> [f32; 4] {
+        [self.strength, self.radius, self.softness, 0.0]
+    }
+}
+
+struct PostPass {
+    effect: Box<dyn PostEffect>,
+    pass: Option<PassHandle>,
+    input: Option<ImageHandle>,
+    pipeline: vk::Pipeline,
+    // one set per instance of the input image.
+    descriptor_sets: Vec<vk::DescriptorSet>,
+}
+
+pub struct PostProcessChain {
+    passes: Vec<PostPass>,
+
+    descriptor_set_layout: vk::DescriptorSetLayout,
+    pipeline_layout: vk::PipelineLayout,
+    sampler: vk::Sampler,
+    descriptor_pool: vk::DescriptorPool,
+}
+
+impl PostProcessChain {
+    pub fn new(device: &ash::Device) -> PostProcessChain {
+        let descriptor_set_layout = PostProcessChain::create_descriptor_set_layout(device);
+        let pipeline_layout =
+            PostProcessChain::create_pipeline_layout(device, descriptor_set_layout);
+        let sampler = PostProcessChain::create_sampler(device);
+
+        PostProcessChain {
+            passes: vec![],
+
+            descriptor_set_layout,
+            pipeline_layout,
+            sampler,
+            descriptor_pool: vk::DescriptorPool::null(),
+        }
+    }
+
+    /// Append an effect to the end of the chain, returns its index.
+    pub fn push_effect(&mut self, effect: Box<dyn PostEffect>) -> usize {
+        self.passes.push(PostPass {
+            effect,
+            pass: None,
+            input: None,
+            pipeline: vk::Pipeline::null(),
+            descriptor_sets: vec![],
+        });
+
+        self.passes.len() - 1
+    }
+
+    /// Swap the parameters of an effect, e.g. a `Tonemap` with another exposure.
+    ///
+    /// The new effect must use the same shader, the pipeline of the slot is kept.
+    pub fn replace_effect(&mut self, index: usize, effect: Box<dyn PostEffect>) {
+        assert_eq!(
+            self.passes[index].effect.name(),
+            effect.name(),
+            "Replacing an effect with a different one requires rebuilding the chain."
+        );
+
+        self.passes[index].effect = effect;
+    }
+
+    pub fn effect(&self, index: usize) -> &dyn PostEffect {
+        self.passes[index].effect.as_ref()
+    }
+
+    pub fn len(&self) -> usize {
+        self.passes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.passes.is_empty()
+    }
+
+    /// Declare one graphics pass per effect, from `input` to `output`.
+    ///
+    /// The images between two effects are transient images described by `intermediate_desc`.
+    pub fn add_passes(
+        &mut self,
+        graph: &mut RenderGraph,
+        input: ImageHandle,
+        output: ImageHandle,
+        intermediate_desc: ImageDesc,
+    ) {
+        assert!(!self.passes.is_empty(), "Post-processing chain is empty.");
+
+        let last_index = self.passes.len() - 1;
+        let mut current_input = input;
+
+        for (index, post_pass) in self.passes.iter_mut().enumerate() {
+            let target = if index == last_index {
+                output
+            } else {
+                graph.create_image(
+                    &format!("{} output", post_pass.effect.name()),
+                    intermediate_desc,
+                )
+            };
+
+            let pass = graph
+                .add_pass(post_pass.effect.name(), PassKind::Graphics)
+                .use_image(
+                    current_input,
+                    ImageUsage::Sampled(vk::PipelineStageFlags::FRAGMENT_SHADER),
+                )
+                .color_attachment(target, AttachmentLoad::DontCare)
+                .finish();
+
+            post_pass.pass = Some(pass);
+            post_pass.input = Some(current_input);
+            current_input = target;
+        }
+    }
+
+    /// Create the pipelines and descriptor sets, call this after `graph` has been compiled.
+    pub fn create_pipelines(&mut self, device: &ash::Device, graph: &RenderGraph) {
+        let vert_shader_module = share::create_shader_module(
+            device,
+            include_bytes!("../../shaders/spv/30-post-fullscreen.vert.spv").to_vec(),
+        );
+
+        let set_count: usize = self
+            .passes
+            .iter()
+            .filter_map(|post_pass| post_pass.input)
+            .map(|input| graph.image_instance_count(input))
+            .sum();
+        self.descriptor_pool = PostProcessChain::create_descriptor_pool(device, set_count as u32);
+
+        for post_pass in self.passes.iter_mut() {
+            let (pass, input) = match (post_pass.pass, post_pass.input) {
+                (Some(pass), Some(input)) => (pass, input),
+                _ => panic!(
+                    "Post pass {} is not in the render graph!",
+                    post_pass.effect.name()
+                ),
+            };
+            let render_pass = graph
+                .render_pass(pass)
+                .expect("Post pass was culled from the render graph!");
+
+            post_pass.pipeline = PostProcessChain::create_pipeline(
+                device,
+                render_pass,
+                self.pipeline_layout,
+                vert_shader_module,
+                post_pass.effect.fragment_shader_code(),
+            );
+
+            let input_views: Vec<vk::ImageView> = (0..graph.image_instance_count(input))
+                .map(|instance_index| graph.image_view(input, instance_index))
+                .collect();
+            post_pass.descriptor_sets = PostProcessChain::create_descriptor_sets(
+                device,
+                self.descriptor_pool,
+                self.descriptor_set_layout,
+                &input_views,
+                self.sampler,
+            );
+        }
+
+        unsafe {
+            device.destroy_shader_module(vert_shader_module, None);
+        }
+    }
+
+    /// Record the draw of a post pass, returns false if `pass` does not belong to this chain.
+    pub fn record(&self, pass: PassHandle, context: &PassContext) -> bool {
+        let post_pass = match self
+            .passes
+            .iter()
+            .find(|post_pass| post_pass.pass == Some(pass))
+        {
+            Some(post_pass) => post_pass,
+            None => return false,
+        };
+
+        let device = context.device;
+        let command_buffer = context.command_buffer;
+        let post_params = PostParams {
+            params: post_pass.effect.params(),
+            texel_size: [
+                1.0 / context.extent.width as f32,
+                1.0 / context.extent.height as f32,
+            ],
+        };
+        let viewports = [vk::Viewport {
+            x: 0.0,
+            y: 0.0,
+            width: context.extent.width as f32,
+            height: context.extent.height as f32,
+            min_depth: 0.0,
+            max_depth: 1.0,
+        }];
+        let scissors = [vk::Rect2D {
+            offset: vk::Offset2D { x: 0, y: 0 },
+            extent: context.extent,
+        }];
+        let descriptor_sets_to_bind =
+            [post_pass.descriptor_sets[context.instance_index % post_pass.descriptor_sets.len()]];
+
+        unsafe {
+            device.cmd_bind_pipeline(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                post_pass.pipeline,
+            );
+            device.cmd_set_viewport(command_buffer, 0, &viewports);
+            device.cmd_set_scissor(command_buffer, 0, &scissors);
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.pipeline_layout,
+                0,
+                &descriptor_sets_to_bind,
+                &[],
+            );
+            device.cmd_push_constants(
+                command_buffer,
+                self.pipeline_layout,
+                vk::ShaderStageFlags::FRAGMENT,
+                0,
+                std::slice::from_raw_parts(
+                    &post_params as *const PostParams as *const u8,
+                    std::mem::size_of::<PostParams>(),
+                ),
+            );
+            device.cmd_draw(command_buffer, 3, 1, 0, 0);
+        }
+
+        true
+    }
+
+    /// Destroy the pipelines and descriptor sets, they must be recreated with the render graph.
+    pub fn destroy_pipelines(&mut self, device: &ash::Device) {
+        unsafe {
+            for post_pass in self.passes.iter_mut() {
+                if post_pass.pipeline != vk::Pipeline::null() {
+                    device.destroy_pipeline(post_pass.pipeline, None);
+                }
+                post_pass.pipeline = vk::Pipeline::null();
+                post_pass.descriptor_sets.clear();
+                post_pass.pass = None;
+                post_pass.input = None;
+            }
+
+            if self.descriptor_pool != vk::DescriptorPool::null() {
+                device.destroy_descriptor_pool(self.descriptor_pool, None);
+                self.descriptor_pool = vk::DescriptorPool::null();
+            }
+        }
+    }
+
+    pub fn destroy(&mut self, device: &ash::Device) {
+        self.destroy_pipelines(device);
+
+        unsafe {
+            device.destroy_sampler(self.sampler, None);
+            device.destroy_pipeline_layout(self.pipeline_layout, None);
+            device.destroy_descriptor_set_layout(self.descriptor_set_layout, None);
+        }
+    }
+
+    fn create_descriptor_set_layout(device: &ash::Device) -> vk::DescriptorSetLayout {
+        let bindings = [
+            vk::DescriptorSetLayoutBinding {
+                binding: 0,
+                descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            },
+            vk::DescriptorSetLayoutBinding {
+                binding: 1,
+                descriptor_type: vk::DescriptorType::SAMPLER,
+                descriptor_count: 1,
+                stage_flags: vk::ShaderStageFlags::FRAGMENT,
+                p_immutable_samplers: ptr::null(),
+            },
+        ];
+
+        let layout_create_info = vk::DescriptorSetLayoutCreateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::DescriptorSetLayoutCreateFlags::empty(),
+            binding_count: bindings.len() as u32,
+            p_bindings: bindings.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_descriptor_set_layout(&layout_create_info, None)
+                .expect("Failed to create Descriptor Set Layout!")
+        }
+    }
+
+    fn create_pipeline_layout(
+        device: &ash::Device,
+        set_layout: vk::DescriptorSetLayout,
+    ) -> vk::PipelineLayout {
+        let set_layouts = [set_layout];
+        let push_constant_ranges = [vk::PushConstantRange {
+            stage_flags: vk::ShaderStageFlags::FRAGMENT,
+            offset: 0,
+            size: std::mem::size_of::<PostParams>() as u32,
+        }];
+
+        let pipeline_layout_create_info = vk::PipelineLayoutCreateInfo {
+            s_type: vk::StructureType::PIPELINE_LAYOUT_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineLayoutCreateFlags::empty(),
+            set_layout_count: set_layouts.len() as u32,
+            p_set_layouts: set_layouts.as_ptr(),
+            push_constant_range_count: push_constant_ranges.len() as u32,
+            p_push_constant_ranges: push_constant_ranges.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_pipeline_layout(&pipeline_layout_create_info, None)
+                .expect("Failed to create pipeline layout!")
+        }
+    }
+
+    fn create_sampler(device: &ash::Device) -> vk::Sampler {
+        let sampler_create_info = vk::SamplerCreateInfo {
+            s_type: vk::StructureType::SAMPLER_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::SamplerCreateFlags::empty(),
+            mag_filter: vk::Filter::LINEAR,
+            min_filter: vk::Filter::LINEAR,
+            address_mode_u: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            address_mode_v: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            address_mode_w: vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            anisotropy_enable: vk::FALSE,
+            max_anisotropy: 1.0,
+            compare_enable: vk::FALSE,
+            compare_op: vk::CompareOp::ALWAYS,
+            mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+            min_lod: 0.0,
+            max_lod: 0.0,
+            mip_lod_bias: 0.0,
+            border_color: vk::BorderColor::INT_OPAQUE_BLACK,
+            unnormalized_coordinates: vk::FALSE,
+        };
+
+        unsafe {
+            device
+                .create_sampler(&sampler_create_info, None)
+                .expect("Failed to create Sampler!")
+        }
+    }
+
+    fn create_descriptor_pool(device: &ash::Device, set_count: u32) -> vk::DescriptorPool {
+        let pool_sizes = [
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLED_IMAGE,
+                descriptor_count: set_count,
+            },
+            vk::DescriptorPoolSize {
+                ty: vk::DescriptorType::SAMPLER,
+                descriptor_count: set_count,
+            },
+        ];
+
+        let descriptor_pool_create_info = vk::DescriptorPoolCreateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_POOL_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::DescriptorPoolCreateFlags::empty(),
+            max_sets: set_count,
+            pool_size_count: pool_sizes.len() as u32,
+            p_pool_sizes: pool_sizes.as_ptr(),
+        };
+
+        unsafe {
+            device
+                .create_descriptor_pool(&descriptor_pool_create_info, None)
+                .expect("Failed to create Descriptor Pool!")
+        }
+    }
+
+    fn create_descriptor_sets(
+        device: &ash::Device,
+        descriptor_pool: vk::DescriptorPool,
+        descriptor_set_layout: vk::DescriptorSetLayout,
+        input_views: &[vk::ImageView],
+        sampler: vk::Sampler,
+    ) -> Vec<vk::DescriptorSet> {
+        let layouts = vec![descriptor_set_layout; input_views.len()];
+
+        let descriptor_set_allocate_info = vk::DescriptorSetAllocateInfo {
+            s_type: vk::StructureType::DESCRIPTOR_SET_ALLOCATE_INFO,
+            p_next: ptr::null(),
+            descriptor_pool,
+            descriptor_set_count: layouts.len() as u32,
+            p_set_layouts: layouts.as_ptr(),
+        };
+
+        let descriptor_sets = unsafe {
+            device
+                .allocate_descriptor_sets(&descriptor_set_allocate_info)
+                .expect("Failed to allocate descriptor sets!")
+        };
+
+        for (&descriptor_set, &input_view) in descriptor_sets.iter().zip(input_views.iter()) {
+            let image_infos = [vk::DescriptorImageInfo {
+                sampler: vk::Sampler::null(),
+                image_view: input_view,
+                image_layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            }];
+            let sampler_infos = [vk::DescriptorImageInfo {
+                sampler,
+                image_view: vk::ImageView::null(),
+                image_layout: vk::ImageLayout::UNDEFINED,
+            }];
+
+            let descriptor_write_sets = [
+                vk::WriteDescriptorSet {
+                    s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+                    p_next: ptr::null(),
+                    dst_set: descriptor_set,
+                    dst_binding: 0,
+                    dst_array_element: 0,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::SAMPLED_IMAGE,
+                    p_image_info: image_infos.as_ptr(),
+                    p_buffer_info: ptr::null(),
+                    p_texel_buffer_view: ptr::null(),
+                },
+                vk::WriteDescriptorSet {
+                    s_type: vk::StructureType::WRITE_DESCRIPTOR_SET,
+                    p_next: ptr::null(),
+                    dst_set: descriptor_set,
+                    dst_binding: 1,
+                    dst_array_element: 0,
+                    descriptor_count: 1,
+                    descriptor_type: vk::DescriptorType::SAMPLER,
+                    p_image_info: sampler_infos.as_ptr(),
+                    p_buffer_info: ptr::null(),
+                    p_texel_buffer_view: ptr::null(),
+                },
+            ];
+
+            unsafe {
+                device.update_descriptor_sets(&descriptor_write_sets, &[]);
+            }
+        }
+
+        descriptor_sets
+    }
+
+    fn create_pipeline(
+        device: &ash::Device,
+        render_pass: vk::RenderPass,
+        pipeline_layout: vk::PipelineLayout,
+        vert_shader_module: vk::ShaderModule,
+        frag_shader_code: Vec<u8>,
+    ) -> vk::Pipeline {
+        let frag_shader_module = share::create_shader_module(device, frag_shader_code);
+
+        let main_function_name = CString::new("main").unwrap();
+
+        let shader_stages = [
+            vk::PipelineShaderStageCreateInfo {
+                // Vertex Shader
+                s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineShaderStageCreateFlags::empty(),
+                module: vert_shader_module,
+                p_name: main_function_name.as_ptr(),
+                p_specialization_info: ptr::null(),
+                stage: vk::ShaderStageFlags::VERTEX,
+            },
+            vk::PipelineShaderStageCreateInfo {
+                // Fragment Shader
+                s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::PipelineShaderStageCreateFlags::empty(),
+                module: frag_shader_module,
+                p_name: main_function_name.as_ptr(),
+                p_specialization_info: ptr::null(),
+                stage: vk::ShaderStageFlags::FRAGMENT,
+            },
+        ];
+
+        // the full-screen triangle is generated from gl_VertexIndex.
+        let vertex_input_state_create_info = vk::PipelineVertexInputStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VERTEX_INPUT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineVertexInputStateCreateFlags::empty(),
+            vertex_attribute_description_count: 0,
+            p_vertex_attribute_descriptions: ptr::null(),
+            vertex_binding_description_count: 0,
+            p_vertex_binding_descriptions: ptr::null(),
+        };
+        let vertex_input_assembly_state_info = vk::PipelineInputAssemblyStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_INPUT_ASSEMBLY_STATE_CREATE_INFO,
+            flags: vk::PipelineInputAssemblyStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            primitive_restart_enable: vk::FALSE,
+            topology: vk::PrimitiveTopology::TRIANGLE_LIST,
+        };
+
+        // viewport and scissor are set when recording, the pipeline works for any extent.
+        let viewport_state_create_info = vk::PipelineViewportStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_VIEWPORT_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineViewportStateCreateFlags::empty(),
+            scissor_count: 1,
+            p_scissors: ptr::null(),
+            viewport_count: 1,
+            p_viewports: ptr::null(),
+        };
+        let dynamic_states = [vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+        let dynamic_state_create_info = vk::PipelineDynamicStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_DYNAMIC_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineDynamicStateCreateFlags::empty(),
+            dynamic_state_count: dynamic_states.len() as u32,
+            p_dynamic_states: dynamic_states.as_ptr(),
+        };
+
+        let rasterization_statue_create_info = vk::PipelineRasterizationStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
+            depth_clamp_enable: vk::FALSE,
+            cull_mode: vk::CullModeFlags::NONE,
+            front_face: vk::FrontFace::COUNTER_CLOCKWISE,
+            line_width: 1.0,
+            polygon_mode: vk::PolygonMode::FILL,
+            rasterizer_discard_enable: vk::FALSE,
+            depth_bias_clamp: 0.0,
+            depth_bias_constant_factor: 0.0,
+            depth_bias_enable: vk::FALSE,
+            depth_bias_slope_factor: 0.0,
+        };
+
+        let multisample_state_create_info = vk::PipelineMultisampleStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
+            flags: vk::PipelineMultisampleStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            rasterization_samples: vk::SampleCountFlags::TYPE_1,
+            sample_shading_enable: vk::FALSE,
+            min_sample_shading: 0.0,
+            p_sample_mask: ptr::null(),
+            alpha_to_one_enable: vk::FALSE,
+            alpha_to_coverage_enable: vk::FALSE,
+        };
+
+        let color_blend_attachment_states = [vk::PipelineColorBlendAttachmentState {
+            blend_enable: vk::FALSE,
+            color_write_mask: vk::ColorComponentFlags::all(),
+            src_color_blend_factor: vk::BlendFactor::ONE,
+            dst_color_blend_factor: vk::BlendFactor::ZERO,
+            color_blend_op: vk::BlendOp::ADD,
+            src_alpha_blend_factor: vk::BlendFactor::ONE,
+            dst_alpha_blend_factor: vk::BlendFactor::ZERO,
+            alpha_blend_op: vk::BlendOp::ADD,
+        }];
+
+        let color_blend_state = vk::PipelineColorBlendStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_COLOR_BLEND_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineColorBlendStateCreateFlags::empty(),
+            logic_op_enable: vk::FALSE,
+            logic_op: vk::LogicOp::COPY,
+            attachment_count: color_blend_attachment_states.len() as u32,
+            p_attachments: color_blend_attachment_states.as_ptr(),
+            blend_constants: [0.0, 0.0, 0.0, 0.0],
+        };
+
+        let graphic_pipeline_create_infos = [vk::GraphicsPipelineCreateInfo {
+            s_type: vk::StructureType::GRAPHICS_PIPELINE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineCreateFlags::empty(),
+            stage_count: shader_stages.len() as u32,
+            p_stages: shader_stages.as_ptr(),
+            p_vertex_input_state: &vertex_input_state_create_info,
+            p_input_assembly_state: &vertex_input_assembly_state_info,
+            p_tessellation_state: ptr::null(),
+            p_viewport_state: &viewport_state_create_info,
+            p_rasterization_state: &rasterization_statue_create_info,
+            p_multisample_state: &multisample_state_create_info,
+            p_depth_stencil_state: ptr::null(),
+            p_color_blend_state: &color_blend_state,
+            p_dynamic_state: &dynamic_state_create_info,
+            layout: pipeline_layout,
+            render_pass,
+            subpass: 0,
+            base_pipeline_handle: vk::Pipeline::null(),
+            base_pipeline_index: -1,
+        }];
+
+        let graphics_pipelines = unsafe {
+            device
+                .create_graphics_pipelines(
+                    vk::PipelineCache::null(),
+                    &graphic_pipeline_create_infos,
+                    None,
+                )
+                .expect("Failed to create Graphics Pipeline!.")
+        };
+
+        unsafe {
+            device.destroy_shader_module(frag_shader_module, None);
+        }
+
+        graphics_pipelines[0]
+    }
+}
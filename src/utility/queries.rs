@@ -0,0 +1,330 @@
+//! Pipeline statistics queries per pass and occlusion queries per object.
+//!
+//! Like the GPU profiler, every slot owns its query pools and is read back without waiting the
+//! next time the same slot comes around, so the results arrive a frame or two late.
+
+use ash::version::DeviceV1_0;
+use ash::vk;
+
+use std::mem;
+use std::ptr;
+
+/// The counters collected by a pipeline statistics query, in the order Vulkan writes them.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PipelineStatistics {
+    pub input_assembly_vertices: u64,
+    pub vertex_shader_invocations: u64,
+    pub clipping_invocations: u64,
+    pub clipping_primitives: u64,
+    pub fragment_shader_invocations: u64,
+}
+
+impl PipelineStatistics {
+    fn query_flags() -> vk::QueryPipelineStatisticFlags {
+        vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES
+            | vk::QueryPipelineStatisticFlags::VERTEX_SHADER_INVOCATIONS
+            | vk::QueryPipelineStatisticFlags::CLIPPING_INVOCATIONS
+            | vk::QueryPipelineStatisticFlags::CLIPPING_PRIMITIVES
+            | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatisticsQuery(u32);
+
+struct QuerySlot {
+    statistics_pool: Option<vk::QueryPool>,
+    occlusion_pool: vk::QueryPool,
+    statistics_names: Vec<String>,
+    occlusion_count: u32,
+}
+
+pub struct QueryManager {
+    slots: Vec<QuerySlot>,
+    max_statistics_queries: u32,
+    max_occlusion_queries: u32,
+    is_occlusion_precise: bool,
+}
+
+impl QueryManager {
+    /// Create the query pools of `slot_count` slots.
+    ///
+    /// Pipeline statistics need the `pipelineStatisticsQuery` device feature, without it the
+    /// statistics queries are skipped. Without `occlusionQueryPrecise` the occlusion results
+    /// only tell whether any sample passed.
+    pub fn new(
+        device: &ash::Device,
+        enabled_features: &vk::PhysicalDeviceFeatures,
+        slot_count: usize,
+        max_statistics_queries: u32,
+        max_occlusion_queries: u32,
+    ) -> QueryManager {
+        let is_statistics_supported = enabled_features.pipeline_statistics_query == vk::TRUE;
+
+        let slots = (0..slot_count)
+            .map(|_| QuerySlot {
+                statistics_pool: if is_statistics_supported {
+                    Some(create_query_pool(
+                        device,
+                        vk::QueryType::PIPELINE_STATISTICS,
+                        max_statistics_queries,
+                        PipelineStatistics::query_flags(),
+                    ))
+                } else {
+                    None
+                },
+                occlusion_pool: create_query_pool(
+                    device,
+                    vk::QueryType::OCCLUSION,
+                    max_occlusion_queries,
+                    vk::QueryPipelineStatisticFlags::empty(),
+                ),
+                statistics_names: vec![],
+                occlusion_count: 0,
+            })
+            .collect();
+
+        QueryManager {
+            slots,
+            max_statistics_queries,
+            max_occlusion_queries,
+            is_occlusion_precise: enabled_features.occlusion_query_precise == vk::TRUE,
+        }
+    }
+
+    pub fn is_statistics_supported(&self) -> bool {
+        self.slots
+            .first()
+            .is_some_and(|slot| slot.statistics_pool.is_some())
+    }
+
+    /// Reset the queries of `slot` for `occlusion_count` objects. This must be recorded outside
+    /// of a render pass and before any query of the slot.
+    pub fn reset(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+        occlusion_count: u32,
+    ) {
+        assert!(
+            occlusion_count <= self.max_occlusion_queries,
+            "QueryManager is out of occlusion queries, increase max_occlusion_queries."
+        );
+
+        let slot = &mut self.slots[slot];
+
+        unsafe {
+            if let Some(statistics_pool) = slot.statistics_pool {
+                device.cmd_reset_query_pool(
+                    command_buffer,
+                    statistics_pool,
+                    0,
+                    self.max_statistics_queries,
+                );
+            }
+            device.cmd_reset_query_pool(
+                command_buffer,
+                slot.occlusion_pool,
+                0,
+                self.max_occlusion_queries,
+            );
+        }
+
+        slot.statistics_names.clear();
+        slot.occlusion_count = occlusion_count;
+    }
+
+    /// Begin collecting the pipeline statistics of a pass, `None` if they are not supported.
+    pub fn begin_statistics(
+        &mut self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+        name: &str,
+    ) -> Option<StatisticsQuery> {
+        let max_statistics_queries = self.max_statistics_queries;
+        let slot = &mut self.slots[slot];
+        let statistics_pool = slot.statistics_pool?;
+        assert!(
+            (slot.statistics_names.len() as u32) < max_statistics_queries,
+            "QueryManager is out of statistics queries, increase max_statistics_queries."
+        );
+
+        let query = StatisticsQuery(slot.statistics_names.len() as u32);
+        slot.statistics_names.push(name.to_string());
+
+        unsafe {
+            device.cmd_begin_query(
+                command_buffer,
+                statistics_pool,
+                query.0,
+                vk::QueryControlFlags::empty(),
+            );
+        }
+
+        Some(query)
+    }
+
+    pub fn end_statistics(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+        query: Option<StatisticsQuery>,
+    ) {
+        if let (Some(statistics_pool), Some(query)) = (self.slots[slot].statistics_pool, query) {
+            unsafe {
+                device.cmd_end_query(command_buffer, statistics_pool, query.0);
+            }
+        }
+    }
+
+    /// Begin counting the samples of object `object_index` that pass the depth test.
+    pub fn begin_occlusion(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+        object_index: u32,
+    ) {
+        let slot = &self.slots[slot];
+        assert!(object_index < slot.occlusion_count);
+
+        let flags = if self.is_occlusion_precise {
+            vk::QueryControlFlags::PRECISE
+        } else {
+            vk::QueryControlFlags::empty()
+        };
+
+        unsafe {
+            device.cmd_begin_query(command_buffer, slot.occlusion_pool, object_index, flags);
+        }
+    }
+
+    pub fn end_occlusion(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        slot: usize,
+        object_index: u32,
+    ) {
+        unsafe {
+            device.cmd_end_query(
+                command_buffer,
+                self.slots[slot].occlusion_pool,
+                object_index,
+            );
+        }
+    }
+
+    /// The pipeline statistics of every pass of the last submission of `slot`, `None` if the GPU
+    /// is not done with it yet or if nothing was recorded. This never waits.
+    pub fn statistics_results(
+        &self,
+        device: &ash::Device,
+        slot: usize,
+    ) -> Option<Vec<(String, PipelineStatistics)>> {
+        let slot = &self.slots[slot];
+        let statistics_pool = slot.statistics_pool?;
+        if slot.statistics_names.is_empty() {
+            return None;
+        }
+
+        let mut statistics = vec![PipelineStatistics::default(); slot.statistics_names.len()];
+        let is_ready = get_query_results(device, statistics_pool, &mut statistics);
+
+        if is_ready {
+            Some(
+                slot.statistics_names
+                    .iter()
+                    .cloned()
+                    .zip(statistics)
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    /// The number of samples that passed for every object of the last submission of `slot`,
+    /// `None` if the GPU is not done with it yet. This never waits.
+    pub fn occlusion_results(&self, device: &ash::Device, slot: usize) -> Option<Vec<u64>> {
+        let slot = &self.slots[slot];
+        if slot.occlusion_count == 0 {
+            return None;
+        }
+
+        let mut samples_passed = vec![0_u64; slot.occlusion_count as usize];
+        let is_ready = get_query_results(device, slot.occlusion_pool, &mut samples_passed);
+
+        if is_ready {
+            Some(samples_passed)
+        } else {
+            None
+        }
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        for slot in self.slots.iter() {
+            unsafe {
+                if let Some(statistics_pool) = slot.statistics_pool {
+                    device.destroy_query_pool(statistics_pool, None);
+                }
+                device.destroy_query_pool(slot.occlusion_pool, None);
+            }
+        }
+    }
+}
+
+fn create_query_pool(
+    device: &ash::Device,
+    query_type: vk::QueryType,
+    query_count: u32,
+    pipeline_statistics: vk::QueryPipelineStatisticFlags,
+) -> vk::QueryPool {
+    let query_pool_create_info = vk::QueryPoolCreateInfo {
+        s_type: vk::StructureType::QUERY_POOL_CREATE_INFO,
+        p_next: ptr::null(),
+        flags: vk::QueryPoolCreateFlags::empty(),
+        query_type,
+        query_count,
+        pipeline_statistics,
+    };
+
+    unsafe {
+        device
+            .create_query_pool(&query_pool_create_info, None)
+            .expect("Failed to create Query Pool!")
+    }
+}
+
+/// Read back one `T` per query from the start of `query_pool`, `T` being made of 64 bit
+/// counters. Return false if any of the results is not available yet.
+fn get_query_results<T>(
+    device: &ash::Device,
+    query_pool: vk::QueryPool,
+    results: &mut [T],
+) -> bool {
+    // `get_query_pool_results` of ash assumes a single value per query, which is not the case
+    // for pipeline statistics, so the stride is given by hand.
+    let result = unsafe {
+        device.fp_v1_0().get_query_pool_results(
+            device.handle(),
+            query_pool,
+            0,
+            results.len() as u32,
+            mem::size_of_val(results),
+            results.as_mut_ptr() as *mut _,
+            mem::size_of::<T>() as vk::DeviceSize,
+            vk::QueryResultFlags::TYPE_64,
+        )
+    };
+
+    match result {
+        vk::Result::SUCCESS => true,
+        vk::Result::NOT_READY => false,
+        _ => panic!("Failed to get Query Pool results!"),
+    }
+}
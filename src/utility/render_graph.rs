@@ -0,0 +1,1329 @@
+//! A small render graph.
+//!
+//! Passes declare which images and buffers they use and how. When the graph is compiled it
+//! orders the passes by their dependencies, drops passes whose results are never used,
+//! allocates the transient images, works out every pipeline barrier and layout transition,
+//! and creates the render passes and framebuffers of the graphics passes.
+//!
+//! Layout transitions are done with explicit barriers before each pass, so every render pass
+//! created here keeps its attachments in the attachment layout from begin to end.
+
+use ash::version::DeviceV1_0;
+use ash::vk;
+
+use std::collections::VecDeque;
+use std::ptr;
+
+use crate::utility::gpu_profiler::GpuProfiler;
+use crate::utility::share;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageHandle(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferHandle(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PassHandle(usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PassKind {
+    Graphics,
+    Compute,
+    Transfer,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ImageDesc {
+    pub format: vk::Format,
+    pub extent: vk::Extent2D,
+    pub samples: vk::SampleCountFlags,
+}
+
+/// How a pass uses an image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageUsage {
+    ColorAttachment,
+    ResolveAttachment,
+    DepthStencilAttachment,
+    DepthStencilReadOnly,
+    Sampled(vk::PipelineStageFlags),
+    StorageRead(vk::PipelineStageFlags),
+    StorageWrite(vk::PipelineStageFlags),
+    TransferSrc,
+    TransferDst,
+}
+
+/// How a pass uses a buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BufferUsage {
+    Vertex,
+    Index,
+    Indirect,
+    Uniform(vk::PipelineStageFlags),
+    StorageRead(vk::PipelineStageFlags),
+    StorageWrite(vk::PipelineStageFlags),
+    TransferSrc,
+    TransferDst,
+}
+
+/// What happens to an attachment at the beginning of a render pass.
+#[derive(Clone, Copy)]
+pub enum AttachmentLoad {
+    Load,
+    Clear(vk::ClearValue),
+    DontCare,
+}
+
+/// The layout, stages and accesses an image is in between two uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ImageState {
+    pub layout: vk::ImageLayout,
+    pub stage_mask: vk::PipelineStageFlags,
+    pub access_mask: vk::AccessFlags,
+}
+
+impl ImageState {
+    /// Nothing is known about the content, e.g. an image that has just been created.
+    pub fn undefined() -> ImageState {
+        ImageState {
+            layout: vk::ImageLayout::UNDEFINED,
+            stage_mask: vk::PipelineStageFlags::TOP_OF_PIPE,
+            access_mask: vk::AccessFlags::empty(),
+        }
+    }
+
+    /// A swapchain image just acquired, its availability semaphore is waited at the color output stage.
+    pub fn acquired_swapchain_image() -> ImageState {
+        ImageState {
+            layout: vk::ImageLayout::UNDEFINED,
+            stage_mask: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            access_mask: vk::AccessFlags::empty(),
+        }
+    }
+}
+
+impl ImageUsage {
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            ImageUsage::ColorAttachment
+                | ImageUsage::ResolveAttachment
+                | ImageUsage::DepthStencilAttachment
+                | ImageUsage::StorageWrite(_)
+                | ImageUsage::TransferDst
+        )
+    }
+
+    pub fn is_attachment(&self) -> bool {
+        matches!(
+            self,
+            ImageUsage::ColorAttachment
+                | ImageUsage::ResolveAttachment
+                | ImageUsage::DepthStencilAttachment
+                | ImageUsage::DepthStencilReadOnly
+        )
+    }
+
+    pub fn state(&self) -> ImageState {
+        let (layout, stage_mask, access_mask) = match *self {
+            ImageUsage::ColorAttachment | ImageUsage::ResolveAttachment => (
+                vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+                vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            ),
+            ImageUsage::DepthStencilAttachment => (
+                vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL,
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                    | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            ),
+            ImageUsage::DepthStencilReadOnly => (
+                vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL,
+                vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                    | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ,
+            ),
+            ImageUsage::Sampled(stages) => (
+                vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                stages,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            ImageUsage::StorageRead(stages) => (
+                vk::ImageLayout::GENERAL,
+                stages,
+                vk::AccessFlags::SHADER_READ,
+            ),
+            ImageUsage::StorageWrite(stages) => (
+                vk::ImageLayout::GENERAL,
+                stages,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ),
+            ImageUsage::TransferSrc => (
+                vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            ImageUsage::TransferDst => (
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+        };
+
+        ImageState {
+            layout,
+            stage_mask,
+            access_mask,
+        }
+    }
+
+    fn image_usage_flags(&self) -> vk::ImageUsageFlags {
+        match self {
+            ImageUsage::ColorAttachment | ImageUsage::ResolveAttachment => {
+                vk::ImageUsageFlags::COLOR_ATTACHMENT
+            }
+            ImageUsage::DepthStencilAttachment | ImageUsage::DepthStencilReadOnly => {
+                vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+            }
+            ImageUsage::Sampled(_) => vk::ImageUsageFlags::SAMPLED,
+            ImageUsage::StorageRead(_) | ImageUsage::StorageWrite(_) => {
+                vk::ImageUsageFlags::STORAGE
+            }
+            ImageUsage::TransferSrc => vk::ImageUsageFlags::TRANSFER_SRC,
+            ImageUsage::TransferDst => vk::ImageUsageFlags::TRANSFER_DST,
+        }
+    }
+}
+
+impl BufferUsage {
+    pub fn is_write(&self) -> bool {
+        matches!(
+            self,
+            BufferUsage::StorageWrite(_) | BufferUsage::TransferDst
+        )
+    }
+
+    pub fn stage_and_access(&self) -> (vk::PipelineStageFlags, vk::AccessFlags) {
+        match *self {
+            BufferUsage::Vertex => (
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::AccessFlags::VERTEX_ATTRIBUTE_READ,
+            ),
+            BufferUsage::Index => (
+                vk::PipelineStageFlags::VERTEX_INPUT,
+                vk::AccessFlags::INDEX_READ,
+            ),
+            BufferUsage::Indirect => (
+                vk::PipelineStageFlags::DRAW_INDIRECT,
+                vk::AccessFlags::INDIRECT_COMMAND_READ,
+            ),
+            BufferUsage::Uniform(stages) => (stages, vk::AccessFlags::UNIFORM_READ),
+            BufferUsage::StorageRead(stages) => (stages, vk::AccessFlags::SHADER_READ),
+            BufferUsage::StorageWrite(stages) => (
+                stages,
+                vk::AccessFlags::SHADER_READ | vk::AccessFlags::SHADER_WRITE,
+            ),
+            BufferUsage::TransferSrc => (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_READ,
+            ),
+            BufferUsage::TransferDst => (
+                vk::PipelineStageFlags::TRANSFER,
+                vk::AccessFlags::TRANSFER_WRITE,
+            ),
+        }
+    }
+}
+
+/// Everything a pass needs to record its commands.
+pub struct PassContext<'a> {
+    pub device: &'a ash::Device,
+    pub command_buffer: vk::CommandBuffer,
+    /// The render pass the commands are recorded in, null for compute and transfer passes.
+    pub render_pass: vk::RenderPass,
+    pub extent: vk::Extent2D,
+    /// Which instance of the imported images is used, e.g. the acquired swapchain image index.
+    pub instance_index: usize,
+}
+
+struct ImageResource {
+    name: String,
+    desc: ImageDesc,
+    // one entry for transient images, several for imported images like the swapchain images.
+    images: Vec<vk::Image>,
+    views: Vec<vk::ImageView>,
+    memory: Option<vk::DeviceMemory>,
+    usage_flags: vk::ImageUsageFlags,
+    imported: Option<ImportedImage>,
+}
+
+struct ImportedImage {
+    initial_state: ImageState,
+    final_layout: vk::ImageLayout,
+}
+
+struct BufferResource {
+    name: String,
+    buffer: vk::Buffer,
+}
+
+struct ImageUse {
+    image: ImageHandle,
+    usage: ImageUsage,
+    load: AttachmentLoad,
+}
+
+struct PassNode {
+    name: String,
+    kind: PassKind,
+    images: Vec<ImageUse>,
+    buffers: Vec<(BufferHandle, BufferUsage)>,
+}
+
+struct ImageBarrierDesc {
+    image: ImageHandle,
+    old_state: ImageState,
+    new_state: ImageState,
+}
+
+struct BufferBarrierDesc {
+    buffer: BufferHandle,
+    src: (vk::PipelineStageFlags, vk::AccessFlags),
+    dst: (vk::PipelineStageFlags, vk::AccessFlags),
+}
+
+#[derive(Default)]
+struct Barriers {
+    images: Vec<ImageBarrierDesc>,
+    buffers: Vec<BufferBarrierDesc>,
+}
+
+/// What a use of an image does to its state, and the barrier it needs, if any.
+struct ImageUseTransition {
+    barrier: Option<ImageBarrierDesc>,
+    // (state after the use, is the use a write)
+    next_state: (ImageState, bool),
+}
+
+impl ImageUseTransition {
+    fn new(
+        (current, is_last_write): (ImageState, bool),
+        image_use: &ImageUse,
+    ) -> ImageUseTransition {
+        let mut new_state = image_use.usage.state();
+        let is_write = image_use.usage.is_write();
+
+        // the old content does not matter when it is going to be cleared or discarded.
+        let is_content_discarded =
+            image_use.usage.is_attachment() && !matches!(image_use.load, AttachmentLoad::Load);
+
+        let barrier = if current.layout == new_state.layout && !is_last_write && !is_write {
+            // read after read, only widen the stages the next writer has to wait for.
+            new_state.stage_mask |= current.stage_mask;
+            new_state.access_mask |= current.access_mask;
+            None
+        } else {
+            Some(ImageBarrierDesc {
+                image: image_use.image,
+                old_state: ImageState {
+                    layout: if is_content_discarded {
+                        vk::ImageLayout::UNDEFINED
+                    } else {
+                        current.layout
+                    },
+                    stage_mask: current.stage_mask,
+                    access_mask: if is_last_write {
+                        current.access_mask
+                    } else {
+                        vk::AccessFlags::empty()
+                    },
+                },
+                new_state,
+            })
+        };
+
+        ImageUseTransition {
+            barrier,
+            next_state: (new_state, is_write),
+        }
+    }
+}
+
+struct CompiledPass {
+    pass: usize,
+    barriers: Barriers,
+    render_pass: vk::RenderPass,
+    framebuffers: Vec<vk::Framebuffer>,
+    clear_values: Vec<vk::ClearValue>,
+    extent: vk::Extent2D,
+}
+
+pub struct RenderGraph {
+    images: Vec<ImageResource>,
+    buffers: Vec<BufferResource>,
+    passes: Vec<PassNode>,
+
+    compiled_passes: Vec<CompiledPass>,
+    final_barriers: Barriers,
+    is_compiled: bool,
+}
+
+pub struct PassBuilder<'a> {
+    graph: &'a mut RenderGraph,
+    pass: usize,
+}
+
+impl<'a> PassBuilder<'a> {
+    pub fn color_attachment(self, image: ImageHandle, load: AttachmentLoad) -> Self {
+        self.use_image_with_load(image, ImageUsage::ColorAttachment, load)
+    }
+
+    /// The multisampled color attachments are resolved into these, in declaration order.
+    pub fn resolve_attachment(self, image: ImageHandle) -> Self {
+        self.use_image_with_load(
+            image,
+            ImageUsage::ResolveAttachment,
+            AttachmentLoad::DontCare,
+        )
+    }
+
+    pub fn depth_stencil_attachment(self, image: ImageHandle, load: AttachmentLoad) -> Self {
+        self.use_image_with_load(image, ImageUsage::DepthStencilAttachment, load)
+    }
+
+    pub fn depth_stencil_read_only(self, image: ImageHandle) -> Self {
+        self.use_image_with_load(
+            image,
+            ImageUsage::DepthStencilReadOnly,
+            AttachmentLoad::Load,
+        )
+    }
+
+    /// Declare a non-attachment use of an image, like sampling it or a transfer.
+    pub fn use_image(self, image: ImageHandle, usage: ImageUsage) -> Self {
+        assert!(
+            !usage.is_attachment(),
+            "Use the attachment functions to declare attachments."
+        );
+        self.use_image_with_load(image, usage, AttachmentLoad::Load)
+    }
+
+    pub fn use_buffer(self, buffer: BufferHandle, usage: BufferUsage) -> Self {
+        self.graph.passes[self.pass].buffers.push((buffer, usage));
+        self
+    }
+
+    pub fn finish(self) -> PassHandle {
+        PassHandle(self.pass)
+    }
+
+    fn use_image_with_load(
+        self,
+        image: ImageHandle,
+        usage: ImageUsage,
+        load: AttachmentLoad,
+    ) -> Self {
+        assert!(image.0 < self.graph.images.len(), "Invalid image handle.");
+
+        self.graph.passes[self.pass]
+            .images
+            .push(ImageUse { image, usage, load });
+        self
+    }
+}
+
+impl RenderGraph {
+    pub fn new() -> RenderGraph {
+        RenderGraph {
+            images: vec![],
+            buffers: vec![],
+            passes: vec![],
+
+            compiled_passes: vec![],
+            final_barriers: Barriers::default(),
+            is_compiled: false,
+        }
+    }
+
+    /// An image owned by the graph, created when the graph is compiled.
+    pub fn create_image(&mut self, name: &str, desc: ImageDesc) -> ImageHandle {
+        self.images.push(ImageResource {
+            name: name.to_owned(),
+            desc,
+            images: vec![],
+            views: vec![],
+            memory: None,
+            usage_flags: vk::ImageUsageFlags::empty(),
+            imported: None,
+        });
+
+        ImageHandle(self.images.len() - 1)
+    }
+
+    /// An image owned by the caller, e.g. the swapchain images.
+    ///
+    /// With several images, `execute` picks one of them by its instance index. They start in
+    /// `initial_state` and are left in `final_layout` once the graph has been executed.
+    pub fn import_image(
+        &mut self,
+        name: &str,
+        images: &[vk::Image],
+        views: &[vk::ImageView],
+        desc: ImageDesc,
+        initial_state: ImageState,
+        final_layout: vk::ImageLayout,
+    ) -> ImageHandle {
+        assert!(
+            !images.is_empty() && images.len() == views.len(),
+            "Every imported image needs exactly one view."
+        );
+
+        self.images.push(ImageResource {
+            name: name.to_owned(),
+            desc,
+            images: images.to_vec(),
+            views: views.to_vec(),
+            memory: None,
+            usage_flags: vk::ImageUsageFlags::empty(),
+            imported: Some(ImportedImage {
+                initial_state,
+                final_layout,
+            }),
+        });
+
+        ImageHandle(self.images.len() - 1)
+    }
+
+    pub fn import_buffer(&mut self, name: &str, buffer: vk::Buffer) -> BufferHandle {
+        self.buffers.push(BufferResource {
+            name: name.to_owned(),
+            buffer,
+        });
+
+        BufferHandle(self.buffers.len() - 1)
+    }
+
+    pub fn add_pass(&mut self, name: &str, kind: PassKind) -> PassBuilder<'_> {
+        assert!(
+            !self.is_compiled,
+            "Passes can not be added to a compiled render graph."
+        );
+
+        self.passes.push(PassNode {
+            name: name.to_owned(),
+            kind,
+            images: vec![],
+            buffers: vec![],
+        });
+
+        let pass = self.passes.len() - 1;
+        PassBuilder { graph: self, pass }
+    }
+
+    pub fn image(&self, image: ImageHandle, instance_index: usize) -> vk::Image {
+        let resource = &self.images[image.0];
+        resource.images[instance_index % resource.images.len()]
+    }
+
+    pub fn image_view(&self, image: ImageHandle, instance_index: usize) -> vk::ImageView {
+        let resource = &self.images[image.0];
+        resource.views[instance_index % resource.views.len()]
+    }
+
+    /// How many images stand behind `image`, one for transient images.
+    pub fn image_instance_count(&self, image: ImageHandle) -> usize {
+        self.images[image.0].views.len()
+    }
+
+    pub fn image_desc(&self, image: ImageHandle) -> ImageDesc {
+        self.images[image.0].desc
+    }
+
+    pub fn buffer(&self, buffer: BufferHandle) -> vk::Buffer {
+        self.buffers[buffer.0].buffer
+    }
+
+    /// The render pass created for a graphics pass, needed to create its pipelines.
+    ///
+    /// Returns `None` before compilation, for non-graphics passes and for culled passes.
+    pub fn render_pass(&self, pass: PassHandle) -> Option<vk::RenderPass> {
+        self.compiled_passes
+            .iter()
+            .find(|compiled| compiled.pass == pass.0)
+            .map(|compiled| compiled.render_pass)
+            .filter(|render_pass| *render_pass != vk::RenderPass::null())
+    }
+
+    /// The names of the passes that will run, in execution order.
+    pub fn execution_order(&self) -> Vec<&str> {
+        self.compiled_passes
+            .iter()
+            .map(|compiled| self.passes[compiled.pass].name.as_str())
+            .collect()
+    }
+
+    /// Order the passes, allocate transient images and create the barriers, render passes and framebuffers.
+    pub fn compile(
+        &mut self,
+        device: &ash::Device,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    ) {
+        assert!(!self.is_compiled, "Render graph is compiled twice.");
+
+        let order = self.sort_passes();
+        let order = self.cull_passes(order);
+
+        self.allocate_images(device, device_memory_properties, &order);
+        self.build_barriers(&order);
+        self.create_render_passes(device);
+
+        self.is_compiled = true;
+    }
+
+    /// Record every pass in `command_buffer`.
+    ///
+    /// `record_pass` is called once for each pass, inside its render pass for graphics passes.
+    pub fn execute<F>(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        instance_index: usize,
+        record_pass: F,
+    ) where
+        F: FnMut(PassHandle, &PassContext),
+    {
+        self.execute_passes(device, command_buffer, instance_index, None, record_pass);
+    }
+
+    /// Like `execute`, with every pass measured as a region of `profiler_slot` of `profiler`,
+    /// named after the pass. The profiler frame must have begun already.
+    pub fn execute_profiled<F>(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        instance_index: usize,
+        profiler: &mut GpuProfiler,
+        profiler_slot: usize,
+        record_pass: F,
+    ) where
+        F: FnMut(PassHandle, &PassContext),
+    {
+        self.execute_passes(
+            device,
+            command_buffer,
+            instance_index,
+            Some((profiler, profiler_slot)),
+            record_pass,
+        );
+    }
+
+    fn execute_passes<F>(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        instance_index: usize,
+        mut profiler: Option<(&mut GpuProfiler, usize)>,
+        mut record_pass: F,
+    ) where
+        F: FnMut(PassHandle, &PassContext),
+    {
+        assert!(
+            self.is_compiled,
+            "Render graph must be compiled before execution."
+        );
+
+        for compiled in self.compiled_passes.iter() {
+            self.record_barriers(device, command_buffer, instance_index, &compiled.barriers);
+
+            let context = PassContext {
+                device,
+                command_buffer,
+                render_pass: compiled.render_pass,
+                extent: compiled.extent,
+                instance_index,
+            };
+
+            let region = profiler.as_mut().map(|(profiler, slot)| {
+                profiler.begin_region(
+                    device,
+                    command_buffer,
+                    *slot,
+                    &self.passes[compiled.pass].name,
+                )
+            });
+
+            if compiled.render_pass != vk::RenderPass::null() {
+                let render_pass_begin_info = vk::RenderPassBeginInfo {
+                    s_type: vk::StructureType::RENDER_PASS_BEGIN_INFO,
+                    p_next: ptr::null(),
+                    render_pass: compiled.render_pass,
+                    framebuffer: compiled.framebuffers
+                        [instance_index % compiled.framebuffers.len()],
+                    render_area: vk::Rect2D {
+                        offset: vk::Offset2D { x: 0, y: 0 },
+                        extent: compiled.extent,
+                    },
+                    clear_value_count: compiled.clear_values.len() as u32,
+                    p_clear_values: compiled.clear_values.as_ptr(),
+                };
+
+                unsafe {
+                    device.cmd_begin_render_pass(
+                        command_buffer,
+                        &render_pass_begin_info,
+                        vk::SubpassContents::INLINE,
+                    );
+                }
+                record_pass(PassHandle(compiled.pass), &context);
+                unsafe {
+                    device.cmd_end_render_pass(command_buffer);
+                }
+            } else {
+                record_pass(PassHandle(compiled.pass), &context);
+            }
+
+            if let (Some((profiler, slot)), Some(region)) = (profiler.as_mut(), region) {
+                profiler.end_region(device, command_buffer, *slot, region);
+            }
+        }
+
+        self.record_barriers(device, command_buffer, instance_index, &self.final_barriers);
+    }
+
+    /// Destroy everything created by `compile`. Imported resources are left untouched.
+    pub fn destroy(&mut self, device: &ash::Device) {
+        unsafe {
+            for compiled in self.compiled_passes.iter() {
+                for &framebuffer in compiled.framebuffers.iter() {
+                    device.destroy_framebuffer(framebuffer, None);
+                }
+                if compiled.render_pass != vk::RenderPass::null() {
+                    device.destroy_render_pass(compiled.render_pass, None);
+                }
+            }
+
+            for resource in self.images.iter_mut() {
+                if let Some(memory) = resource.memory.take() {
+                    for &view in resource.views.iter() {
+                        device.destroy_image_view(view, None);
+                    }
+                    for &image in resource.images.iter() {
+                        device.destroy_image(image, None);
+                    }
+                    device.free_memory(memory, None);
+
+                    resource.views.clear();
+                    resource.images.clear();
+                }
+            }
+        }
+
+        self.compiled_passes.clear();
+        self.final_barriers = Barriers::default();
+        self.is_compiled = false;
+    }
+
+    fn image_users(&self, pass: usize) -> impl Iterator<Item = (usize, bool)> + '_ {
+        self.passes[pass]
+            .images
+            .iter()
+            .map(|image_use| (image_use.image.0, image_use.usage.is_write()))
+    }
+
+    fn buffer_users(&self, pass: usize) -> impl Iterator<Item = (usize, bool)> + '_ {
+        self.passes[pass]
+            .buffers
+            .iter()
+            .map(|(buffer, usage)| (buffer.0, usage.is_write()))
+    }
+
+    /// Topologically sort the passes, keeping declaration order between independent passes.
+    ///
+    /// A read depends on the closest write declared before it, or the first write after it
+    /// when the resource is read before its producer is declared. Every access also depends
+    /// on the accesses declared before it that it may conflict with.
+    fn sort_passes(&self) -> Vec<usize> {
+        let pass_count = self.passes.len();
+        let mut dependencies: Vec<Vec<usize>> = vec![vec![]; pass_count];
+
+        let mut add_dependencies = |accesses: &dyn Fn(usize) -> Vec<(usize, bool)>,
+                                    resource_count| {
+            for resource in 0..resource_count {
+                // (pass, is_write) in declaration order.
+                let uses: Vec<(usize, bool)> = (0..pass_count)
+                    .flat_map(|pass| {
+                        accesses(pass)
+                            .into_iter()
+                            .filter(move |(id, _)| *id == resource)
+                            .map(move |(_, is_write)| (pass, is_write))
+                    })
+                    .collect();
+
+                for (i, &(pass, is_write)) in uses.iter().enumerate() {
+                    let previous_write = uses[..i]
+                        .iter()
+                        .rev()
+                        .find(|(other, other_write)| *other_write && *other != pass);
+
+                    match previous_write {
+                        Some(&(writer, _)) => dependencies[pass].push(writer),
+                        None if !is_write => {
+                            // read before any declared write: wait for the first producer.
+                            if let Some(&(writer, _)) = uses[i + 1..]
+                                .iter()
+                                .find(|(other, other_write)| *other_write && *other != pass)
+                            {
+                                dependencies[pass].push(writer);
+                            }
+                        }
+                        None => {}
+                    }
+
+                    if is_write {
+                        // write after read, later writers wait for the earlier readers.
+                        for &(reader, reader_write) in uses[..i].iter() {
+                            if !reader_write
+                                && reader != pass
+                                && !dependencies[reader].contains(&pass)
+                            {
+                                dependencies[pass].push(reader);
+                            }
+                        }
+                    }
+                }
+            }
+        };
+
+        add_dependencies(&|pass| self.image_users(pass).collect(), self.images.len());
+        add_dependencies(
+            &|pass| self.buffer_users(pass).collect(),
+            self.buffers.len(),
+        );
+
+        // Kahn's algorithm, always picking the earliest declared pass that is ready.
+        let mut remaining: Vec<usize> = dependencies.iter().map(|deps| deps.len()).collect();
+        let mut order = Vec::with_capacity(pass_count);
+        let mut is_scheduled = vec![false; pass_count];
+
+        while order.len() < pass_count {
+            let next = (0..pass_count).find(|&pass| !is_scheduled[pass] && remaining[pass] == 0);
+            let next = match next {
+                Some(pass) => pass,
+                None => panic!("Render graph contains a dependency cycle!"),
+            };
+
+            is_scheduled[next] = true;
+            order.push(next);
+            for (pass, deps) in dependencies.iter().enumerate() {
+                remaining[pass] -= deps.iter().filter(|&&dep| dep == next).count();
+            }
+        }
+
+        order
+    }
+
+    /// Drop passes that write nothing that is imported or read by a pass that is kept.
+    fn cull_passes(&self, order: Vec<usize>) -> Vec<usize> {
+        let mut is_needed = vec![false; self.passes.len()];
+        let mut is_image_needed: Vec<bool> = self
+            .images
+            .iter()
+            .map(|resource| resource.imported.is_some())
+            .collect();
+        // buffers are always imported, so writing one is a side effect.
+        let mut queue: VecDeque<usize> = order.iter().rev().cloned().collect();
+
+        while let Some(pass) = queue.pop_front() {
+            if is_needed[pass] {
+                continue;
+            }
+
+            let is_writing_needed = self
+                .image_users(pass)
+                .any(|(image, is_write)| is_write && is_image_needed[image])
+                || self.buffer_users(pass).any(|(_, is_write)| is_write)
+                || self.passes[pass].images.is_empty() && self.passes[pass].buffers.is_empty();
+
+            if is_writing_needed {
+                is_needed[pass] = true;
+                for (image, _) in self.image_users(pass) {
+                    if !is_image_needed[image] {
+                        is_image_needed[image] = true;
+                        // producers of this image may have been skipped already.
+                        queue.extend(order.iter().rev().cloned());
+                    }
+                }
+            }
+        }
+
+        order.into_iter().filter(|&pass| is_needed[pass]).collect()
+    }
+
+    fn allocate_images(
+        &mut self,
+        device: &ash::Device,
+        device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+        order: &[usize],
+    ) {
+        for &pass in order.iter() {
+            for image_use in self.passes[pass].images.iter() {
+                self.images[image_use.image.0].usage_flags |= image_use.usage.image_usage_flags();
+            }
+        }
+
+        for resource in self.images.iter_mut() {
+            if resource.imported.is_some() || resource.usage_flags.is_empty() {
+                continue;
+            }
+
+            let (image, memory) = share::v1::create_image(
+                device,
+                resource.desc.extent.width,
+                resource.desc.extent.height,
+                1,
+                resource.desc.samples,
+                resource.desc.format,
+                vk::ImageTiling::OPTIMAL,
+                resource.usage_flags,
+                vk::MemoryPropertyFlags::DEVICE_LOCAL,
+                device_memory_properties,
+            );
+            let view = share::v1::create_image_view(
+                device,
+                image,
+                resource.desc.format,
+                share::image_aspect_mask(resource.desc.format),
+                1,
+            );
+
+            resource.images = vec![image];
+            resource.views = vec![view];
+            resource.memory = Some(memory);
+        }
+    }
+
+    fn build_barriers(&mut self, order: &[usize]) {
+        // (current state, is the last access a write) for every resource.
+        let mut image_states: Vec<(ImageState, bool)> = self
+            .images
+            .iter()
+            .map(|resource| match &resource.imported {
+                Some(imported) => (imported.initial_state, false),
+                None => (ImageState::undefined(), false),
+            })
+            .collect();
+
+        // transient images are shared by the frames in flight, so the first use in a frame has
+        // to wait for the last use in the previous one. The layout stays undefined, the content
+        // of a transient image is never carried over from one frame to the next.
+        for &pass in order.iter() {
+            for image_use in self.passes[pass].images.iter() {
+                let image_state = &mut image_states[image_use.image.0];
+                *image_state = ImageUseTransition::new(*image_state, image_use).next_state;
+            }
+        }
+        for (index, resource) in self.images.iter().enumerate() {
+            image_states[index] = match &resource.imported {
+                Some(imported) => (imported.initial_state, false),
+                None => {
+                    let (last_use_state, is_last_write) = image_states[index];
+                    let state = ImageState {
+                        layout: vk::ImageLayout::UNDEFINED,
+                        ..last_use_state
+                    };
+                    (state, is_last_write)
+                }
+            };
+        }
+
+        let mut buffer_states: Vec<(vk::PipelineStageFlags, vk::AccessFlags, bool)> = self
+            .buffers
+            .iter()
+            .map(|_| {
+                (
+                    vk::PipelineStageFlags::TOP_OF_PIPE,
+                    vk::AccessFlags::empty(),
+                    false,
+                )
+            })
+            .collect();
+
+        let mut compiled_passes = vec![];
+        for &pass in order.iter() {
+            let mut barriers = Barriers::default();
+
+            for image_use in self.passes[pass].images.iter() {
+                let transition =
+                    ImageUseTransition::new(image_states[image_use.image.0], image_use);
+                if let Some(barrier) = transition.barrier {
+                    barriers.images.push(barrier);
+                }
+                image_states[image_use.image.0] = transition.next_state;
+            }
+
+            for &(buffer, usage) in self.passes[pass].buffers.iter() {
+                let (current_stage, current_access, is_last_write) = buffer_states[buffer.0];
+                let (stage, access) = usage.stage_and_access();
+                let is_write = usage.is_write();
+
+                if is_last_write || is_write {
+                    barriers.buffers.push(BufferBarrierDesc {
+                        buffer,
+                        src: (
+                            current_stage,
+                            if is_last_write {
+                                current_access
+                            } else {
+                                vk::AccessFlags::empty()
+                            },
+                        ),
+                        dst: (stage, access),
+                    });
+                    buffer_states[buffer.0] = (stage, access, is_write);
+                } else {
+                    buffer_states[buffer.0] =
+                        (current_stage | stage, current_access | access, false);
+                }
+            }
+
+            compiled_passes.push(CompiledPass {
+                pass,
+                barriers,
+                render_pass: vk::RenderPass::null(),
+                framebuffers: vec![],
+                clear_values: vec![],
+                extent: vk::Extent2D {
+                    width: 0,
+                    height: 0,
+                },
+            });
+        }
+
+        // hand imported images back in the layout the caller expects.
+        let mut final_barriers = Barriers::default();
+        for (index, resource) in self.images.iter().enumerate() {
+            if let Some(imported) = &resource.imported {
+                let (current, is_last_write) = image_states[index];
+                if current.layout != imported.final_layout || is_last_write {
+                    final_barriers.images.push(ImageBarrierDesc {
+                        image: ImageHandle(index),
+                        old_state: ImageState {
+                            access_mask: if is_last_write {
+                                current.access_mask
+                            } else {
+                                vk::AccessFlags::empty()
+                            },
+                            ..current
+                        },
+                        new_state: ImageState {
+                            layout: imported.final_layout,
+                            stage_mask: vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+                            access_mask: vk::AccessFlags::empty(),
+                        },
+                    });
+                }
+            }
+        }
+
+        self.compiled_passes = compiled_passes;
+        self.final_barriers = final_barriers;
+    }
+
+    fn create_render_passes(&mut self, device: &ash::Device) {
+        for compiled_index in 0..self.compiled_passes.len() {
+            let pass = self.compiled_passes[compiled_index].pass;
+            if self.passes[pass].kind != PassKind::Graphics {
+                continue;
+            }
+
+            let attachment_uses: Vec<&ImageUse> = self.passes[pass]
+                .images
+                .iter()
+                .filter(|image_use| image_use.usage.is_attachment())
+                .collect();
+            assert!(
+                !attachment_uses.is_empty(),
+                "Graphics pass {} has no attachment.",
+                self.passes[pass].name
+            );
+
+            let mut attachments = vec![];
+            let mut clear_values = vec![];
+            let mut color_refs = vec![];
+            let mut resolve_refs = vec![];
+            let mut depth_ref = None;
+
+            for (index, image_use) in attachment_uses.iter().enumerate() {
+                let resource = &self.images[image_use.image.0];
+                let state = image_use.usage.state();
+                let is_read_later = self.is_read_after(compiled_index, image_use.image);
+
+                let (load_op, clear_value) = match image_use.load {
+                    AttachmentLoad::Load => (vk::AttachmentLoadOp::LOAD, None),
+                    AttachmentLoad::Clear(value) => (vk::AttachmentLoadOp::CLEAR, Some(value)),
+                    AttachmentLoad::DontCare => (vk::AttachmentLoadOp::DONT_CARE, None),
+                };
+                let store_op =
+                    if is_read_later || image_use.usage == ImageUsage::DepthStencilReadOnly {
+                        vk::AttachmentStoreOp::STORE
+                    } else {
+                        vk::AttachmentStoreOp::DONT_CARE
+                    };
+                let has_stencil = share::has_stencil_component(resource.desc.format);
+
+                attachments.push(vk::AttachmentDescription {
+                    flags: vk::AttachmentDescriptionFlags::empty(),
+                    format: resource.desc.format,
+                    samples: resource.desc.samples,
+                    load_op,
+                    store_op,
+                    stencil_load_op: if has_stencil {
+                        load_op
+                    } else {
+                        vk::AttachmentLoadOp::DONT_CARE
+                    },
+                    stencil_store_op: if has_stencil {
+                        store_op
+                    } else {
+                        vk::AttachmentStoreOp::DONT_CARE
+                    },
+                    // the barriers before the pass already moved the image into this layout.
+                    initial_layout: state.layout,
+                    final_layout: state.layout,
+                });
+                clear_values.push(clear_value.unwrap_or(vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: [0.0, 0.0, 0.0, 0.0],
+                    },
+                }));
+
+                let reference = vk::AttachmentReference {
+                    attachment: index as u32,
+                    layout: state.layout,
+                };
+                match image_use.usage {
+                    ImageUsage::ColorAttachment => color_refs.push(reference),
+                    ImageUsage::ResolveAttachment => resolve_refs.push(reference),
+                    _ => depth_ref = Some(reference),
+                }
+            }
+
+            assert!(
+                resolve_refs.is_empty() || resolve_refs.len() == color_refs.len(),
+                "Graphics pass {} must resolve all or none of its color attachments.",
+                self.passes[pass].name
+            );
+
+            let subpasses = [vk::SubpassDescription {
+                flags: vk::SubpassDescriptionFlags::empty(),
+                pipeline_bind_point: vk::PipelineBindPoint::GRAPHICS,
+                input_attachment_count: 0,
+                p_input_attachments: ptr::null(),
+                color_attachment_count: color_refs.len() as u32,
+                p_color_attachments: color_refs.as_ptr(),
+                p_resolve_attachments: if resolve_refs.is_empty() {
+                    ptr::null()
+                } else {
+                    resolve_refs.as_ptr()
+                },
+                p_depth_stencil_attachment: match &depth_ref {
+                    Some(reference) => reference,
+                    None => ptr::null(),
+                },
+                preserve_attachment_count: 0,
+                p_preserve_attachments: ptr::null(),
+            }];
+
+            let render_pass_create_info = vk::RenderPassCreateInfo {
+                s_type: vk::StructureType::RENDER_PASS_CREATE_INFO,
+                p_next: ptr::null(),
+                flags: vk::RenderPassCreateFlags::empty(),
+                attachment_count: attachments.len() as u32,
+                p_attachments: attachments.as_ptr(),
+                subpass_count: subpasses.len() as u32,
+                p_subpasses: subpasses.as_ptr(),
+                // synchronization is done by the barriers recorded before the pass.
+                dependency_count: 0,
+                p_dependencies: ptr::null(),
+            };
+
+            let render_pass = unsafe {
+                device
+                    .create_render_pass(&render_pass_create_info, None)
+                    .expect("Failed to create render pass!")
+            };
+
+            let extent = self.images[attachment_uses[0].image.0].desc.extent;
+            let instance_count = attachment_uses
+                .iter()
+                .map(|image_use| self.images[image_use.image.0].views.len())
+                .max()
+                .unwrap_or(1);
+
+            let mut framebuffers = vec![];
+            for instance_index in 0..instance_count {
+                let views: Vec<vk::ImageView> = attachment_uses
+                    .iter()
+                    .map(|image_use| self.image_view(image_use.image, instance_index))
+                    .collect();
+
+                let framebuffer_create_info = vk::FramebufferCreateInfo {
+                    s_type: vk::StructureType::FRAMEBUFFER_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::FramebufferCreateFlags::empty(),
+                    render_pass,
+                    attachment_count: views.len() as u32,
+                    p_attachments: views.as_ptr(),
+                    width: extent.width,
+                    height: extent.height,
+                    layers: 1,
+                };
+
+                let framebuffer = unsafe {
+                    device
+                        .create_framebuffer(&framebuffer_create_info, None)
+                        .expect("Failed to create Framebuffer!")
+                };
+                framebuffers.push(framebuffer);
+            }
+
+            let compiled = &mut self.compiled_passes[compiled_index];
+            compiled.render_pass = render_pass;
+            compiled.framebuffers = framebuffers;
+            compiled.clear_values = clear_values;
+            compiled.extent = extent;
+        }
+    }
+
+    /// Whether the content of `image` is still needed after the compiled pass at `compiled_index`.
+    fn is_read_after(&self, compiled_index: usize, image: ImageHandle) -> bool {
+        if self.images[image.0].imported.is_some() {
+            return true;
+        }
+
+        for compiled in self.compiled_passes[compiled_index + 1..].iter() {
+            for image_use in self.passes[compiled.pass].images.iter() {
+                if image_use.image != image {
+                    continue;
+                }
+
+                let is_overwritten = image_use.usage.is_attachment()
+                    && image_use.usage.is_write()
+                    && !matches!(image_use.load, AttachmentLoad::Load);
+                return !is_overwritten;
+            }
+        }
+
+        false
+    }
+
+    fn record_barriers(
+        &self,
+        device: &ash::Device,
+        command_buffer: vk::CommandBuffer,
+        instance_index: usize,
+        barriers: &Barriers,
+    ) {
+        if barriers.images.is_empty() && barriers.buffers.is_empty() {
+            return;
+        }
+
+        let mut src_stage_mask = vk::PipelineStageFlags::empty();
+        let mut dst_stage_mask = vk::PipelineStageFlags::empty();
+
+        let image_barriers: Vec<vk::ImageMemoryBarrier> = barriers
+            .images
+            .iter()
+            .map(|barrier| {
+                src_stage_mask |= barrier.old_state.stage_mask;
+                dst_stage_mask |= barrier.new_state.stage_mask;
+
+                vk::ImageMemoryBarrier {
+                    s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+                    p_next: ptr::null(),
+                    src_access_mask: barrier.old_state.access_mask,
+                    dst_access_mask: barrier.new_state.access_mask,
+                    old_layout: barrier.old_state.layout,
+                    new_layout: barrier.new_state.layout,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    image: self.image(barrier.image, instance_index),
+                    subresource_range: vk::ImageSubresourceRange {
+                        aspect_mask: share::image_aspect_mask(
+                            self.images[barrier.image.0].desc.format,
+                        ),
+                        base_mip_level: 0,
+                        level_count: vk::REMAINING_MIP_LEVELS,
+                        base_array_layer: 0,
+                        layer_count: vk::REMAINING_ARRAY_LAYERS,
+                    },
+                }
+            })
+            .collect();
+
+        let buffer_barriers: Vec<vk::BufferMemoryBarrier> = barriers
+            .buffers
+            .iter()
+            .map(|barrier| {
+                src_stage_mask |= barrier.src.0;
+                dst_stage_mask |= barrier.dst.0;
+
+                vk::BufferMemoryBarrier {
+                    s_type: vk::StructureType::BUFFER_MEMORY_BARRIER,
+                    p_next: ptr::null(),
+                    src_access_mask: barrier.src.1,
+                    dst_access_mask: barrier.dst.1,
+                    src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+                    buffer: self.buffer(barrier.buffer),
+                    offset: 0,
+                    size: vk::WHOLE_SIZE,
+                }
+            })
+            .collect();
+
+        unsafe {
+            device.cmd_pipeline_barrier(
+                command_buffer,
+                src_stage_mask,
+                dst_stage_mask,
+                vk::DependencyFlags::empty(),
+                &[],
+                &buffer_barriers,
+                &image_barriers,
+            );
+        }
+    }
+}
+
+impl Default for RenderGraph {
+    fn default() -> RenderGraph {
+        RenderGraph::new()
+    }
+}
+
+impl std::fmt::Debug for RenderGraph {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("RenderGraph")
+            .field(
+                "images",
+                &self
+                    .images
+                    .iter()
+                    .map(|image| &image.name)
+                    .collect::<Vec<_>>(),
+            )
+            .field(
+                "buffers",
+                &self
+                    .buffers
+                    .iter()
+                    .map(|buffer| &buffer.name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("execution_order", &self.execution_order())
+            .finish()
+    }
+}
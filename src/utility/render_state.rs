@@ -0,0 +1,293 @@
+//! Rasterization states that can be flipped at runtime, and a cache of the pipelines built for them.
+
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use ash::vk;
+
+use winit::event::VirtualKeyCode;
+
+use std::collections::HashMap;
+use std::ptr;
+
+use crate::utility::input::{self, ActionMap, Binding};
+
+pub const CYCLE_POLYGON_MODE: &str = "cycle_polygon_mode";
+pub const CYCLE_CULL_MODE: &str = "cycle_cull_mode";
+pub const TOGGLE_FRONT_FACE: &str = "toggle_front_face";
+
+/// Register the actions handled by `RasterizationMode::on_input`, bound to F1, F2 and F3.
+/// `toggle_wireframe` is a built-in action and needs no registration.
+pub fn register_actions(actions: &mut ActionMap) {
+    actions.register(CYCLE_POLYGON_MODE, &[Binding::key(VirtualKeyCode::F1)]);
+    actions.register(CYCLE_CULL_MODE, &[Binding::key(VirtualKeyCode::F2)]);
+    actions.register(TOGGLE_FRONT_FACE, &[Binding::key(VirtualKeyCode::F3)]);
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RasterizationMode {
+    pub polygon_mode: vk::PolygonMode,
+    pub cull_mode: vk::CullModeFlags,
+    pub front_face: vk::FrontFace,
+}
+
+impl RasterizationMode {
+    pub fn new(front_face: vk::FrontFace) -> RasterizationMode {
+        RasterizationMode {
+            polygon_mode: vk::PolygonMode::FILL,
+            cull_mode: vk::CullModeFlags::BACK,
+            front_face,
+        }
+    }
+
+    /// Switch between fill, line and point mode.
+    ///
+    /// Line and point mode require the `fillModeNonSolid` device feature, without it the mode stays at fill.
+    pub fn cycle_polygon_mode(&mut self, is_non_solid_supported: bool) {
+        self.polygon_mode = if !is_non_solid_supported {
+            vk::PolygonMode::FILL
+        } else {
+            match self.polygon_mode {
+                vk::PolygonMode::FILL => vk::PolygonMode::LINE,
+                vk::PolygonMode::LINE => vk::PolygonMode::POINT,
+                _ => vk::PolygonMode::FILL,
+            }
+        };
+    }
+
+    /// Switch between fill and line mode, line mode needs `fillModeNonSolid` as well.
+    pub fn toggle_wireframe(&mut self, is_non_solid_supported: bool) {
+        let is_fill = self.polygon_mode == vk::PolygonMode::FILL;
+        self.polygon_mode = if is_non_solid_supported && is_fill {
+            vk::PolygonMode::LINE
+        } else {
+            vk::PolygonMode::FILL
+        };
+    }
+
+    /// Switch between back, front, none and front-and-back face culling.
+    pub fn cycle_cull_mode(&mut self) {
+        self.cull_mode = match self.cull_mode {
+            vk::CullModeFlags::BACK => vk::CullModeFlags::FRONT,
+            vk::CullModeFlags::FRONT => vk::CullModeFlags::NONE,
+            vk::CullModeFlags::NONE => vk::CullModeFlags::FRONT_AND_BACK,
+            _ => vk::CullModeFlags::BACK,
+        };
+    }
+
+    pub fn toggle_front_face(&mut self) {
+        self.front_face = match self.front_face {
+            vk::FrontFace::COUNTER_CLOCKWISE => vk::FrontFace::CLOCKWISE,
+            _ => vk::FrontFace::COUNTER_CLOCKWISE,
+        };
+    }
+
+    /// Apply the rasterization actions pressed during this frame, and tell whether there were
+    /// any, in which case the pipeline has to be switched.
+    pub fn on_input(&mut self, actions: &ActionMap, is_non_solid_supported: bool) -> bool {
+        let is_polygon_mode_requested =
+            actions.is_pressed(CYCLE_POLYGON_MODE) || actions.is_pressed(input::TOGGLE_WIREFRAME);
+        if is_polygon_mode_requested && !is_non_solid_supported {
+            println!("fillModeNonSolid is not supported, keep polygon mode as FILL.");
+        }
+
+        let mut is_changed = false;
+        if actions.is_pressed(CYCLE_POLYGON_MODE) {
+            self.cycle_polygon_mode(is_non_solid_supported);
+            is_changed = true;
+        }
+        if actions.is_pressed(input::TOGGLE_WIREFRAME) {
+            self.toggle_wireframe(is_non_solid_supported);
+            is_changed = true;
+        }
+        if actions.is_pressed(CYCLE_CULL_MODE) {
+            self.cycle_cull_mode();
+            is_changed = true;
+        }
+        if actions.is_pressed(TOGGLE_FRONT_FACE) {
+            self.toggle_front_face();
+            is_changed = true;
+        }
+
+        is_changed
+    }
+
+    pub fn rasterization_state_create_info(&self) -> vk::PipelineRasterizationStateCreateInfo {
+        vk::PipelineRasterizationStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_RASTERIZATION_STATE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::PipelineRasterizationStateCreateFlags::empty(),
+            depth_clamp_enable: vk::FALSE,
+            cull_mode: self.cull_mode,
+            front_face: self.front_face,
+            line_width: 1.0,
+            polygon_mode: self.polygon_mode,
+            rasterizer_discard_enable: vk::FALSE,
+            depth_bias_clamp: 0.0,
+            depth_bias_constant_factor: 0.0,
+            depth_bias_enable: vk::FALSE,
+            depth_bias_slope_factor: 0.0,
+        }
+    }
+}
+
+/// Sample counts a chapter lets the user pick from, lowest first.
+pub const SELECTABLE_SAMPLE_COUNTS: [vk::SampleCountFlags; 4] = [
+    vk::SampleCountFlags::TYPE_1,
+    vk::SampleCountFlags::TYPE_2,
+    vk::SampleCountFlags::TYPE_4,
+    vk::SampleCountFlags::TYPE_8,
+];
+
+/// Fraction of the samples shaded individually when per-sample shading is on.
+pub const MIN_SAMPLE_SHADING: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MultisampleMode {
+    pub samples: vk::SampleCountFlags,
+    pub is_sample_shading_enabled: bool,
+}
+
+impl MultisampleMode {
+    pub fn new(samples: vk::SampleCountFlags) -> MultisampleMode {
+        MultisampleMode {
+            samples,
+            is_sample_shading_enabled: false,
+        }
+    }
+
+    /// Switch to the next selectable sample count the device supports, wrapping around to 1x.
+    pub fn cycle_samples(&mut self, supported_sample_counts: vk::SampleCountFlags) {
+        self.samples = SELECTABLE_SAMPLE_COUNTS
+            .iter()
+            .skip_while(|&&samples| samples != self.samples)
+            .skip(1)
+            .find(|&&samples| supported_sample_counts.contains(samples))
+            .cloned()
+            .unwrap_or(vk::SampleCountFlags::TYPE_1);
+    }
+
+    /// Per-sample shading requires the `sampleRateShading` device feature, without it the shading stays per pixel.
+    pub fn toggle_sample_shading(&mut self, is_sample_rate_shading_supported: bool) {
+        self.is_sample_shading_enabled =
+            is_sample_rate_shading_supported && !self.is_sample_shading_enabled;
+    }
+
+    pub fn multisample_state_create_info(&self) -> vk::PipelineMultisampleStateCreateInfo {
+        vk::PipelineMultisampleStateCreateInfo {
+            s_type: vk::StructureType::PIPELINE_MULTISAMPLE_STATE_CREATE_INFO,
+            flags: vk::PipelineMultisampleStateCreateFlags::empty(),
+            p_next: ptr::null(),
+            rasterization_samples: self.samples,
+            sample_shading_enable: if self.is_sample_shading_enabled {
+                vk::TRUE
+            } else {
+                vk::FALSE
+            },
+            min_sample_shading: MIN_SAMPLE_SHADING,
+            p_sample_mask: ptr::null(),
+            alpha_to_one_enable: vk::FALSE,
+            alpha_to_coverage_enable: vk::FALSE,
+        }
+    }
+}
+
+/// Graphics pipelines keyed by their rasterization mode, created on first use.
+///
+/// All variants share the same render pass and pipeline layout, so they must be dropped
+/// whenever the swapchain is recreated.
+pub struct PipelineVariants {
+    pipelines: HashMap<RasterizationMode, vk::Pipeline>,
+}
+
+impl PipelineVariants {
+    pub fn new() -> PipelineVariants {
+        PipelineVariants {
+            pipelines: HashMap::new(),
+        }
+    }
+
+    pub fn get_or_create<F>(&mut self, mode: RasterizationMode, create_pipeline: F) -> vk::Pipeline
+    where
+        F: FnOnce(&RasterizationMode) -> vk::Pipeline,
+    {
+        *self
+            .pipelines
+            .entry(mode)
+            .or_insert_with(|| create_pipeline(&mode))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    /// Destroy every cached pipeline, the cache itself is left untouched.
+    pub fn destroy_pipelines(&self, device: &ash::Device) {
+        for &pipeline in self.pipelines.values() {
+            unsafe {
+                device.destroy_pipeline(pipeline, None);
+            }
+        }
+    }
+
+    /// Forget every cached pipeline, call this after `destroy_pipelines`.
+    pub fn clear(&mut self) {
+        self.pipelines.clear();
+    }
+}
+
+impl Default for PipelineVariants {
+    fn default() -> PipelineVariants {
+        PipelineVariants::new()
+    }
+}
+
+pub fn is_fill_mode_non_solid_supported(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+) -> bool {
+    let device_features = unsafe { instance.get_physical_device_features(physical_device) };
+
+    device_features.fill_mode_non_solid == vk::TRUE
+}
+
+pub fn is_sample_rate_shading_supported(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+) -> bool {
+    let device_features = unsafe { instance.get_physical_device_features(physical_device) };
+
+    device_features.sample_rate_shading == vk::TRUE
+}
+
+/// Sample counts usable for both the color and the depth attachment.
+pub fn supported_sample_counts(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+) -> vk::SampleCountFlags {
+    let physical_device_properties =
+        unsafe { instance.get_physical_device_properties(physical_device) };
+
+    physical_device_properties
+        .limits
+        .framebuffer_color_sample_counts
+        & physical_device_properties
+            .limits
+            .framebuffer_depth_sample_counts
+}
+
+/// The highest selectable sample count not above `requested` that the device supports.
+pub fn clamp_sample_count(
+    requested: vk::SampleCountFlags,
+    supported_sample_counts: vk::SampleCountFlags,
+) -> vk::SampleCountFlags {
+    SELECTABLE_SAMPLE_COUNTS
+        .iter()
+        .rev()
+        .skip_while(|&&samples| samples.as_raw() > requested.as_raw())
+        .find(|&&samples| supported_sample_counts.contains(samples))
+        .cloned()
+        .unwrap_or(vk::SampleCountFlags::TYPE_1)
+}
@@ -0,0 +1,187 @@
+//! Save the content of a swapchain image to a PNG file.
+//!
+//! The image is copied after the frame is drawn and before it is presented, while the app still
+//! owns it. Swapchains are created with `TRANSFER_SRC` usage where the surface supports it, which
+//! is what the copy needs. Apps that take screenshots register the `screenshot` action and
+//! handle it themselves, as only they know when their swapchain image can be copied.
+
+use ash::version::DeviceV1_0;
+use ash::vk;
+use winit::event::VirtualKeyCode;
+
+use std::path::{Path, PathBuf};
+use std::ptr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::utility::input::{ActionMap, Binding};
+use crate::utility::share;
+use crate::utility::structures::SurfaceStuff;
+
+pub const SCREENSHOT: &str = "screenshot";
+
+/// Register the `screenshot` action, bound to F12.
+pub fn register_actions(actions: &mut ActionMap) {
+    actions.register(SCREENSHOT, &[Binding::key(VirtualKeyCode::F12)]);
+}
+
+/// Whether the swapchain images of the surface can be copied from.
+pub fn is_screenshot_supported(
+    physical_device: vk::PhysicalDevice,
+    surface_stuff: &SurfaceStuff,
+) -> bool {
+    let swapchain_support = share::query_swapchain_support(physical_device, surface_stuff);
+
+    swapchain_support
+        .capabilities
+        .supported_usage_flags
+        .contains(vk::ImageUsageFlags::TRANSFER_SRC)
+}
+
+/// A file name in the working directory that no earlier screenshot has taken.
+pub fn screenshot_path() -> PathBuf {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+
+    PathBuf::from(format!("screenshot-{}.png", since_epoch.as_millis()))
+}
+
+/// Copy `image`, a swapchain image drawn by a render pass that left it in `PRESENT_SRC_KHR`
+/// layout, and save it to `path`. The image goes back to `PRESENT_SRC_KHR` before this returns,
+/// so it can be presented as usual.
+#[allow(clippy::too_many_arguments)]
+pub fn save_swapchain_image(
+    device: &ash::Device,
+    command_pool: vk::CommandPool,
+    submit_queue: vk::Queue,
+    image: vk::Image,
+    format: vk::Format,
+    extent: vk::Extent2D,
+    device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+    path: &Path,
+) -> Result<(), String> {
+    let is_bgra = match format {
+        vk::Format::B8G8R8A8_UNORM | vk::Format::B8G8R8A8_SRGB => true,
+        vk::Format::R8G8B8A8_UNORM | vk::Format::R8G8B8A8_SRGB => false,
+        _ => return Err(format!("Unsupported swapchain format {:?}", format)),
+    };
+
+    let buffer_size = (extent.width * extent.height * 4) as vk::DeviceSize;
+    let (buffer, buffer_memory) = share::create_buffer(
+        device,
+        buffer_size,
+        vk::BufferUsageFlags::TRANSFER_DST,
+        vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT,
+        device_memory_properties,
+    );
+
+    let command_buffer = share::begin_single_time_command(device, command_pool);
+    let subresource_range = share::image_subresource_range(format, 0, 1, 0, 1);
+
+    // the render pass wrote the image and moved it to the present layout, wait for those writes.
+    let to_transfer_barriers = [vk::ImageMemoryBarrier {
+        s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+        p_next: ptr::null(),
+        src_access_mask: vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+        dst_access_mask: vk::AccessFlags::TRANSFER_READ,
+        old_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+        new_layout: vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        image,
+        subresource_range,
+    }];
+    let copy_regions = [vk::BufferImageCopy {
+        buffer_offset: 0,
+        buffer_row_length: 0,
+        buffer_image_height: 0,
+        image_subresource: vk::ImageSubresourceLayers {
+            aspect_mask: vk::ImageAspectFlags::COLOR,
+            mip_level: 0,
+            base_array_layer: 0,
+            layer_count: 1,
+        },
+        image_offset: vk::Offset3D { x: 0, y: 0, z: 0 },
+        image_extent: vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        },
+    }];
+    let to_host_barriers = [vk::BufferMemoryBarrier {
+        s_type: vk::StructureType::BUFFER_MEMORY_BARRIER,
+        p_next: ptr::null(),
+        src_access_mask: vk::AccessFlags::TRANSFER_WRITE,
+        dst_access_mask: vk::AccessFlags::HOST_READ,
+        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        buffer,
+        offset: 0,
+        size: vk::WHOLE_SIZE,
+    }];
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &to_transfer_barriers,
+        );
+        device.cmd_copy_image_to_buffer(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            buffer,
+            &copy_regions,
+        );
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::HOST,
+            vk::DependencyFlags::empty(),
+            &[],
+            &to_host_barriers,
+            &[],
+        );
+    }
+    share::cmd_transition_image_layout(
+        device,
+        command_buffer,
+        image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        vk::ImageLayout::PRESENT_SRC_KHR,
+        subresource_range,
+    );
+
+    share::end_single_time_command(device, command_pool, submit_queue, command_buffer);
+
+    let mut pixels = vec![0u8; buffer_size as usize];
+    unsafe {
+        let data_ptr = device
+            .map_memory(buffer_memory, 0, buffer_size, vk::MemoryMapFlags::empty())
+            .expect("Failed to Map Memory") as *const u8;
+        data_ptr.copy_to_nonoverlapping(pixels.as_mut_ptr(), pixels.len());
+        device.unmap_memory(buffer_memory);
+
+        device.destroy_buffer(buffer, None);
+        device.free_memory(buffer_memory, None);
+    }
+
+    if is_bgra {
+        for pixel in pixels.chunks_exact_mut(4) {
+            pixel.swap(0, 2);
+        }
+    }
+
+    image::save_buffer(
+        path,
+        &pixels,
+        extent.width,
+        extent.height,
+        image::ColorType::RGBA(8),
+    )
+    .map_err(|err| err.to_string())
+}
@@ -19,6 +19,7 @@ use crate::utility::constants::*;
 use crate::utility::debug;
 use crate::utility::platforms;
 use crate::utility::structures::*;
+use crate::utility::timeline;
 
 pub fn create_instance(
     entry: &ash::Entry,
@@ -48,7 +49,12 @@ pub fn create_instance(
     let debug_utils_create_info = debug::populate_debug_messenger_create_info();
 
     // VK_EXT debug report has been requested here.
-    let extension_names = platforms::required_extension_names();
+    let mut extension_names = platforms::required_extension_names();
+    // optional, required by device extensions like VK_KHR_timeline_semaphore on Vulkan 1.0.
+    let properties2_name = vk::KhrGetPhysicalDeviceProperties2Fn::name();
+    if is_properties2_available(entry) {
+        extension_names.push(properties2_name.as_ptr());
+    }
 
     let requred_validation_layer_raw_names: Vec<CString> = required_validation_layers
         .iter()
@@ -92,13 +98,27 @@ pub fn create_instance(
     instance
 }
 
+/// Whether `VK_KHR_get_physical_device_properties2` is available, `create_instance` enables it
+/// whenever it is.
+pub fn is_properties2_available(entry: &ash::Entry) -> bool {
+    let properties2_name = vk::KhrGetPhysicalDeviceProperties2Fn::name();
+
+    entry
+        .enumerate_instance_extension_properties()
+        .expect("Failed to get instance extension properties.")
+        .iter()
+        .any(|extension| {
+            super::tools::vk_to_string(&extension.extension_name)
+                == properties2_name.to_str().unwrap()
+        })
+}
+
 pub fn create_surface(
     entry: &ash::Entry,
     instance: &ash::Instance,
     window: &winit::window::Window,
-    screen_width: u32,
-    screen_height: u32,
 ) -> SurfaceStuff {
+    let window_size = window.inner_size();
     let surface = unsafe {
         platforms::create_surface(entry, instance, window).expect("Failed to create surface.")
     };
@@ -107,8 +127,8 @@ pub fn create_surface(
     SurfaceStuff {
         surface_loader,
         surface,
-        screen_width,
-        screen_height,
+        screen_width: window_size.width,
+        screen_height: window_size.height,
     }
 }
 
@@ -180,6 +200,46 @@ pub fn create_logical_device(
     device_extensions: &DeviceExtension,
     surface_stuff: &SurfaceStuff,
 ) -> (ash::Device, QueueFamilyIndices) {
+    let (device, indices, _is_timeline_semaphore_enabled) = create_device(
+        instance,
+        physical_device,
+        validation,
+        device_extensions,
+        surface_stuff,
+        false,
+    );
+
+    (device, indices)
+}
+
+/// Same as `create_logical_device`, also enabling timeline semaphores if the device supports
+/// them. The returned flag tells whether they are enabled.
+pub fn create_logical_device_with_timeline_semaphore(
+    entry: &ash::Entry,
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    validation: &super::debug::ValidationInfo,
+    device_extensions: &DeviceExtension,
+    surface_stuff: &SurfaceStuff,
+) -> (ash::Device, QueueFamilyIndices, bool) {
+    create_device(
+        instance,
+        physical_device,
+        validation,
+        device_extensions,
+        surface_stuff,
+        timeline::is_timeline_semaphore_supported(entry, instance, physical_device),
+    )
+}
+
+fn create_device(
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+    validation: &super::debug::ValidationInfo,
+    device_extensions: &DeviceExtension,
+    surface_stuff: &SurfaceStuff,
+    is_timeline_semaphore_enabled: bool,
+) -> (ash::Device, QueueFamilyIndices, bool) {
     let indices = find_queue_family(instance, physical_device, surface_stuff);
 
     use std::collections::HashSet;
@@ -201,8 +261,16 @@ pub fn create_logical_device(
         queue_create_infos.push(queue_create_info);
     }
 
+    let supported_features = unsafe { instance.get_physical_device_features(physical_device) };
     let physical_device_features = vk::PhysicalDeviceFeatures {
         sampler_anisotropy: vk::TRUE, // enable anisotropy device feature from Chapter-24.
+        // optional, allow line and point polygon mode when the device supports it.
+        fill_mode_non_solid: supported_features.fill_mode_non_solid,
+        // optional, allow per-sample shading when the device supports it.
+        sample_rate_shading: supported_features.sample_rate_shading,
+        // optional, allow pipeline statistics and exact sample counts in occlusion queries.
+        pipeline_statistics_query: supported_features.pipeline_statistics_query,
+        occlusion_query_precise: supported_features.occlusion_query_precise,
         ..Default::default()
     };
 
@@ -216,11 +284,24 @@ pub fn create_logical_device(
         .map(|layer_name| layer_name.as_ptr())
         .collect();
 
-    let enable_extension_names = device_extensions.get_extensions_raw_names();
+    let mut enable_extension_names = device_extensions.get_extensions_raw_names().to_vec();
+
+    let timeline_semaphore_extension_name =
+        CString::new(timeline::TIMELINE_SEMAPHORE_EXTENSION_NAME).unwrap();
+    let timeline_semaphore_features =
+        timeline::PhysicalDeviceTimelineSemaphoreFeatures::enabled();
+    if is_timeline_semaphore_enabled {
+        enable_extension_names.push(timeline_semaphore_extension_name.as_ptr());
+    }
 
     let device_create_info = vk::DeviceCreateInfo {
         s_type: vk::StructureType::DEVICE_CREATE_INFO,
-        p_next: ptr::null(),
+        p_next: if is_timeline_semaphore_enabled {
+            &timeline_semaphore_features as *const timeline::PhysicalDeviceTimelineSemaphoreFeatures
+                as *const c_void
+        } else {
+            ptr::null()
+        },
         flags: vk::DeviceCreateFlags::empty(),
         queue_create_info_count: queue_create_infos.len() as u32,
         p_queue_create_infos: queue_create_infos.as_ptr(),
@@ -245,7 +326,7 @@ pub fn create_logical_device(
             .expect("Failed to create logical Device!")
     };
 
-    (device, indices)
+    (device, indices, is_timeline_semaphore_enabled)
 }
 
 pub fn find_queue_family(
@@ -289,6 +370,24 @@ pub fn find_queue_family(
     queue_family_indices
 }
 
+/// Whether the queue family at `queue_family_index` can present to the surface. A device picked
+/// for one window has to pass this for every other window it presents to.
+pub fn is_present_supported(
+    physical_device: vk::PhysicalDevice,
+    queue_family_index: u32,
+    surface_stuff: &SurfaceStuff,
+) -> bool {
+    unsafe {
+        surface_stuff
+            .surface_loader
+            .get_physical_device_surface_support(
+                physical_device,
+                queue_family_index,
+                surface_stuff.surface,
+            )
+    }
+}
+
 pub fn check_device_extension_support(
     instance: &ash::Instance,
     physical_device: vk::PhysicalDevice,
@@ -347,6 +446,14 @@ pub fn query_swapchain_support(
     }
 }
 
+/// Whether `window` has no area to present to, as while it is minimized on some platforms. No
+/// swapchain can be created for it until it is restored.
+pub fn is_window_minimized(window: &winit::window::Window) -> bool {
+    let window_size = window.inner_size();
+    window_size.width == 0 || window_size.height == 0
+}
+
+/// Create a swapchain at the current size of `window`, which must not be minimized.
 pub fn create_swapchain(
     instance: &ash::Instance,
     device: &ash::Device,
@@ -360,6 +467,16 @@ pub fn create_swapchain(
     let surface_format = choose_swapchain_format(&swapchain_support.formats);
     let present_mode = choose_swapchain_present_mode(&swapchain_support.present_modes);
     let extent = choose_swapchain_extent(&swapchain_support.capabilities, window);
+    // screenshots copy from the swapchain images.
+    let image_usage = if swapchain_support
+        .capabilities
+        .supported_usage_flags
+        .contains(vk::ImageUsageFlags::TRANSFER_SRC)
+    {
+        vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSFER_SRC
+    } else {
+        vk::ImageUsageFlags::COLOR_ATTACHMENT
+    };
 
     let image_count = swapchain_support.capabilities.min_image_count + 1;
     let image_count = if swapchain_support.capabilities.max_image_count > 0 {
@@ -391,7 +508,7 @@ pub fn create_swapchain(
         image_color_space: surface_format.color_space,
         image_format: surface_format.format,
         image_extent: extent,
-        image_usage: vk::ImageUsageFlags::COLOR_ATTACHMENT,
+        image_usage,
         image_sharing_mode,
         p_queue_family_indices: queue_family_indices.as_ptr(),
         queue_family_index_count,
@@ -452,6 +569,8 @@ pub fn choose_swapchain_present_mode(
     vk::PresentModeKHR::FIFO
 }
 
+/// The size of the swapchain images in physical pixels. This is zero while the window is
+/// minimized on some platforms, and no swapchain can be created until it is restored.
 pub fn choose_swapchain_extent(
     capabilities: &vk::SurfaceCapabilitiesKHR,
     window: &winit::window::Window,
@@ -651,18 +770,160 @@ pub fn find_memory_type(
     required_properties: vk::MemoryPropertyFlags,
     mem_properties: &vk::PhysicalDeviceMemoryProperties,
 ) -> u32 {
-    for (i, memory_type) in mem_properties.memory_types.iter().enumerate() {
-        if (type_filter & (1 << i)) > 0 && memory_type.property_flags.contains(required_properties)
-        {
-            return i as u32;
-        }
-    }
+    try_find_memory_type(type_filter, required_properties, mem_properties)
+        .expect("Failed to find suitable memory type!")
+}
 
-    panic!("Failed to find suitable memory type!")
+/// Like `find_memory_type`, for properties the device may not offer at all.
+pub fn try_find_memory_type(
+    type_filter: u32,
+    required_properties: vk::MemoryPropertyFlags,
+    mem_properties: &vk::PhysicalDeviceMemoryProperties,
+) -> Option<u32> {
+    (0..mem_properties.memory_type_count).find(|&i| {
+        let memory_type = mem_properties.memory_types[i as usize];
+        (type_filter & (1 << i)) > 0 && memory_type.property_flags.contains(required_properties)
+    })
 }
 
 pub fn has_stencil_component(format: vk::Format) -> bool {
-    format == vk::Format::D32_SFLOAT_S8_UINT || format == vk::Format::D24_UNORM_S8_UINT
+    format == vk::Format::D32_SFLOAT_S8_UINT
+        || format == vk::Format::D24_UNORM_S8_UINT
+        || format == vk::Format::D16_UNORM_S8_UINT
+}
+
+pub fn has_depth_component(format: vk::Format) -> bool {
+    format == vk::Format::D16_UNORM
+        || format == vk::Format::X8_D24_UNORM_PACK32
+        || format == vk::Format::D32_SFLOAT
+        || has_stencil_component(format)
+}
+
+/// The aspects of an image with `format` that a barrier or a view covers.
+pub fn image_aspect_mask(format: vk::Format) -> vk::ImageAspectFlags {
+    if format == vk::Format::S8_UINT {
+        vk::ImageAspectFlags::STENCIL
+    } else if has_stencil_component(format) {
+        vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+    } else if has_depth_component(format) {
+        vk::ImageAspectFlags::DEPTH
+    } else {
+        vk::ImageAspectFlags::COLOR
+    }
+}
+
+pub fn image_subresource_range(
+    format: vk::Format,
+    base_mip_level: u32,
+    level_count: u32,
+    base_array_layer: u32,
+    layer_count: u32,
+) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange {
+        aspect_mask: image_aspect_mask(format),
+        base_mip_level,
+        level_count,
+        base_array_layer,
+        layer_count,
+    }
+}
+
+/// The accesses and pipeline stages an image in `layout` is used by.
+///
+/// The same pair works on both sides of a barrier: as the source it covers every access the
+/// old layout may still have in flight, as the destination every access the new layout allows.
+/// Layouts without a dedicated entry get the full memory barrier, which is slow but correct.
+pub fn layout_access_and_stage(
+    layout: vk::ImageLayout,
+) -> (vk::AccessFlags, vk::PipelineStageFlags) {
+    match layout {
+        vk::ImageLayout::UNDEFINED => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+        ),
+        vk::ImageLayout::PREINITIALIZED => {
+            (vk::AccessFlags::HOST_WRITE, vk::PipelineStageFlags::HOST)
+        }
+        vk::ImageLayout::GENERAL => (
+            vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+        ),
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL => (
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::VERTEX_SHADER | vk::PipelineStageFlags::FRAGMENT_SHADER,
+        ),
+        vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL => (
+            vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        ),
+        vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL => (
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+        ),
+        vk::ImageLayout::DEPTH_STENCIL_READ_ONLY_OPTIMAL => (
+            vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::FRAGMENT_SHADER,
+        ),
+        // presentation is synchronized by semaphores, the barrier only has to finish the layout change.
+        vk::ImageLayout::PRESENT_SRC_KHR => (
+            vk::AccessFlags::empty(),
+            vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        ),
+        _ => (
+            vk::AccessFlags::MEMORY_READ | vk::AccessFlags::MEMORY_WRITE,
+            vk::PipelineStageFlags::ALL_COMMANDS,
+        ),
+    }
+}
+
+/// Record a barrier moving `subresource_range` of `image` from `old_layout` to `new_layout`.
+pub fn cmd_transition_image_layout(
+    device: &ash::Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    subresource_range: vk::ImageSubresourceRange,
+) {
+    let (src_access_mask, source_stage) = layout_access_and_stage(old_layout);
+    let (dst_access_mask, destination_stage) = layout_access_and_stage(new_layout);
+
+    let image_barriers = [vk::ImageMemoryBarrier {
+        s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
+        p_next: ptr::null(),
+        src_access_mask,
+        dst_access_mask,
+        old_layout,
+        new_layout,
+        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
+        image,
+        subresource_range,
+    }];
+
+    unsafe {
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            source_stage,
+            destination_stage,
+            vk::DependencyFlags::empty(),
+            &[],
+            &[],
+            &image_barriers,
+        );
+    }
 }
 
 pub fn copy_buffer_to_image(
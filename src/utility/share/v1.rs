@@ -771,74 +771,42 @@ pub fn transition_image_layout(
     command_pool: vk::CommandPool,
     submit_queue: vk::Queue,
     image: vk::Image,
-    _format: vk::Format,
+    format: vk::Format,
     old_layout: vk::ImageLayout,
     new_layout: vk::ImageLayout,
     mip_levels: u32,
 ) {
-    let command_buffer = begin_single_time_command(device, command_pool);
+    transition_image_subresource_layout(
+        device,
+        command_pool,
+        submit_queue,
+        image,
+        old_layout,
+        new_layout,
+        image_subresource_range(format, 0, mip_levels, 0, 1),
+    );
+}
 
-    let src_access_mask;
-    let dst_access_mask;
-    let source_stage;
-    let destination_stage;
-
-    if old_layout == vk::ImageLayout::UNDEFINED
-        && new_layout == vk::ImageLayout::TRANSFER_DST_OPTIMAL
-    {
-        src_access_mask = vk::AccessFlags::empty();
-        dst_access_mask = vk::AccessFlags::TRANSFER_WRITE;
-        source_stage = vk::PipelineStageFlags::TOP_OF_PIPE;
-        destination_stage = vk::PipelineStageFlags::TRANSFER;
-    } else if old_layout == vk::ImageLayout::TRANSFER_DST_OPTIMAL
-        && new_layout == vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
-    {
-        src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
-        dst_access_mask = vk::AccessFlags::SHADER_READ;
-        source_stage = vk::PipelineStageFlags::TRANSFER;
-        destination_stage = vk::PipelineStageFlags::FRAGMENT_SHADER;
-    } else if old_layout == vk::ImageLayout::UNDEFINED
-        && new_layout == vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL
-    {
-        src_access_mask = vk::AccessFlags::empty();
-        dst_access_mask =
-            vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE;
-        source_stage = vk::PipelineStageFlags::TOP_OF_PIPE;
-        destination_stage = vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT;
-    } else {
-        panic!("Unsupported layout transition!")
-    }
+/// Like `transition_image_layout`, for any range of mip levels and array layers.
+pub fn transition_image_subresource_layout(
+    device: &ash::Device,
+    command_pool: vk::CommandPool,
+    submit_queue: vk::Queue,
+    image: vk::Image,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    subresource_range: vk::ImageSubresourceRange,
+) {
+    let command_buffer = begin_single_time_command(device, command_pool);
 
-    let image_barriers = [vk::ImageMemoryBarrier {
-        s_type: vk::StructureType::IMAGE_MEMORY_BARRIER,
-        p_next: ptr::null(),
-        src_access_mask,
-        dst_access_mask,
+    cmd_transition_image_layout(
+        device,
+        command_buffer,
+        image,
         old_layout,
         new_layout,
-        src_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-        dst_queue_family_index: vk::QUEUE_FAMILY_IGNORED,
-        image,
-        subresource_range: vk::ImageSubresourceRange {
-            aspect_mask: vk::ImageAspectFlags::COLOR,
-            base_mip_level: 0,
-            level_count: mip_levels,
-            base_array_layer: 0,
-            layer_count: 1,
-        },
-    }];
-
-    unsafe {
-        device.cmd_pipeline_barrier(
-            command_buffer,
-            source_stage,
-            destination_stage,
-            vk::DependencyFlags::empty(),
-            &[],
-            &[],
-            &image_barriers,
-        );
-    }
+        subresource_range,
+    );
 
     end_single_time_command(device, command_pool, submit_queue, command_buffer);
 }
@@ -943,6 +911,37 @@ pub fn create_texture_sampler(device: &ash::Device) -> vk::Sampler {
     }
 }
 
+/// Sampler for shadow maps: `texture()` on it returns the result of the depth comparison.
+pub fn create_shadow_sampler(device: &ash::Device, filter: vk::Filter) -> vk::Sampler {
+    let sampler_create_info = vk::SamplerCreateInfo {
+        s_type: vk::StructureType::SAMPLER_CREATE_INFO,
+        p_next: ptr::null(),
+        flags: vk::SamplerCreateFlags::empty(),
+        mag_filter: filter,
+        min_filter: filter,
+        address_mode_u: vk::SamplerAddressMode::CLAMP_TO_BORDER,
+        address_mode_v: vk::SamplerAddressMode::CLAMP_TO_BORDER,
+        address_mode_w: vk::SamplerAddressMode::CLAMP_TO_BORDER,
+        max_anisotropy: 1.0,
+        compare_enable: vk::TRUE,
+        compare_op: vk::CompareOp::LESS_OR_EQUAL,
+        mipmap_mode: vk::SamplerMipmapMode::NEAREST,
+        min_lod: 0.0,
+        max_lod: 0.0,
+        mip_lod_bias: 0.0,
+        // everything outside of the shadow map is lit.
+        border_color: vk::BorderColor::FLOAT_OPAQUE_WHITE,
+        anisotropy_enable: vk::FALSE,
+        unnormalized_coordinates: vk::FALSE,
+    };
+
+    unsafe {
+        device
+            .create_sampler(&sampler_create_info, None)
+            .expect("Failed to create Sampler!")
+    }
+}
+
 pub fn create_texture_image(
     device: &ash::Device,
     command_pool: vk::CommandPool,
@@ -1078,6 +1077,117 @@ pub fn create_depth_resources(
     (depth_image, depth_image_view, depth_image_memory)
 }
 
+/// Create an attachment that is only ever cleared and rendered to inside a render pass.
+/// It prefers `LAZILY_ALLOCATED` memory and falls back to plain `DEVICE_LOCAL` memory.
+pub fn create_transient_attachment(
+    device: &ash::Device,
+    extent: vk::Extent2D,
+    num_samples: vk::SampleCountFlags,
+    format: vk::Format,
+    usage: vk::ImageUsageFlags,
+    aspect_flags: vk::ImageAspectFlags,
+    device_memory_properties: &vk::PhysicalDeviceMemoryProperties,
+) -> TransientAttachment {
+    let image_create_info = vk::ImageCreateInfo {
+        s_type: vk::StructureType::IMAGE_CREATE_INFO,
+        p_next: ptr::null(),
+        flags: vk::ImageCreateFlags::empty(),
+        image_type: vk::ImageType::TYPE_2D,
+        format,
+        mip_levels: 1,
+        array_layers: 1,
+        samples: num_samples,
+        tiling: vk::ImageTiling::OPTIMAL,
+        usage: usage | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+        sharing_mode: vk::SharingMode::EXCLUSIVE,
+        queue_family_index_count: 0,
+        p_queue_family_indices: ptr::null(),
+        initial_layout: vk::ImageLayout::UNDEFINED,
+        extent: vk::Extent3D {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        },
+    };
+
+    let image = unsafe {
+        device
+            .create_image(&image_create_info, None)
+            .expect("Failed to create Attachment Image!")
+    };
+
+    let image_memory_requirement = unsafe { device.get_image_memory_requirements(image) };
+    let lazy_memory_type = try_find_memory_type(
+        image_memory_requirement.memory_type_bits,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::LAZILY_ALLOCATED,
+        device_memory_properties,
+    );
+    let memory_type_index = lazy_memory_type.unwrap_or_else(|| {
+        find_memory_type(
+            image_memory_requirement.memory_type_bits,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            device_memory_properties,
+        )
+    });
+
+    let memory_allocate_info = vk::MemoryAllocateInfo {
+        s_type: vk::StructureType::MEMORY_ALLOCATE_INFO,
+        p_next: ptr::null(),
+        allocation_size: image_memory_requirement.size,
+        memory_type_index,
+    };
+
+    let memory = unsafe {
+        device
+            .allocate_memory(&memory_allocate_info, None)
+            .expect("Failed to allocate Attachment Image memory!")
+    };
+
+    unsafe {
+        device
+            .bind_image_memory(image, memory, 0)
+            .expect("Failed to bind Image Memmory!");
+    }
+
+    let image_view = create_image_view(device, image, format, aspect_flags, 1);
+
+    TransientAttachment {
+        image,
+        image_view,
+        memory,
+        allocation_size: image_memory_requirement.size,
+        is_lazily_allocated: lazy_memory_type.is_some(),
+    }
+}
+
+/// Bytes of a transient attachment the driver has actually backed with memory so far.
+pub fn transient_attachment_committed_size(
+    device: &ash::Device,
+    attachment: &TransientAttachment,
+) -> vk::DeviceSize {
+    if attachment.is_lazily_allocated {
+        let mut committed_size: vk::DeviceSize = 0;
+        unsafe {
+            device.fp_v1_0().get_device_memory_commitment(
+                device.handle(),
+                attachment.memory,
+                &mut committed_size,
+            );
+        }
+        committed_size
+    } else {
+        attachment.allocation_size
+    }
+}
+
+pub fn destroy_transient_attachment(device: &ash::Device, attachment: &TransientAttachment) {
+    unsafe {
+        device.destroy_image_view(attachment.image_view, None);
+        device.destroy_image(attachment.image, None);
+        device.free_memory(attachment.memory, None);
+    }
+}
+
 pub fn generate_mipmaps(
     device: &ash::Device,
     command_pool: vk::CommandPool,
@@ -0,0 +1,281 @@
+//! Typed specialization constants for shader stage creation.
+//!
+//! One SPIR-V module can declare constants with `layout(constant_id = N)`, and each pipeline
+//! fills them in at creation time through `vk::SpecializationInfo`. The constants collected here
+//! can be checked against the ids and types actually declared in the module before use.
+
+use ash::vk;
+
+use std::ffi::CStr;
+use std::fmt;
+use std::mem::size_of;
+use std::ptr;
+
+// SPIR-V opcodes and decorations used to reflect the declared specialization constants.
+const SPIRV_MAGIC_NUMBER: u32 = 0x0723_0203;
+const SPIRV_HEADER_WORDS: usize = 5;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_DECORATE: u32 = 71;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const DECORATION_SPEC_ID: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpecConstantType {
+    Bool,
+    Int,
+    UInt,
+    Float,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpecConstantDecl {
+    pub constant_id: u32,
+    pub constant_type: SpecConstantType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecializationError {
+    InvalidSpirv,
+    UnknownConstantId(u32),
+    TypeMismatch {
+        constant_id: u32,
+        declared: SpecConstantType,
+        provided: SpecConstantType,
+    },
+}
+
+impl fmt::Display for SpecializationError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpecializationError::InvalidSpirv => write!(f, "Shader code is not valid SPIR-V"),
+            SpecializationError::UnknownConstantId(constant_id) => write!(
+                f,
+                "Specialization constant {} is not declared in shader module",
+                constant_id
+            ),
+            SpecializationError::TypeMismatch {
+                constant_id,
+                declared,
+                provided,
+            } => write!(
+                f,
+                "Specialization constant {} is declared as {:?}, but {:?} was provided",
+                constant_id, declared, provided
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SpecializationError {}
+
+/// The constants of one shader stage, packed the way `vk::SpecializationInfo` expects them.
+///
+/// Every value occupies 4 bytes, booleans are stored as `vk::Bool32`.
+#[derive(Debug, Clone, Default)]
+pub struct SpecializationConstants {
+    entries: Vec<vk::SpecializationMapEntry>,
+    types: Vec<SpecConstantType>,
+    data: Vec<u8>,
+}
+
+impl SpecializationConstants {
+    pub fn new() -> SpecializationConstants {
+        SpecializationConstants::default()
+    }
+
+    pub fn add_bool(&mut self, constant_id: u32, value: bool) -> &mut Self {
+        let value = if value { vk::TRUE } else { vk::FALSE };
+        self.push(constant_id, SpecConstantType::Bool, value.to_ne_bytes())
+    }
+
+    pub fn add_int(&mut self, constant_id: u32, value: i32) -> &mut Self {
+        self.push(constant_id, SpecConstantType::Int, value.to_ne_bytes())
+    }
+
+    pub fn add_uint(&mut self, constant_id: u32, value: u32) -> &mut Self {
+        self.push(constant_id, SpecConstantType::UInt, value.to_ne_bytes())
+    }
+
+    pub fn add_float(&mut self, constant_id: u32, value: f32) -> &mut Self {
+        self.push(
+            constant_id,
+            SpecConstantType::Float,
+            value.to_bits().to_ne_bytes(),
+        )
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Build the info struct passed to `p_specialization_info`.
+    ///
+    /// The returned struct points into `self`, so `self` must outlive the pipeline creation call.
+    pub fn info(&self) -> vk::SpecializationInfo {
+        vk::SpecializationInfo {
+            map_entry_count: self.entries.len() as u32,
+            p_map_entries: self.entries.as_ptr(),
+            data_size: self.data.len(),
+            p_data: self.data.as_ptr() as *const std::os::raw::c_void,
+        }
+    }
+
+    /// Check that every constant set here is declared in `code` with the same type.
+    pub fn check_against(&self, code: &[u8]) -> Result<(), SpecializationError> {
+        let declared_constants = reflect_spec_constants(code)?;
+
+        for (entry, &provided) in self.entries.iter().zip(self.types.iter()) {
+            let declared = declared_constants
+                .iter()
+                .find(|decl| decl.constant_id == entry.constant_id)
+                .ok_or(SpecializationError::UnknownConstantId(entry.constant_id))?;
+
+            // signedness is not part of the data layout, so int and uint are interchangeable.
+            let is_compatible = match (declared.constant_type, provided) {
+                (SpecConstantType::Int, SpecConstantType::UInt)
+                | (SpecConstantType::UInt, SpecConstantType::Int) => true,
+                (declared, provided) => declared == provided,
+            };
+            if !is_compatible {
+                return Err(SpecializationError::TypeMismatch {
+                    constant_id: entry.constant_id,
+                    declared: declared.constant_type,
+                    provided,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn push(
+        &mut self,
+        constant_id: u32,
+        constant_type: SpecConstantType,
+        bytes: [u8; 4],
+    ) -> &mut Self {
+        // setting the same id twice overrides the previous value.
+        if let Some(index) = self
+            .entries
+            .iter()
+            .position(|entry| entry.constant_id == constant_id)
+        {
+            let offset = self.entries[index].offset as usize;
+            self.data[offset..offset + bytes.len()].copy_from_slice(&bytes);
+            self.types[index] = constant_type;
+        } else {
+            self.entries.push(vk::SpecializationMapEntry {
+                constant_id,
+                offset: self.data.len() as u32,
+                size: size_of::<u32>(),
+            });
+            self.types.push(constant_type);
+            self.data.extend_from_slice(&bytes);
+        }
+
+        self
+    }
+}
+
+/// List the specialization constants declared in a SPIR-V module.
+pub fn reflect_spec_constants(code: &[u8]) -> Result<Vec<SpecConstantDecl>, SpecializationError> {
+    let chunks = code.chunks_exact(4);
+    if !chunks.remainder().is_empty() || code.len() < SPIRV_HEADER_WORDS * 4 {
+        return Err(SpecializationError::InvalidSpirv);
+    }
+
+    let mut words: Vec<u32> = chunks
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .collect();
+    if words[0] != SPIRV_MAGIC_NUMBER {
+        if words[0].swap_bytes() != SPIRV_MAGIC_NUMBER {
+            return Err(SpecializationError::InvalidSpirv);
+        }
+        words.iter_mut().for_each(|word| *word = word.swap_bytes());
+    }
+
+    let mut spec_ids = vec![]; // (result id, constant id)
+    let mut types = vec![]; // (type id, type)
+    let mut constants = vec![]; // (result id, type id)
+
+    let mut cursor = SPIRV_HEADER_WORDS;
+    while cursor < words.len() {
+        let word_count = (words[cursor] >> 16) as usize;
+        let opcode = words[cursor] & 0xFFFF;
+        if word_count == 0 || cursor + word_count > words.len() {
+            return Err(SpecializationError::InvalidSpirv);
+        }
+        let operands = &words[cursor + 1..cursor + word_count];
+
+        match opcode {
+            OP_DECORATE if operands.len() >= 3 && operands[1] == DECORATION_SPEC_ID => {
+                spec_ids.push((operands[0], operands[2]));
+            }
+            OP_TYPE_BOOL if !operands.is_empty() => {
+                types.push((operands[0], SpecConstantType::Bool));
+            }
+            OP_TYPE_INT if operands.len() >= 3 => {
+                let int_type = if operands[2] == 1 {
+                    SpecConstantType::Int
+                } else {
+                    SpecConstantType::UInt
+                };
+                types.push((operands[0], int_type));
+            }
+            OP_TYPE_FLOAT if !operands.is_empty() => {
+                types.push((operands[0], SpecConstantType::Float));
+            }
+            OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_SPEC_CONSTANT
+                if operands.len() >= 2 =>
+            {
+                constants.push((operands[1], operands[0]));
+            }
+            _ => {}
+        }
+
+        cursor += word_count;
+    }
+
+    let mut declared_constants = vec![];
+    for &(result_id, constant_id) in spec_ids.iter() {
+        let type_id = match constants.iter().find(|(id, _)| *id == result_id) {
+            Some(&(_, type_id)) => type_id,
+            None => continue, // SpecId on something that is not a scalar constant.
+        };
+        if let Some(&(_, constant_type)) = types.iter().find(|(id, _)| *id == type_id) {
+            declared_constants.push(SpecConstantDecl {
+                constant_id,
+                constant_type,
+            });
+        }
+    }
+
+    Ok(declared_constants)
+}
+
+/// Fill a shader stage create info, optionally specialized.
+///
+/// `specialization_info` is referenced by pointer and must outlive the pipeline creation call.
+pub fn shader_stage_create_info(
+    module: vk::ShaderModule,
+    stage: vk::ShaderStageFlags,
+    entry_name: &CStr,
+    specialization_info: Option<&vk::SpecializationInfo>,
+) -> vk::PipelineShaderStageCreateInfo {
+    vk::PipelineShaderStageCreateInfo {
+        s_type: vk::StructureType::PIPELINE_SHADER_STAGE_CREATE_INFO,
+        p_next: ptr::null(),
+        flags: vk::PipelineShaderStageCreateFlags::empty(),
+        module,
+        p_name: entry_name.as_ptr(),
+        p_specialization_info: match specialization_info {
+            Some(info) => info,
+            None => ptr::null(),
+        },
+        stage,
+    }
+}
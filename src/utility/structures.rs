@@ -12,6 +12,7 @@ pub struct SurfaceStuff {
     pub surface_loader: ash::extensions::khr::Surface,
     pub surface: vk::SurfaceKHR,
 
+    // the size of the window in physical pixels, zero while it is minimized.
     pub screen_width: u32,
     pub screen_height: u32,
 }
@@ -53,6 +54,16 @@ pub struct SyncObjects {
     pub inflight_fences: Vec<vk::Fence>,
 }
 
+/// An attachment whose content never leaves the render pass, like MSAA color or depth.
+pub struct TransientAttachment {
+    pub image: vk::Image,
+    pub image_view: vk::ImageView,
+    pub memory: vk::DeviceMemory,
+    pub allocation_size: vk::DeviceSize,
+    /// Backed by `LAZILY_ALLOCATED` memory, which tiled GPUs may never commit.
+    pub is_lazily_allocated: bool,
+}
+
 #[repr(C)]
 #[derive(Clone, Debug, Copy)]
 pub struct UniformBufferObject {
@@ -0,0 +1,525 @@
+//! Frame and upload synchronization on a timeline semaphore, with binary semaphores and fences
+//! as fallback.
+//!
+//! A timeline semaphore holds a 64 bit value that only goes up. Every submission to the queue
+//! signals the next value, so waiting for a frame or an upload is waiting for the value its
+//! submission signaled, and one semaphore replaces the fence of every frame in flight.
+//!
+//! Swapchain acquire and present only accept binary semaphores, so those stay in both paths.
+//!
+//! `VK_KHR_timeline_semaphore` is newer than the ash version used here, so its structures and
+//! functions are declared below.
+
+use ash::version::{DeviceV1_0, InstanceV1_0};
+use ash::vk;
+use ash::vk_make_version;
+
+use std::ffi::CString;
+use std::os::raw::c_void;
+use std::ptr;
+
+use crate::utility::constants::API_VERSION;
+use crate::utility::share;
+
+pub const TIMELINE_SEMAPHORE_EXTENSION_NAME: &str = "VK_KHR_timeline_semaphore";
+
+// VK_KHR_timeline_semaphore ------------------------------------------------------------------
+const STRUCTURE_TYPE_PHYSICAL_DEVICE_TIMELINE_SEMAPHORE_FEATURES: i32 = 1_000_207_000;
+const STRUCTURE_TYPE_SEMAPHORE_TYPE_CREATE_INFO: i32 = 1_000_207_002;
+const STRUCTURE_TYPE_TIMELINE_SEMAPHORE_SUBMIT_INFO: i32 = 1_000_207_003;
+const STRUCTURE_TYPE_SEMAPHORE_WAIT_INFO: i32 = 1_000_207_004;
+const SEMAPHORE_TYPE_TIMELINE: i32 = 1;
+
+#[repr(C)]
+pub struct PhysicalDeviceTimelineSemaphoreFeatures {
+    pub s_type: vk::StructureType,
+    pub p_next: *mut c_void,
+    pub timeline_semaphore: vk::Bool32,
+}
+
+impl PhysicalDeviceTimelineSemaphoreFeatures {
+    /// The feature structure to chain into `vk::DeviceCreateInfo` to enable timeline semaphores.
+    pub fn enabled() -> PhysicalDeviceTimelineSemaphoreFeatures {
+        PhysicalDeviceTimelineSemaphoreFeatures {
+            s_type: vk::StructureType::from_raw(
+                STRUCTURE_TYPE_PHYSICAL_DEVICE_TIMELINE_SEMAPHORE_FEATURES,
+            ),
+            p_next: ptr::null_mut(),
+            timeline_semaphore: vk::TRUE,
+        }
+    }
+}
+
+#[repr(C)]
+struct SemaphoreTypeCreateInfo {
+    s_type: vk::StructureType,
+    p_next: *const c_void,
+    semaphore_type: i32,
+    initial_value: u64,
+}
+
+#[repr(C)]
+struct TimelineSemaphoreSubmitInfo {
+    s_type: vk::StructureType,
+    p_next: *const c_void,
+    wait_semaphore_value_count: u32,
+    p_wait_semaphore_values: *const u64,
+    signal_semaphore_value_count: u32,
+    p_signal_semaphore_values: *const u64,
+}
+
+#[repr(C)]
+struct SemaphoreWaitInfo {
+    s_type: vk::StructureType,
+    p_next: *const c_void,
+    flags: u32,
+    semaphore_count: u32,
+    p_semaphores: *const vk::Semaphore,
+    p_values: *const u64,
+}
+
+type GetSemaphoreCounterValue =
+    unsafe extern "system" fn(vk::Device, vk::Semaphore, *mut u64) -> vk::Result;
+type WaitSemaphores =
+    unsafe extern "system" fn(vk::Device, *const SemaphoreWaitInfo, u64) -> vk::Result;
+
+#[derive(Clone, Copy)]
+struct TimelineSemaphoreFn {
+    get_semaphore_counter_value: GetSemaphoreCounterValue,
+    wait_semaphores: WaitSemaphores,
+}
+
+impl TimelineSemaphoreFn {
+    fn load(instance: &ash::Instance, device: &ash::Device) -> TimelineSemaphoreFn {
+        let load_function = |name: &str| {
+            let name = CString::new(name).unwrap();
+            unsafe { instance.get_device_proc_addr(device.handle(), name.as_ptr()) }
+                .expect("Failed to load timeline semaphore functions!")
+        };
+
+        unsafe {
+            TimelineSemaphoreFn {
+                get_semaphore_counter_value: std::mem::transmute::<
+                    unsafe extern "system" fn() -> c_void,
+                    GetSemaphoreCounterValue,
+                >(load_function(
+                    "vkGetSemaphoreCounterValueKHR",
+                )),
+                wait_semaphores: std::mem::transmute::<
+                    unsafe extern "system" fn() -> c_void,
+                    WaitSemaphores,
+                >(load_function("vkWaitSemaphoresKHR")),
+            }
+        }
+    }
+}
+// --------------------------------------------------------------------------------------------
+
+/// Check if the device can run with timeline semaphores, on an instance from
+/// `share::create_instance`.
+pub fn is_timeline_semaphore_supported(
+    entry: &ash::Entry,
+    instance: &ash::Instance,
+    physical_device: vk::PhysicalDevice,
+) -> bool {
+    // on Vulkan 1.0 the extension depends on VK_KHR_get_physical_device_properties2, which the
+    // instance has enabled if it is available.
+    if API_VERSION < vk_make_version!(1, 1, 0) && !share::is_properties2_available(entry) {
+        return false;
+    }
+
+    let available_extensions = unsafe {
+        instance
+            .enumerate_device_extension_properties(physical_device)
+            .expect("Failed to get device extension properties.")
+    };
+
+    // the extension requires the timelineSemaphore feature.
+    available_extensions.iter().any(|extension| {
+        super::tools::vk_to_string(&extension.extension_name) == TIMELINE_SEMAPHORE_EXTENSION_NAME
+    })
+}
+
+/// The timeline semaphore of a queue, every submission to the queue signals the next value.
+pub struct QueueTimeline {
+    semaphore: vk::Semaphore,
+    last_value: u64,
+    fns: TimelineSemaphoreFn,
+}
+
+impl QueueTimeline {
+    /// The device must have been created with timeline semaphores enabled.
+    pub fn new(instance: &ash::Instance, device: &ash::Device) -> QueueTimeline {
+        let semaphore_type_create_info = SemaphoreTypeCreateInfo {
+            s_type: vk::StructureType::from_raw(STRUCTURE_TYPE_SEMAPHORE_TYPE_CREATE_INFO),
+            p_next: ptr::null(),
+            semaphore_type: SEMAPHORE_TYPE_TIMELINE,
+            initial_value: 0,
+        };
+        let semaphore_create_info = vk::SemaphoreCreateInfo {
+            s_type: vk::StructureType::SEMAPHORE_CREATE_INFO,
+            p_next: &semaphore_type_create_info as *const SemaphoreTypeCreateInfo as *const c_void,
+            flags: vk::SemaphoreCreateFlags::empty(),
+        };
+
+        let semaphore = unsafe {
+            device
+                .create_semaphore(&semaphore_create_info, None)
+                .expect("Failed to create Semaphore Object!")
+        };
+
+        QueueTimeline {
+            semaphore,
+            last_value: 0,
+            fns: TimelineSemaphoreFn::load(instance, device),
+        }
+    }
+
+    /// Submit `command_buffers`, waiting on the binary `wait_semaphores` and signaling the
+    /// binary `signal_semaphores` along with the next timeline value, which is returned.
+    pub fn submit(
+        &mut self,
+        device: &ash::Device,
+        queue: vk::Queue,
+        command_buffers: &[vk::CommandBuffer],
+        wait_semaphores: &[(vk::Semaphore, vk::PipelineStageFlags)],
+        signal_semaphores: &[vk::Semaphore],
+    ) -> u64 {
+        self.last_value += 1;
+
+        let (wait_semaphore_handles, wait_stages): (
+            Vec<vk::Semaphore>,
+            Vec<vk::PipelineStageFlags>,
+        ) = wait_semaphores.iter().cloned().unzip();
+        // values of binary semaphores are ignored, but every semaphore needs one.
+        let wait_values = vec![0_u64; wait_semaphores.len()];
+
+        let mut signal_semaphore_handles = signal_semaphores.to_vec();
+        signal_semaphore_handles.push(self.semaphore);
+        let mut signal_values = vec![0_u64; signal_semaphores.len()];
+        signal_values.push(self.last_value);
+
+        let timeline_submit_info = TimelineSemaphoreSubmitInfo {
+            s_type: vk::StructureType::from_raw(STRUCTURE_TYPE_TIMELINE_SEMAPHORE_SUBMIT_INFO),
+            p_next: ptr::null(),
+            wait_semaphore_value_count: wait_values.len() as u32,
+            p_wait_semaphore_values: wait_values.as_ptr(),
+            signal_semaphore_value_count: signal_values.len() as u32,
+            p_signal_semaphore_values: signal_values.as_ptr(),
+        };
+
+        let submit_infos = [vk::SubmitInfo {
+            s_type: vk::StructureType::SUBMIT_INFO,
+            p_next: &timeline_submit_info as *const TimelineSemaphoreSubmitInfo as *const c_void,
+            wait_semaphore_count: wait_semaphore_handles.len() as u32,
+            p_wait_semaphores: wait_semaphore_handles.as_ptr(),
+            p_wait_dst_stage_mask: wait_stages.as_ptr(),
+            command_buffer_count: command_buffers.len() as u32,
+            p_command_buffers: command_buffers.as_ptr(),
+            signal_semaphore_count: signal_semaphore_handles.len() as u32,
+            p_signal_semaphores: signal_semaphore_handles.as_ptr(),
+        }];
+
+        unsafe {
+            device
+                .queue_submit(queue, &submit_infos, vk::Fence::null())
+                .expect("Failed to execute queue submit.");
+        }
+
+        self.last_value
+    }
+
+    /// The highest value the GPU has signaled so far.
+    pub fn completed_value(&self, device: &ash::Device) -> u64 {
+        let mut value = 0;
+        let result = unsafe {
+            (self.fns.get_semaphore_counter_value)(device.handle(), self.semaphore, &mut value)
+        };
+        assert_eq!(
+            result,
+            vk::Result::SUCCESS,
+            "Failed to get Semaphore value!"
+        );
+
+        value
+    }
+
+    /// Block until the GPU has signaled `value`.
+    pub fn wait(&self, device: &ash::Device, value: u64) {
+        let wait_info = SemaphoreWaitInfo {
+            s_type: vk::StructureType::from_raw(STRUCTURE_TYPE_SEMAPHORE_WAIT_INFO),
+            p_next: ptr::null(),
+            flags: 0,
+            semaphore_count: 1,
+            p_semaphores: &self.semaphore,
+            p_values: &value,
+        };
+
+        let result = unsafe { (self.fns.wait_semaphores)(device.handle(), &wait_info, u64::MAX) };
+        assert_eq!(result, vk::Result::SUCCESS, "Failed to wait for Semaphore!");
+    }
+
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_semaphore(self.semaphore, None);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncKind {
+    /// One fence per frame in flight.
+    Binary,
+    /// One timeline semaphore for the queue.
+    Timeline,
+}
+
+/// Marks an upload submitted with `FrameSync::submit_upload`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadTicket(u64);
+
+enum FrameFences {
+    Binary(Vec<vk::Fence>),
+    Timeline {
+        timeline: QueueTimeline,
+        // the value signaled by the last submission of each frame in flight.
+        frame_values: Vec<u64>,
+    },
+}
+
+/// The synchronization of the frames in flight and the uploads on one queue.
+pub struct FrameSync {
+    image_available_semaphores: Vec<vk::Semaphore>,
+    render_finished_semaphores: Vec<vk::Semaphore>,
+    fences: FrameFences,
+}
+
+impl FrameSync {
+    /// `SyncKind::Timeline` requires a device created with timeline semaphores enabled.
+    pub fn new(
+        instance: &ash::Instance,
+        device: &ash::Device,
+        frame_count: usize,
+        kind: SyncKind,
+    ) -> FrameSync {
+        let semaphore_create_info = vk::SemaphoreCreateInfo {
+            s_type: vk::StructureType::SEMAPHORE_CREATE_INFO,
+            p_next: ptr::null(),
+            flags: vk::SemaphoreCreateFlags::empty(),
+        };
+        let create_semaphores = || -> Vec<vk::Semaphore> {
+            (0..frame_count)
+                .map(|_| unsafe {
+                    device
+                        .create_semaphore(&semaphore_create_info, None)
+                        .expect("Failed to create Semaphore Object!")
+                })
+                .collect()
+        };
+
+        let fences = match kind {
+            SyncKind::Binary => {
+                let fence_create_info = vk::FenceCreateInfo {
+                    s_type: vk::StructureType::FENCE_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::FenceCreateFlags::SIGNALED,
+                };
+
+                FrameFences::Binary(
+                    (0..frame_count)
+                        .map(|_| unsafe {
+                            device
+                                .create_fence(&fence_create_info, None)
+                                .expect("Failed to create Fence Object!")
+                        })
+                        .collect(),
+                )
+            }
+            SyncKind::Timeline => FrameFences::Timeline {
+                timeline: QueueTimeline::new(instance, device),
+                frame_values: vec![0; frame_count],
+            },
+        };
+
+        FrameSync {
+            image_available_semaphores: create_semaphores(),
+            render_finished_semaphores: create_semaphores(),
+            fences,
+        }
+    }
+
+    pub fn kind(&self) -> SyncKind {
+        match self.fences {
+            FrameFences::Binary(_) => SyncKind::Binary,
+            FrameFences::Timeline { .. } => SyncKind::Timeline,
+        }
+    }
+
+    pub fn image_available_semaphore(&self, frame: usize) -> vk::Semaphore {
+        self.image_available_semaphores[frame]
+    }
+
+    pub fn render_finished_semaphore(&self, frame: usize) -> vk::Semaphore {
+        self.render_finished_semaphores[frame]
+    }
+
+    /// Block until the GPU is done with the last submission of `frame`.
+    pub fn wait_for_frame(&self, device: &ash::Device, frame: usize) {
+        match &self.fences {
+            FrameFences::Binary(fences) => unsafe {
+                device
+                    .wait_for_fences(&fences[frame..frame + 1], true, u64::MAX)
+                    .expect("Failed to wait for Fence!");
+            },
+            FrameFences::Timeline {
+                timeline,
+                frame_values,
+            } => timeline.wait(device, frame_values[frame]),
+        }
+    }
+
+    /// Submit the command buffer of `frame`, once the swapchain image is available, signaling
+    /// the semaphore presentation waits on.
+    pub fn submit_frame(
+        &mut self,
+        device: &ash::Device,
+        queue: vk::Queue,
+        frame: usize,
+        command_buffer: vk::CommandBuffer,
+    ) {
+        let wait_semaphores = [(
+            self.image_available_semaphores[frame],
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        )];
+        let signal_semaphores = [self.render_finished_semaphores[frame]];
+
+        match &mut self.fences {
+            FrameFences::Binary(fences) => {
+                submit_with_fence(
+                    device,
+                    queue,
+                    command_buffer,
+                    &wait_semaphores,
+                    &signal_semaphores,
+                    fences[frame],
+                );
+            }
+            FrameFences::Timeline {
+                timeline,
+                frame_values,
+            } => {
+                frame_values[frame] = timeline.submit(
+                    device,
+                    queue,
+                    &[command_buffer],
+                    &wait_semaphores,
+                    &signal_semaphores,
+                );
+            }
+        }
+    }
+
+    /// Submit a transfer command buffer. The binary path blocks until it is done, the timeline
+    /// path returns at once and `wait_for_upload` blocks later, if needed at all.
+    pub fn submit_upload(
+        &mut self,
+        device: &ash::Device,
+        queue: vk::Queue,
+        command_buffer: vk::CommandBuffer,
+    ) -> UploadTicket {
+        match &mut self.fences {
+            FrameFences::Binary(_) => {
+                let fence_create_info = vk::FenceCreateInfo {
+                    s_type: vk::StructureType::FENCE_CREATE_INFO,
+                    p_next: ptr::null(),
+                    flags: vk::FenceCreateFlags::empty(),
+                };
+
+                unsafe {
+                    let fence = device
+                        .create_fence(&fence_create_info, None)
+                        .expect("Failed to create Fence Object!");
+                    submit_with_fence(device, queue, command_buffer, &[], &[], fence);
+                    device
+                        .wait_for_fences(&[fence], true, u64::MAX)
+                        .expect("Failed to wait for Fence!");
+                    device.destroy_fence(fence, None);
+                }
+
+                UploadTicket(0)
+            }
+            FrameFences::Timeline { timeline, .. } => {
+                UploadTicket(timeline.submit(device, queue, &[command_buffer], &[], &[]))
+            }
+        }
+    }
+
+    pub fn is_upload_complete(&self, device: &ash::Device, ticket: UploadTicket) -> bool {
+        match &self.fences {
+            FrameFences::Binary(_) => true,
+            FrameFences::Timeline { timeline, .. } => timeline.completed_value(device) >= ticket.0,
+        }
+    }
+
+    pub fn wait_for_upload(&self, device: &ash::Device, ticket: UploadTicket) {
+        if let FrameFences::Timeline { timeline, .. } = &self.fences {
+            timeline.wait(device, ticket.0);
+        }
+    }
+
+    /// The device must be idle.
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            for (&image_available, &render_finished) in self
+                .image_available_semaphores
+                .iter()
+                .zip(self.render_finished_semaphores.iter())
+            {
+                device.destroy_semaphore(image_available, None);
+                device.destroy_semaphore(render_finished, None);
+            }
+
+            match &self.fences {
+                FrameFences::Binary(fences) => {
+                    for &fence in fences.iter() {
+                        device.destroy_fence(fence, None);
+                    }
+                }
+                FrameFences::Timeline { timeline, .. } => timeline.destroy(device),
+            }
+        }
+    }
+}
+
+fn submit_with_fence(
+    device: &ash::Device,
+    queue: vk::Queue,
+    command_buffer: vk::CommandBuffer,
+    wait_semaphores: &[(vk::Semaphore, vk::PipelineStageFlags)],
+    signal_semaphores: &[vk::Semaphore],
+    fence: vk::Fence,
+) {
+    let (wait_semaphore_handles, wait_stages): (Vec<vk::Semaphore>, Vec<vk::PipelineStageFlags>) =
+        wait_semaphores.iter().cloned().unzip();
+
+    let submit_infos = [vk::SubmitInfo {
+        s_type: vk::StructureType::SUBMIT_INFO,
+        p_next: ptr::null(),
+        wait_semaphore_count: wait_semaphore_handles.len() as u32,
+        p_wait_semaphores: wait_semaphore_handles.as_ptr(),
+        p_wait_dst_stage_mask: wait_stages.as_ptr(),
+        command_buffer_count: 1,
+        p_command_buffers: &command_buffer,
+        signal_semaphore_count: signal_semaphores.len() as u32,
+        p_signal_semaphores: signal_semaphores.as_ptr(),
+    }];
+
+    unsafe {
+        device
+            .reset_fences(&[fence])
+            .expect("Failed to reset Fence!");
+        device
+            .queue_submit(queue, &submit_infos, fence)
+            .expect("Failed to execute queue submit.");
+    }
+}
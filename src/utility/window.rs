@@ -1,6 +1,19 @@
 
-use winit::event::{Event, VirtualKeyCode, ElementState, KeyboardInput, WindowEvent};
+use winit::dpi::PhysicalPosition;
+use winit::event::{Event, VirtualKeyCode, ElementState, WindowEvent};
+use winit::event::{MouseButton, MouseScrollDelta};
 use winit::event_loop::{EventLoop, ControlFlow};
+use winit::window::WindowId;
+
+use std::path::PathBuf;
+
+use super::clock::{Clock, FixedStepClock, RealTimeClock};
+use super::display::{DisplayMode, DisplaySettings};
+use super::fixed_timestep::FixedTimestep;
+use super::fps_limiter::FPSLimiter;
+use super::input::{self, ActionMap};
+use super::input_replay::{InputEvent, InputPlayback, InputRecorder};
+use super::share;
 
 
 const IS_PAINT_FPS_COUNTER: bool = true;
@@ -19,80 +32,577 @@ pub fn init_window(
 }
 
 pub trait VulkanApp {
-    fn draw_frame(&mut self, delta_time: f32);
+    /// Advance the simulation by `fixed_dt` seconds. `main_loop` calls this at a fixed rate,
+    /// zero or more times per frame.
+    fn update(&mut self, _fixed_dt: f32) {}
+    /// Draw a frame, `alpha` is how far the time is between the last two updates, from 0 to 1.
+    fn render(&mut self, alpha: f32);
     fn recreate_swapchain(&mut self);
     fn cleanup_swapchain(&self);
     fn wait_device_idle(&self);
     fn resize_framebuffer(&mut self);
     fn window_ref(&self) -> &winit::window::Window;
+
+    /// Called once before the first frame.
+    fn on_start(&mut self) {}
+    /// Called once when the program quits, after the device is idle.
+    fn on_exit(&mut self) {}
+
+    /// Register the actions of the app with their default bindings, before the input config
+    /// is loaded so that it can rebind them.
+    fn register_actions(&mut self, _actions: &mut ActionMap) {}
+    /// Called once per frame before the updates, with the actions pressed, held and released
+    /// during the frame.
+    fn on_input(&mut self, _actions: &ActionMap) {}
+
+    /// Called for every key press, the `quit` action is handled by the driver.
+    fn on_key_pressed(&mut self, _key_code: VirtualKeyCode) {}
+    fn on_key_released(&mut self, _key_code: VirtualKeyCode) {}
+    /// The cursor moved to `position`, in pixels from the top-left corner of the window.
+    fn on_cursor_moved(&mut self, _position: PhysicalPosition<i32>) {}
+    /// Raw mouse motion, not limited by the window or the screen. Camera controls should use
+    /// this rather than the cursor position.
+    fn on_mouse_motion(&mut self, _delta: (f64, f64)) {}
+    fn on_mouse_button(&mut self, _button: MouseButton, _state: ElementState) {}
+    fn on_mouse_wheel(&mut self, _delta: MouseScrollDelta) {}
+    fn on_focus_changed(&mut self, _is_focused: bool) {}
+    /// The window moved to a monitor with a different DPI, or the DPI setting changed.
+    fn on_scale_factor_changed(&mut self, _scale_factor: f64) {}
+    fn on_file_dropped(&mut self, _path: PathBuf) {}
+
+    /// Whether `window_id` is one of the windows of the app. Apps with more than one window
+    /// override this, `window_ref` stays the main window that quits the app when closed.
+    fn has_window(&self, window_id: WindowId) -> bool {
+        window_id == self.window_ref().id()
+    }
+    /// A window other than the main one changed size, or moved to a monitor with another scale.
+    fn resize_window(&mut self, _window_id: WindowId) {}
+    /// A window other than the main one asked to close.
+    fn close_window(&mut self, _window_id: WindowId) {}
+    /// Whether no window of the app has an area to draw to. The driver stops rendering until one
+    /// of them is restored, apps with more than one window override this.
+    fn is_minimized(&self) -> bool {
+        share::is_window_minimized(self.window_ref())
+    }
+}
+
+/// The settings of the frame loop that `AppDriver` runs.
+pub struct DriverSettings {
+    /// Limit the frame rate, `None` to render as fast as presentation allows.
+    pub prefer_fps: Option<f32>,
+    /// The number of frames the frame time statistics are computed over.
+    pub stats_window: usize,
+    /// Simulation updates per second.
+    pub update_rate: f32,
+    /// The most updates run in a single frame when catching up after a slow frame.
+    pub max_update_steps: usize,
+    /// The clock the simulation updates follow.
+    pub clock: Box<dyn Clock>,
+    /// Exit after rendering this many frames, `None` to run until the window is closed.
+    pub frame_limit: Option<u64>,
+    /// The config file rebinding the input actions, see `utility::input`.
+    pub input_config: Option<PathBuf>,
+    /// Record the input events and frame times to this file, see `utility::input_replay`.
+    pub record_input: Option<PathBuf>,
+    /// Replay the input events and frame times of this recording instead of the live input.
+    pub replay_input: Option<PathBuf>,
+    /// The display mode to start in, and the fullscreen mode of the `toggle_fullscreen` action.
+    pub display: DisplaySettings,
+}
+
+impl Default for DriverSettings {
+    fn default() -> DriverSettings {
+        DriverSettings {
+            prefer_fps: None,
+            stats_window: super::fps_limiter::DEFAULT_STATS_WINDOW,
+            update_rate: super::fixed_timestep::DEFAULT_UPDATE_RATE,
+            max_update_steps: super::fixed_timestep::DEFAULT_MAX_UPDATE_STEPS,
+            clock: Box::new(RealTimeClock::new()),
+            frame_limit: None,
+            input_config: None,
+            record_input: None,
+            replay_input: None,
+            display: DisplaySettings::default(),
+        }
+    }
+}
+
+impl DriverSettings {
+    /// Apply the options of the command line:
+    ///
+    /// * `--frames <count>`: exit after rendering `count` frames.
+    /// * `--fixed-step <seconds>`: advance the clock by `seconds` every frame instead of
+    ///   following real time.
+    /// * `--input-config <path>`: rebind the input actions from the config file at `path`.
+    /// * `--record-input <path>`: record the input and the frame times of the session to `path`.
+    /// * `--replay-input <path>`: replay the input and the frame times recorded in `path`, then
+    ///   exit.
+    /// * `--display <windowed|borderless|exclusive>`: the display mode to start in.
+    /// * `--monitor <index>`: the monitor to go fullscreen on, the primary one by default.
+    /// * `--video-mode <width>x<height>[@<hz>]`: the video mode of exclusive fullscreen, the
+    ///   native resolution of the monitor by default.
+    ///
+    /// The first two together render the same frames on every run, which is what golden image tests
+    /// need. A replay does the same with the input of a real session.
+    pub fn parse_command_line<I: Iterator<Item = String>>(&mut self, mut args: I) {
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--frames" => {
+                    let frame_count = args.next()
+                        .and_then(|value| value.parse().ok())
+                        .expect("--frames expects the number of frames to render.");
+                    self.frame_limit = Some(frame_count);
+                },
+                "--fixed-step" => {
+                    let step = args.next()
+                        .and_then(|value| value.parse().ok())
+                        .expect("--fixed-step expects the clock step in seconds.");
+                    self.clock = Box::new(FixedStepClock::new(step));
+                },
+                "--input-config" => {
+                    let path = args.next()
+                        .expect("--input-config expects the path of the config file.");
+                    self.input_config = Some(PathBuf::from(path));
+                },
+                "--record-input" => {
+                    let path = args.next()
+                        .expect("--record-input expects the path of the recording to write.");
+                    self.record_input = Some(PathBuf::from(path));
+                },
+                "--replay-input" => {
+                    let path = args.next()
+                        .expect("--replay-input expects the path of the recording to replay.");
+                    self.replay_input = Some(PathBuf::from(path));
+                },
+                "--display" => {
+                    self.display.mode = args.next()
+                        .and_then(|value| value.parse().ok())
+                        .expect("--display expects windowed, borderless or exclusive.");
+                },
+                "--monitor" => {
+                    let monitor_index = args.next()
+                        .and_then(|value| value.parse().ok())
+                        .expect("--monitor expects the index of the monitor.");
+                    self.display.monitor = Some(monitor_index);
+                },
+                "--video-mode" => {
+                    let video_mode = args.next()
+                        .and_then(|value| value.parse().ok())
+                        .expect("--video-mode expects <width>x<height> or <width>x<height>@<hz>.");
+                    self.display.video_mode = Some(video_mode);
+                },
+                _ => println!("Ignoring unknown command line argument: {}", arg),
+            }
+        }
+    }
+}
+
+/// Runs a `VulkanApp` from winit events, without owning the event loop.
+///
+/// An application with its own event loop passes every event to `handle_event`, calls `render`
+/// whenever it wants a frame, and `shutdown` before exiting. `ProgramProc` does exactly this.
+pub struct AppDriver<A: VulkanApp> {
+    app: A,
+    actions: ActionMap,
+    tick_counter: FPSLimiter,
+    fixed_timestep: FixedTimestep,
+    clock: Box<dyn Clock>,
+    frame_limit: Option<u64>,
+    frame_count: u64,
+    recorder: Option<InputRecorder>,
+    playback: Option<InputPlayback>,
+    display: DisplaySettings,
+    is_minimized: bool,
+    is_exit_requested: bool,
+    is_shut_down: bool,
+}
+
+impl<A: VulkanApp> AppDriver<A> {
+
+    /// Take over `app`, set up its input actions and call its `on_start` hook.
+    pub fn new(mut app: A, settings: DriverSettings) -> AppDriver<A> {
+        let mut tick_counter = FPSLimiter::new();
+        tick_counter.set_prefer_fps(settings.prefer_fps);
+        tick_counter.set_stats_window(settings.stats_window);
+
+        let mut actions = ActionMap::new();
+        app.register_actions(&mut actions);
+        if let Some(input_config) = settings.input_config {
+            actions.load_config(&input_config).unwrap_or_else(|err| {
+                panic!("Failed to load input config {}: {}", input_config.display(), err)
+            });
+        }
+
+        let recorder = settings.record_input.map(|path| {
+            InputRecorder::create(&path).unwrap_or_else(|err| {
+                panic!("Failed to create input recording {}: {}", path.display(), err)
+            })
+        });
+        let playback = settings.replay_input.map(|path| {
+            InputPlayback::load(&path).unwrap_or_else(|err| {
+                panic!("Failed to load input recording {}: {}", path.display(), err)
+            })
+        });
+
+        if settings.display.mode != DisplayMode::Windowed {
+            settings.display.apply(app.window_ref(), settings.display.mode);
+            app.resize_framebuffer();
+        }
+
+        app.on_start();
+
+        AppDriver {
+            app,
+            actions,
+            tick_counter,
+            fixed_timestep: FixedTimestep::new(settings.update_rate, settings.max_update_steps),
+            clock: settings.clock,
+            frame_limit: settings.frame_limit,
+            frame_count: 0,
+            recorder,
+            playback,
+            display: settings.display,
+            is_minimized: false,
+            is_exit_requested: false,
+            is_shut_down: false,
+        }
+    }
+
+    pub fn app(&self) -> &A {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut A {
+        &mut self.app
+    }
+
+    pub fn actions(&self) -> &ActionMap {
+        &self.actions
+    }
+
+    /// Whether the app asked to quit, by closing its window, the `quit` action or reaching the
+    /// frame limit. The owner of the event loop decides when to actually stop.
+    pub fn is_exit_requested(&self) -> bool {
+        self.is_exit_requested
+    }
+
+    /// Whether every window of the app is minimized. Nothing is rendered, and no time is
+    /// simulated, until one of them is restored.
+    pub fn is_minimized(&self) -> bool {
+        self.is_minimized
+    }
+
+    /// Forward an event to the hooks of the app. Events of windows the app does not own are
+    /// ignored, and so are the redraw events: frames are only drawn by `render`. While replaying
+    /// a recording, the live input is ignored too.
+    pub fn handle_event<T>(&mut self, event: &Event<T>) {
+        let vulkan_app = &mut self.app;
+        let app_window_id = vulkan_app.window_ref().id();
+
+        match event {
+            | Event::WindowEvent { window_id, event } if *window_id == app_window_id => {
+                match event {
+                    | WindowEvent::CloseRequested => {
+                        self.is_exit_requested = true;
+                    },
+                    | WindowEvent::Resized(new_size) => {
+                        // the size is in physical pixels, which is what the swapchain follows.
+                        let is_minimized = new_size.width == 0 || new_size.height == 0;
+
+                        // no swapchain can be created with a zero size, wait for the restore.
+                        if !is_minimized {
+                            vulkan_app.wait_device_idle();
+                            vulkan_app.resize_framebuffer();
+                        }
+                    },
+                    | WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                        // the physical size changes with the scale factor, and not every
+                        // platform follows with a resize event.
+                        vulkan_app.wait_device_idle();
+                        vulkan_app.resize_framebuffer();
+                        vulkan_app.on_scale_factor_changed(*scale_factor);
+                    },
+                    | WindowEvent::DroppedFile(path) => {
+                        vulkan_app.on_file_dropped(path.clone());
+                    },
+                    | _ => {},
+                }
+            },
+            | Event::WindowEvent { window_id, event } if vulkan_app.has_window(*window_id) => {
+                match event {
+                    | WindowEvent::CloseRequested => {
+                        vulkan_app.close_window(*window_id);
+                    },
+                    | WindowEvent::Resized(_) | WindowEvent::ScaleFactorChanged { .. } => {
+                        vulkan_app.resize_window(*window_id);
+                    },
+                    | _ => {},
+                }
+            },
+            | _ => {},
+        }
+
+        // the input of every window of the app drives the same actions.
+        let input_window_id = match event {
+            | Event::WindowEvent { window_id, .. } if vulkan_app.has_window(*window_id) => *window_id,
+            | _ => app_window_id,
+        };
+
+        if let Event::WindowEvent {
+            event: WindowEvent::Resized(_) | WindowEvent::CloseRequested,
+            ..
+        } = event {
+            self.update_minimized();
+        }
+
+        if self.playback.is_some() {
+            return;
+        }
+        if let Some(input_event) = InputEvent::from_event(event, input_window_id) {
+            if let Some(recorder) = &mut self.recorder {
+                recorder.record_event(&input_event)
+                    .expect("Failed to write the input recording!");
+            }
+            self.dispatch_input(input_event);
+        }
+    }
+
+    /// Pause once every window of the app is minimized, resume once one of them is restored.
+    fn update_minimized(&mut self) {
+        let is_minimized = self.app.is_minimized();
+        if self.is_minimized && !is_minimized {
+            // the time spent minimized is not simulated.
+            self.clock.tick();
+        }
+        self.is_minimized = is_minimized;
+    }
+
+    fn dispatch_input(&mut self, input_event: InputEvent) {
+        let vulkan_app = &mut self.app;
+
+        match input_event {
+            | InputEvent::Key(key_code, state) => {
+                self.actions.handle_key(key_code, state);
+                if self.actions.is_pressed(input::QUIT) {
+                    self.is_exit_requested = true;
+                }
+
+                match state {
+                    | ElementState::Pressed => vulkan_app.on_key_pressed(key_code),
+                    | ElementState::Released => vulkan_app.on_key_released(key_code),
+                }
+            },
+            | InputEvent::MouseButton(button, state) => {
+                self.actions.handle_mouse_button(button, state);
+                if self.actions.is_pressed(input::QUIT) {
+                    self.is_exit_requested = true;
+                }
+                vulkan_app.on_mouse_button(button, state);
+            },
+            | InputEvent::CursorMoved(position) => {
+                vulkan_app.on_cursor_moved(position);
+            },
+            | InputEvent::MouseMotion(delta) => {
+                vulkan_app.on_mouse_motion(delta);
+            },
+            | InputEvent::MouseWheel(delta) => {
+                vulkan_app.on_mouse_wheel(delta);
+            },
+            | InputEvent::Modifiers(modifiers) => {
+                self.actions.set_modifiers(modifiers);
+            },
+            | InputEvent::Focused(is_focused) => {
+                if !is_focused {
+                    self.actions.release_all();
+                }
+                vulkan_app.on_focus_changed(is_focused);
+            },
+        }
+    }
+
+    /// Run the simulation updates that are due and draw a frame. While every window is minimized
+    /// or once the frame limit is reached, this does nothing.
+    pub fn render(&mut self) {
+        let frame_count = self.frame_count;
+        let is_frame_limit_reached =
+            self.frame_limit.is_some_and(|frame_limit| frame_count >= frame_limit);
+        if self.is_shut_down || self.is_minimized || is_frame_limit_reached {
+            return;
+        }
+
+        let delta_time = if let Some(playback) = &mut self.playback {
+            let recorded_frame = match playback.next_frame() {
+                | Some(recorded_frame) => recorded_frame,
+                | None => {
+                    println!("Input replay finished after {} frames.", self.frame_count);
+                    self.is_exit_requested = true;
+                    return;
+                },
+            };
+            for input_event in recorded_frame.events {
+                self.dispatch_input(input_event);
+            }
+            recorded_frame.delta_time
+        } else {
+            self.clock.tick()
+        };
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record_frame(delta_time)
+                .expect("Failed to write the input recording!");
+        }
+
+        if self.actions.is_pressed(input::TOGGLE_FULLSCREEN) {
+            self.toggle_fullscreen();
+        }
+        self.app.on_input(&self.actions);
+
+        let update_count = self.fixed_timestep.advance(delta_time);
+        for _ in 0..update_count {
+            self.app.update(self.fixed_timestep.step());
+        }
+        self.app.render(self.fixed_timestep.alpha());
+        self.actions.end_frame();
+
+        self.tick_counter.keep_fps();
+        self.tick_counter.tick_frame();
+
+        if IS_PAINT_FPS_COUNTER {
+            print!("FPS: {:.1}\r", self.tick_counter.fps());
+        }
+
+        self.frame_count += 1;
+        if self.frame_limit == Some(self.frame_count) {
+            self.is_exit_requested = true;
+        }
+    }
+
+    /// Switch between a window and the fullscreen mode of the display settings. The swapchain
+    /// is recreated on the next frame, as the surface changes size.
+    pub fn toggle_fullscreen(&mut self) {
+        let window = self.app.window_ref();
+        let window_size = window.inner_size();
+        if window_size.width == 0 || window_size.height == 0 {
+            // a minimized window has nothing to show fullscreen, leave it as it is.
+            return;
+        }
+
+        let mode = if window.fullscreen().is_some() {
+            DisplayMode::Windowed
+        } else {
+            self.display.fullscreen_mode()
+        };
+        self.display.apply(window, mode);
+
+        // not every platform sends a resize event when the fullscreen state changes.
+        self.app.wait_device_idle();
+        self.app.resize_framebuffer();
+    }
+
+    /// Wait for the device and call the `on_exit` hook of the app. The app itself is destroyed
+    /// with the driver. Calling this more than once has no effect.
+    pub fn shutdown(&mut self) {
+        if self.is_shut_down {
+            return;
+        }
+        self.is_shut_down = true;
+
+        self.app.wait_device_idle();
+        self.app.on_exit();
+
+        if let Some(stats) = self.tick_counter.stats() {
+            println!("\n{}", stats);
+        }
+    }
 }
 
+/// Owns the winit event loop and runs a single `AppDriver` on it until the app quits.
 pub struct ProgramProc {
     pub event_loop: EventLoop<()>,
+    settings: DriverSettings,
 }
 
 impl ProgramProc {
 
+    /// Create the event loop, applying the options of the command line, see
+    /// `DriverSettings::parse_command_line`.
     pub fn new() -> ProgramProc {
         // init window stuff
         let event_loop = EventLoop::new();
 
-        ProgramProc { event_loop }
+        let mut settings = DriverSettings::default();
+        settings.parse_command_line(std::env::args().skip(1));
+
+        ProgramProc { event_loop, settings }
+    }
+
+    /// Replace the clock the simulation updates follow, real time by default.
+    pub fn set_clock<C: 'static + Clock>(&mut self, clock: C) {
+        self.settings.clock = Box::new(clock);
+    }
+
+    /// Exit after rendering `frame_limit` frames, `None` to run until the window is closed.
+    pub fn set_frame_limit(&mut self, frame_limit: Option<u64>) {
+        self.settings.frame_limit = frame_limit;
+    }
+
+    /// Limit the frame rate of the main loop, `None` to render as fast as presentation allows.
+    /// A frame rate that is not a positive finite number is the same as `None`.
+    pub fn set_prefer_fps(&mut self, prefer_fps: Option<f32>) {
+        self.settings.prefer_fps = prefer_fps;
+    }
+
+    /// The number of frames the frame time statistics are computed over.
+    pub fn set_stats_window(&mut self, frame_count: usize) {
+        self.settings.stats_window = frame_count;
+    }
+
+    /// Run `update_rate` simulation updates per second, and at most `max_update_steps` of
+    /// them in a single frame when catching up after a slow frame.
+    pub fn set_update_rate(&mut self, update_rate: f32, max_update_steps: usize) {
+        self.settings.update_rate = update_rate;
+        self.settings.max_update_steps = max_update_steps;
     }
 
-    pub fn main_loop<A: 'static + VulkanApp>(self, mut vulkan_app: A) {
+    pub fn main_loop<A: 'static + VulkanApp>(self, vulkan_app: A) {
 
-        let mut tick_counter = super::fps_limiter::FPSLimiter::new();
+        let mut driver = AppDriver::new(vulkan_app, self.settings);
 
         self.event_loop.run(move |event, _, control_flow| {
 
             match event {
-                | Event::WindowEvent { event, .. } => {
-                    match event {
-                        | WindowEvent::CloseRequested => {
-                            vulkan_app.wait_device_idle();
-                            *control_flow = ControlFlow::Exit
-                        },
-                        | WindowEvent::KeyboardInput { input, .. } => {
-                            match input {
-                                | KeyboardInput { virtual_keycode, state, .. } => {
-                                    match (virtual_keycode, state) {
-                                        | (Some(VirtualKeyCode::Escape), ElementState::Pressed) => {
-                                            vulkan_app.wait_device_idle();
-                                            *control_flow = ControlFlow::Exit
-                                        },
-                                        | _ => {},
-                                    }
-                                },
-                            }
-                        },
-                        | WindowEvent::Resized(_new_size) => {
-                            vulkan_app.wait_device_idle();
-                            vulkan_app.resize_framebuffer();
-                        },
-                        | _ => {},
-                    }
-                },
                 | Event::MainEventsCleared => {
-                    vulkan_app.window_ref().request_redraw();
+                    if driver.is_minimized() {
+                        // nothing to draw to, sleep until the next event instead.
+                        *control_flow = ControlFlow::Wait;
+                    } else {
+                        *control_flow = ControlFlow::Poll;
+                        driver.app().window_ref().request_redraw();
+                    }
                 },
-                | Event::RedrawRequested(_window_id) => {
-                    let delta_time = tick_counter.delta_time();
-                    vulkan_app.draw_frame(delta_time);
-
-                    if IS_PAINT_FPS_COUNTER {
-                        print!("FPS: {}\r", tick_counter.fps());
+                | Event::RedrawRequested(window_id) => {
+                    // frames are paced by the main window, a redraw of any other window would
+                    // add an extra frame.
+                    if window_id == driver.app().window_ref().id() {
+                        driver.render();
                     }
-
-                    tick_counter.tick_frame();
                 },
                 | Event::LoopDestroyed => {
-                    vulkan_app.wait_device_idle();
+                    driver.shutdown();
                 },
-                _ => (),
+                | _ => driver.handle_event(&event),
+            }
+
+            if driver.is_exit_requested() {
+                *control_flow = ControlFlow::Exit
             }
 
         })
     }
 
 }
+
+impl Default for ProgramProc {
+    fn default() -> ProgramProc {
+        ProgramProc::new()
+    }
+}
@@ -0,0 +1,272 @@
+//! A window together with its own surface, swapchain and frame synchronization.
+//!
+//! Apps presenting to several windows from one device keep one `WindowTarget` per window. Each
+//! target acquires, submits and presents on its own, with its own frames in flight, so a slow or
+//! minimized window does not hold back the others. The render pass, framebuffers and command
+//! buffers that draw into a target depend on what the app renders and stay with the app.
+
+use ash::version::DeviceV1_0;
+use ash::vk;
+use winit::window::{Window, WindowId};
+
+use std::ptr;
+
+use crate::utility::constants::MAX_FRAMES_IN_FLIGHT;
+use crate::utility::share;
+use crate::utility::structures::{QueueFamilyIndices, SurfaceStuff, SwapChainStuff, SyncObjects};
+
+pub struct WindowTarget {
+    pub window: Window,
+    pub surface_stuff: SurfaceStuff,
+    pub swapchain_stuff: SwapChainStuff,
+    pub image_views: Vec<vk::ImageView>,
+    sync_objects: SyncObjects,
+    current_frame: usize,
+    is_resized: bool,
+}
+
+impl WindowTarget {
+    /// Create the surface and the swapchain of `window`.
+    pub fn new(
+        entry: &ash::Entry,
+        instance: &ash::Instance,
+        device: &ash::Device,
+        physical_device: vk::PhysicalDevice,
+        queue_family: &QueueFamilyIndices,
+        window: Window,
+    ) -> WindowTarget {
+        let surface_stuff = share::create_surface(entry, instance, &window);
+
+        WindowTarget::from_surface(
+            instance,
+            device,
+            physical_device,
+            queue_family,
+            window,
+            surface_stuff,
+        )
+    }
+
+    /// Create the swapchain of `window` on its existing surface, for the window the device was
+    /// picked with. The present queue of `queue_family` must be able to present to the surface,
+    /// which is checked here as the device may have been picked for another window.
+    pub fn from_surface(
+        instance: &ash::Instance,
+        device: &ash::Device,
+        physical_device: vk::PhysicalDevice,
+        queue_family: &QueueFamilyIndices,
+        window: Window,
+        surface_stuff: SurfaceStuff,
+    ) -> WindowTarget {
+        if !share::is_present_supported(
+            physical_device,
+            queue_family.present_family.unwrap(),
+            &surface_stuff,
+        ) {
+            panic!("The present queue can not present to the window!");
+        }
+
+        let swapchain_stuff = share::create_swapchain(
+            instance,
+            device,
+            physical_device,
+            &window,
+            &surface_stuff,
+            queue_family,
+        );
+        let image_views = share::v1::create_image_views(
+            device,
+            swapchain_stuff.swapchain_format,
+            &swapchain_stuff.swapchain_images,
+        );
+        let sync_objects = share::v1::create_sync_objects(device, MAX_FRAMES_IN_FLIGHT);
+
+        WindowTarget {
+            window,
+            surface_stuff,
+            swapchain_stuff,
+            image_views,
+            sync_objects,
+            current_frame: 0,
+            is_resized: false,
+        }
+    }
+
+    pub fn id(&self) -> WindowId {
+        self.window.id()
+    }
+
+    pub fn format(&self) -> vk::Format {
+        self.swapchain_stuff.swapchain_format
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.swapchain_stuff.swapchain_extent
+    }
+
+    /// Whether the window has no area to present to. No swapchain can be created until it is
+    /// restored.
+    pub fn is_minimized(&self) -> bool {
+        share::is_window_minimized(&self.window)
+    }
+
+    /// Recreate the swapchain after the next present, for a resize the surface may not report.
+    pub fn mark_resized(&mut self) {
+        self.is_resized = true;
+    }
+
+    /// Wait until the current frame in flight is done with, then acquire a swapchain image.
+    /// `None` means the swapchain is out of date and has to be recreated before drawing.
+    pub fn acquire_next_image(&mut self, device: &ash::Device) -> Option<u32> {
+        let wait_fences = [self.sync_objects.inflight_fences[self.current_frame]];
+
+        unsafe {
+            device
+                .wait_for_fences(&wait_fences, true, u64::MAX)
+                .expect("Failed to wait for Fence!");
+        }
+
+        let result = unsafe {
+            self.swapchain_stuff.swapchain_loader.acquire_next_image(
+                self.swapchain_stuff.swapchain,
+                u64::MAX,
+                self.sync_objects.image_available_semaphores[self.current_frame],
+                vk::Fence::null(),
+            )
+        };
+        match result {
+            Ok((image_index, _is_sub_optimal)) => Some(image_index),
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) => None,
+            Err(_) => panic!("Failed to acquire Swap Chain Image!"),
+        }
+    }
+
+    /// Submit `command_buffer`, which draws into the image at `image_index`, and present the
+    /// image. Returns whether the swapchain has to be recreated before the next frame.
+    pub fn submit_and_present(
+        &mut self,
+        device: &ash::Device,
+        graphics_queue: vk::Queue,
+        present_queue: vk::Queue,
+        command_buffer: vk::CommandBuffer,
+        image_index: u32,
+    ) -> bool {
+        let wait_fences = [self.sync_objects.inflight_fences[self.current_frame]];
+        let wait_semaphores = [self.sync_objects.image_available_semaphores[self.current_frame]];
+        let wait_stages = [vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
+        let signal_semaphores = [self.sync_objects.render_finished_semaphores[self.current_frame]];
+
+        let submit_infos = [vk::SubmitInfo {
+            s_type: vk::StructureType::SUBMIT_INFO,
+            p_next: ptr::null(),
+            wait_semaphore_count: wait_semaphores.len() as u32,
+            p_wait_semaphores: wait_semaphores.as_ptr(),
+            p_wait_dst_stage_mask: wait_stages.as_ptr(),
+            command_buffer_count: 1,
+            p_command_buffers: &command_buffer,
+            signal_semaphore_count: signal_semaphores.len() as u32,
+            p_signal_semaphores: signal_semaphores.as_ptr(),
+        }];
+
+        unsafe {
+            device
+                .reset_fences(&wait_fences)
+                .expect("Failed to reset Fence!");
+
+            device
+                .queue_submit(graphics_queue, &submit_infos, wait_fences[0])
+                .expect("Failed to execute queue submit.");
+        }
+
+        let swapchains = [self.swapchain_stuff.swapchain];
+
+        let present_info = vk::PresentInfoKHR {
+            s_type: vk::StructureType::PRESENT_INFO_KHR,
+            p_next: ptr::null(),
+            wait_semaphore_count: 1,
+            p_wait_semaphores: signal_semaphores.as_ptr(),
+            swapchain_count: 1,
+            p_swapchains: swapchains.as_ptr(),
+            p_image_indices: &image_index,
+            p_results: ptr::null_mut(),
+        };
+
+        let result = unsafe {
+            self.swapchain_stuff
+                .swapchain_loader
+                .queue_present(present_queue, &present_info)
+        };
+        self.current_frame = (self.current_frame + 1) % MAX_FRAMES_IN_FLIGHT;
+
+        match result {
+            Ok(_) => self.is_resized,
+            Err(vk::Result::ERROR_OUT_OF_DATE_KHR) | Err(vk::Result::SUBOPTIMAL_KHR) => true,
+            Err(_) => panic!("Failed to execute queue present."),
+        }
+    }
+
+    /// Recreate the swapchain and its image views at the current size of the window. The app
+    /// destroys whatever it created from the old image views first.
+    pub fn recreate_swapchain(
+        &mut self,
+        instance: &ash::Instance,
+        device: &ash::Device,
+        physical_device: vk::PhysicalDevice,
+        queue_family: &QueueFamilyIndices,
+    ) {
+        unsafe {
+            device
+                .device_wait_idle()
+                .expect("Failed to wait device idle!")
+        };
+        self.destroy_swapchain(device);
+
+        let window_size = self.window.inner_size();
+        self.surface_stuff.screen_width = window_size.width;
+        self.surface_stuff.screen_height = window_size.height;
+
+        self.swapchain_stuff = share::create_swapchain(
+            instance,
+            device,
+            physical_device,
+            &self.window,
+            &self.surface_stuff,
+            queue_family,
+        );
+        self.image_views = share::v1::create_image_views(
+            device,
+            self.swapchain_stuff.swapchain_format,
+            &self.swapchain_stuff.swapchain_images,
+        );
+        self.is_resized = false;
+    }
+
+    fn destroy_swapchain(&self, device: &ash::Device) {
+        unsafe {
+            for &image_view in self.image_views.iter() {
+                device.destroy_image_view(image_view, None);
+            }
+            self.swapchain_stuff
+                .swapchain_loader
+                .destroy_swapchain(self.swapchain_stuff.swapchain, None);
+        }
+    }
+
+    /// Destroy the swapchain, the sync objects and the surface. The device must be idle, and the
+    /// window closes when the target is dropped.
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            for i in 0..MAX_FRAMES_IN_FLIGHT {
+                device.destroy_semaphore(self.sync_objects.image_available_semaphores[i], None);
+                device.destroy_semaphore(self.sync_objects.render_finished_semaphores[i], None);
+                device.destroy_fence(self.sync_objects.inflight_fences[i], None);
+            }
+
+            self.destroy_swapchain(device);
+
+            self.surface_stuff
+                .surface_loader
+                .destroy_surface(self.surface_stuff.surface, None);
+        }
+    }
+}